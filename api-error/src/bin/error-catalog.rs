@@ -0,0 +1,9 @@
+//! Print the error catalog as markdown, e.g. to regenerate `CATALOG.md`:
+//!
+//! ```sh
+//! cargo run -p api-error --bin error-catalog > api-error/CATALOG.md
+//! ```
+
+fn main() {
+    print!("{}", api_error::catalog_markdown());
+}
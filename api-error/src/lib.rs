@@ -0,0 +1,351 @@
+#![warn(clippy::pedantic)]
+
+//! Errors returned by the platform's HTTP APIs.
+//!
+//! Every error carries a stable, machine-readable [`Code`] and is rendered as an RFC 7807 problem
+//! details document with the `application/problem+json` content type:
+//!
+//! ```json
+//! {
+//!   "type": "urn:scp:error:not_found",
+//!   "code": "not_found",
+//!   "title": "Not found",
+//!   "status": 404,
+//!   "detail": "Hint does not exist"
+//! }
+//! ```
+//!
+//! Internal server errors also carry a `reference` (e.g. `EC.SFCNS`) naming the place that failed,
+//! which is logged alongside the underlying error. The codes are listed in `CATALOG.md`, generated
+//! with `cargo run -p api-error --bin error-catalog`.
+
+use std::{borrow::Cow, fmt, fmt::Write};
+
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web,
+    HttpRequest,
+    HttpResponse,
+    ResponseError,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// The content type of problem details responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+macro_rules! codes {
+    ($(
+        $(#[doc = $doc:literal])*
+        $name:ident => ($code:literal, $status:ident, $title:literal),
+    )*) => {
+        /// A stable, machine-readable error code. Codes are never renamed or reused once released.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Code {
+            $($(#[doc = $doc])* $name,)*
+        }
+
+        impl Code {
+            /// Every code, in catalog order.
+            pub const ALL: &'static [Code] = &[$(Code::$name),*];
+
+            /// The code as it appears in responses.
+            #[must_use]
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Code::$name => $code,)*
+                }
+            }
+
+            /// The HTTP status returned with the code.
+            #[must_use]
+            pub fn status(self) -> StatusCode {
+                match self {
+                    $(Code::$name => StatusCode::$status,)*
+                }
+            }
+
+            /// A short, human-readable summary of the code.
+            #[must_use]
+            pub fn title(self) -> &'static str {
+                match self {
+                    $(Code::$name => $title,)*
+                }
+            }
+
+            /// A description of when the code is returned, taken from its doc comment.
+            #[must_use]
+            pub fn description(self) -> String {
+                let lines: &[&str] = match self {
+                    $(Code::$name => &[$($doc),*],)*
+                };
+                lines.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" ")
+            }
+        }
+    };
+}
+
+codes! {
+    /// The request did not include an `X-Scp-Auth` token.
+    Unauthenticated => ("unauthenticated", UNAUTHORIZED, "Missing authentication token"),
+    /// The authentication token could not be verified, or does not belong to a user.
+    InvalidToken => ("invalid_token", UNAUTHORIZED, "Invalid authentication token"),
+    /// The requester's roles do not grant the permission needed for the action.
+    Forbidden => ("forbidden", FORBIDDEN, "Forbidden"),
+    /// The request body, query or path was malformed or failed validation. The `detail` describes
+    /// the problem.
+    InvalidRequest => ("invalid_request", BAD_REQUEST, "Invalid request"),
+    /// The requested resource does not exist. The `detail` names the resource.
+    NotFound => ("not_found", NOT_FOUND, "Not found"),
+    /// The resource being created already exists, such as a team or service with the same name.
+    AlreadyExists => ("already_exists", BAD_REQUEST, "Already exists"),
+    /// Too many incorrect flags were submitted recently. The `Retry-After` header gives the number
+    /// of seconds to wait.
+    RateLimited => ("rate_limited", TOO_MANY_REQUESTS, "Too many attempts"),
+    /// An unexpected error occurred. The `reference` identifies where it happened and should be
+    /// included when reporting the problem.
+    InternalError => ("internal_error", INTERNAL_SERVER_ERROR, "Internal server error"),
+    /// The user's own instance of the service could not be started. Retrying may succeed.
+    InstanceUnavailable => ("instance_unavailable", SERVICE_UNAVAILABLE, "Instance unavailable"),
+    /// The challenge, flag, hint or attachment has prerequisites that the user has not solved.
+    Locked => ("locked", FORBIDDEN, "Not unlocked yet"),
+    /// The challenge has not been released to the user yet.
+    NotReleased => ("not_released", FORBIDDEN, "Not released yet"),
+    /// The submission deadline for the flag has passed and late submissions are rejected.
+    DeadlinePassed => ("deadline_passed", FORBIDDEN, "Deadline passed"),
+    /// The user, or their team, has already submitted the flag.
+    AlreadySubmitted => ("already_submitted", BAD_REQUEST, "Already submitted"),
+    /// A flag can only be generated for a dynamic flag.
+    StaticFlag => ("static_flag", BAD_REQUEST, "Flag is static"),
+    /// The submitted flag is not of the form `COMP6443{...}`. Attempt reason 1.
+    FlagMalformed => ("flag_malformed", BAD_REQUEST, "Invalid flag provided"),
+    /// The submitted flag does not match the static flag. Attempt reason 2.
+    FlagIncorrect => ("flag_incorrect", BAD_REQUEST, "Invalid flag provided"),
+    /// The flag is dynamic, but the submitted flag is not of the form
+    /// `COMP6443{v2.name.owner.key.sig}` or a legacy dynamic flag. Attempt reason 3.
+    FlagNotDynamic => ("flag_not_dynamic", BAD_REQUEST, "Invalid flag provided"),
+    /// The name in the submitted dynamic flag does not match the flag. Attempt reason 4.
+    FlagWrongName => ("flag_wrong_name", BAD_REQUEST, "Invalid flag provided"),
+    /// The owner or signature in the submitted dynamic flag is not valid base64. Attempt reason 5.
+    FlagBadEncoding => ("flag_bad_encoding", BAD_REQUEST, "Invalid flag provided"),
+    /// The owner in the submitted dynamic flag is not a user or team. Attempt reason 6.
+    FlagBadIdentity => ("flag_bad_identity", BAD_REQUEST, "Invalid flag provided"),
+    /// The submitted dynamic flag was generated for another user. Attempt reason 7.
+    FlagNotOwned => ("flag_not_owned", BAD_REQUEST, "Invalid flag provided"),
+    /// The signature in the submitted dynamic flag is not valid. Attempt reason 8.
+    FlagBadSignature => ("flag_bad_signature", BAD_REQUEST, "Invalid flag provided"),
+    /// The submitted dynamic flag was signed with a key that has been retired. Attempt reason 9.
+    FlagKeyRetired => ("flag_key_retired", BAD_REQUEST, "Flag has expired"),
+    /// A user with the email address has already downloaded their certificates.
+    AlreadyEnrolled => ("already_enrolled", BAD_REQUEST, "Already enrolled"),
+    /// The email address is not one that may enrol.
+    EmailNotAllowed => ("email_not_allowed", BAD_REQUEST, "Email address not allowed"),
+    /// The certificate download token is invalid or has expired.
+    InvalidDownloadToken => ("invalid_download_token", BAD_REQUEST, "Invalid download token"),
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// An error returned by an API, rendered as problem details.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    code:        Code,
+    detail:      Option<Cow<'static, str>>,
+    reference:   Option<&'static str>,
+    retry_after: Option<i64>,
+    extensions:  Map<String, Value>,
+}
+
+impl ApiError {
+    #[must_use]
+    pub fn new(code: Code) -> Self {
+        Self {
+            code,
+            detail: None,
+            reference: None,
+            retry_after: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// An internal server error at the place identified by `reference`. The underlying error is
+    /// logged rather than returned.
+    pub fn internal(reference: &'static str, error: impl fmt::Display) -> Self {
+        tracing::error!("exception occurred (EC.{}): {}", reference, error);
+
+        Self {
+            reference: Some(reference),
+            ..Self::new(Code::InternalError)
+        }
+    }
+
+    /// Explain this occurrence of the error.
+    #[must_use]
+    pub fn detail(mut self, detail: impl Into<Cow<'static, str>>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Add a member to the problem details.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `value` cannot be serialized to JSON.
+    #[must_use]
+    pub fn with(mut self, key: &str, value: impl Serialize) -> Self {
+        self.extensions
+            .insert(key.to_string(), serde_json::to_value(value).unwrap());
+        self
+    }
+
+    /// Send a `Retry-After` header with the response.
+    #[must_use]
+    pub fn retry_after(mut self, seconds: i64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    #[must_use]
+    pub fn code(&self) -> Code { self.code }
+
+    /// The problem details document for the error.
+    #[must_use]
+    pub fn problem(&self) -> Value {
+        let mut problem = Map::new();
+        problem.insert("type".into(), format!("urn:scp:error:{}", self.code).into());
+        problem.insert("code".into(), self.code.as_str().into());
+        problem.insert("title".into(), self.code.title().into());
+        problem.insert("status".into(), self.code.status().as_u16().into());
+        if let Some(detail) = &self.detail {
+            problem.insert("detail".into(), detail.as_ref().into());
+        }
+        if let Some(reference) = self.reference {
+            problem.insert("reference".into(), format!("EC.{reference}").into());
+        }
+        for (key, value) in &self.extensions {
+            problem.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+
+        Value::Object(problem)
+    }
+}
+
+impl From<Code> for ApiError {
+    fn from(code: Code) -> Self { Self::new(code) }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.detail, self.reference) {
+            (Some(detail), _) => write!(f, "{}: {}", self.code.title(), detail),
+            (None, Some(reference)) => write!(f, "{}: EC.{}", self.code.title(), reference),
+            (None, None) => f.write_str(self.code.title()),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode { self.code.status() }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+        res.insert_header((header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON)));
+        if let Some(seconds) = self.retry_after {
+            res.insert_header((header::RETRY_AFTER, seconds));
+        }
+
+        res.body(self.problem().to_string())
+    }
+}
+
+/// Construct a closure that turns any error into an internal server error at the place identified
+/// by `$code`, for use with `map_err`.
+#[macro_export]
+macro_rules! ise {
+    ($code:expr) => {
+        |e| $crate::ApiError::internal($code, e)
+    };
+}
+
+/// Extractor configuration that reports malformed JSON bodies as problem details.
+#[must_use]
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|e: JsonPayloadError, _: &HttpRequest| {
+        ApiError::new(Code::InvalidRequest)
+            .detail(e.to_string())
+            .into()
+    })
+}
+
+/// Extractor configuration that reports malformed query strings as problem details.
+#[must_use]
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|e: QueryPayloadError, _: &HttpRequest| {
+        ApiError::new(Code::InvalidRequest)
+            .detail(e.to_string())
+            .into()
+    })
+}
+
+/// Extractor configuration that reports malformed path segments as problem details.
+#[must_use]
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default().error_handler(|e: PathError, _: &HttpRequest| {
+        ApiError::new(Code::InvalidRequest)
+            .detail(e.to_string())
+            .into()
+    })
+}
+
+/// An entry in the error catalog.
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    pub code:        Code,
+    pub status:      u16,
+    pub title:       &'static str,
+    pub description: String,
+}
+
+/// Every error code, with its status, title and description.
+#[must_use]
+pub fn catalog() -> Vec<CatalogEntry> {
+    Code::ALL
+        .iter()
+        .map(|&code| CatalogEntry {
+            code,
+            status: code.status().as_u16(),
+            title: code.title(),
+            description: code.description(),
+        })
+        .collect()
+}
+
+/// The error catalog as a markdown document.
+#[must_use]
+pub fn catalog_markdown() -> String {
+    let mut doc = String::from(
+        "# Error Codes\n\nGenerated by `cargo run -p api-error --bin error-catalog`. Do not edit.\n\n\
+         Errors are returned as `application/problem+json` with the `code` below. Internal server \
+         errors also include a `reference`, which should be quoted when reporting them.\n\n\
+         | Code | Status | Title | Description |\n| ---- | ------ | ----- | ----------- |\n",
+    );
+    for entry in catalog() {
+        let _ = writeln!(
+            doc,
+            "| `{}` | {} | {} | {} |",
+            entry.code, entry.status, entry.title, entry.description
+        );
+    }
+
+    doc
+}
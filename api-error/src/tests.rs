@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use actix_web::{body::to_bytes, http::header, ResponseError};
+use serde_json::{json, Value};
+
+use crate::{catalog_markdown, ApiError, Code, PROBLEM_JSON};
+
+async fn body(err: &ApiError) -> Value {
+    let bytes = to_bytes(err.error_response().into_body()).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[actix_web::test]
+async fn renders_problem_details() {
+    let err = ApiError::new(Code::NotFound).detail("Hint does not exist");
+    let res = err.error_response();
+    assert_eq!(res.status(), 404);
+    assert_eq!(
+        res.headers().get(header::CONTENT_TYPE).unwrap(),
+        PROBLEM_JSON
+    );
+    assert_eq!(
+        body(&err).await,
+        json!({
+            "type": "urn:scp:error:not_found",
+            "code": "not_found",
+            "title": "Not found",
+            "status": 404,
+            "detail": "Hint does not exist",
+        })
+    );
+}
+
+#[actix_web::test]
+async fn internal_errors_carry_a_reference() {
+    let err = (crate::ise!("SFCNS"))("connection reset");
+    assert_eq!(err.code(), Code::InternalError);
+    assert_eq!(err.to_string(), "Internal server error: EC.SFCNS");
+
+    let problem = body(&err).await;
+    assert_eq!(problem["reference"], "EC.SFCNS");
+    assert_eq!(problem["status"], 500);
+    assert!(problem.get("detail").is_none());
+}
+
+#[actix_web::test]
+async fn extensions_and_headers() {
+    let err = ApiError::new(Code::RateLimited)
+        .retry_after(30)
+        .with("reason", 7)
+        .with("code", "overridden");
+    let res = err.error_response();
+    assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "30");
+
+    let problem = body(&err).await;
+    assert_eq!(problem["reason"], 7);
+    assert_eq!(problem["code"], "rate_limited");
+}
+
+#[test]
+fn codes_are_unique_and_documented() {
+    let codes: HashSet<&str> = Code::ALL.iter().map(|c| c.as_str()).collect();
+    assert_eq!(codes.len(), Code::ALL.len());
+
+    for code in Code::ALL {
+        assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        assert!(!code.description().is_empty(), "{code} is undocumented");
+        assert!(!code.description().contains("  "));
+    }
+}
+
+#[test]
+fn catalog_is_up_to_date() {
+    assert!(
+        include_str!("../CATALOG.md") == catalog_markdown(),
+        "CATALOG.md is out of date, regenerate it with \
+         `cargo run -p api-error --bin error-catalog > api-error/CATALOG.md`"
+    );
+}
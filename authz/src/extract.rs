@@ -0,0 +1,104 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use actix_web::{dev::Payload, web::Data, Error, FromRequest, HttpMessage, HttpRequest};
+use api_error::{ApiError, Code};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{PermissionMarker, Policy, Role, Roles};
+
+const FORBIDDEN: &str = "You do not have permission to perform this action.";
+
+/// Looks up the role names held by the user making a request.
+pub trait RoleSource: Send + Sync + 'static {
+    /// Get the role names of the requester. Requests without valid authentication should be
+    /// rejected here.
+    fn roles(&self, req: &HttpRequest) -> LocalBoxFuture<'static, Result<HashSet<String>, Error>>;
+}
+
+/// The policy and role source used by the extractors in this crate. Must be registered as
+/// `web::Data<Authz>`.
+pub struct Authz {
+    policy: Policy,
+    source: Box<dyn RoleSource>,
+}
+
+impl Authz {
+    pub fn new(policy: Policy, source: impl RoleSource) -> Self {
+        Self {
+            policy,
+            source: Box::new(source),
+        }
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> &Policy { &self.policy }
+}
+
+/// The requester's roles are looked up once per request, then reused by any later extractors.
+impl FromRequest for Roles {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(roles) = req.extensions().get::<Roles>().cloned() {
+            return Box::pin(async move { Ok(roles) });
+        }
+
+        let req = req.clone();
+        Box::pin(async move {
+            let authz = req
+                .app_data::<Data<Authz>>()
+                .cloned()
+                .ok_or_else(|| ApiError::internal("AZNOCFG", "Authz is not registered"))?;
+
+            let names = authz.source.roles(&req).await?;
+            let roles = authz.policy.resolve(names);
+            req.extensions_mut().insert(roles.clone());
+
+            Ok(roles)
+        })
+    }
+}
+
+/// Rejects requests from users whose roles do not grant the permission `P`, one of the markers in
+/// [`crate::perm`].
+#[derive(Debug)]
+pub struct RequirePermission<P: PermissionMarker>(pub Roles, PhantomData<P>);
+
+impl<P: PermissionMarker + 'static> FromRequest for RequirePermission<P> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let roles = Roles::from_request(req, payload);
+        Box::pin(async move {
+            let roles = roles.await?;
+            if !roles.can(P::PERMISSION) {
+                return Err(ApiError::new(Code::Forbidden).detail(FORBIDDEN).into());
+            }
+
+            Ok(Self(roles, PhantomData))
+        })
+    }
+}
+
+/// Rejects requests from users who are not admins.
+#[derive(Debug)]
+pub struct RequireAdmin(pub Roles);
+
+impl FromRequest for RequireAdmin {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let roles = Roles::from_request(req, payload);
+        Box::pin(async move {
+            let roles = roles.await?;
+            if !roles.has(&Role::Admin) {
+                return Err(ApiError::new(Code::Forbidden).detail(FORBIDDEN).into());
+            }
+
+            Ok(Self(roles))
+        })
+    }
+}
@@ -0,0 +1,253 @@
+#![warn(clippy::pedantic)]
+
+//! Roles, the permissions they grant and actix extractors that enforce them.
+//!
+//! Users hold named roles, which are stored by gaia. A [`Policy`] maps each [`Role`] to the
+//! [`Permission`]s it grants: admins are granted everything, tutors are granted what is needed to
+//! run the course and students are granted nothing beyond taking part. Further roles can be defined
+//! through the `CUSTOM_ROLES` configuration key.
+//!
+//! Handlers require a permission by taking a [`RequirePermission`] argument, or inspect the
+//! requester's [`Roles`] directly. Both are resolved through the [`Authz`] stored in the app data.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+use service_config::Loader;
+
+mod extract;
+#[cfg(test)]
+mod tests;
+
+pub use extract::{Authz, RequireAdmin, RequirePermission, RoleSource};
+
+/// A role held by a user.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Role {
+    Admin,
+    Tutor,
+    Student,
+    /// A role defined through `CUSTOM_ROLES`.
+    Custom(String),
+}
+
+impl Role {
+    /// Whether the role belongs to course staff rather than a participant.
+    #[must_use]
+    pub fn is_staff(&self) -> bool { matches!(self, Role::Admin | Role::Tutor) }
+
+    /// The name of the role, as stored by gaia.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::Admin => "admin",
+            Role::Tutor => "tutor",
+            Role::Student => "student",
+            Role::Custom(name) => name,
+        }
+    }
+}
+
+impl From<&str> for Role {
+    fn from(name: &str) -> Self {
+        match name {
+            "admin" => Role::Admin,
+            "tutor" => Role::Tutor,
+            "student" => Role::Student,
+            _ => Role::Custom(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// Implemented by the marker types in [`perm`], which name a permission at the type level for
+/// [`RequirePermission`].
+pub trait PermissionMarker {
+    const PERMISSION: Permission;
+}
+
+macro_rules! permissions {
+    ($($(#[$doc:meta])* $name:ident => $str:literal,)*) => {
+        /// An action that a role may be granted.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Permission {
+            $($(#[$doc])* $name,)*
+        }
+
+        impl Permission {
+            /// Every permission, in declaration order.
+            pub const ALL: &'static [Permission] = &[$(Permission::$name),*];
+
+            /// The name of the permission, as used in `CUSTOM_ROLES`.
+            #[must_use]
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Permission::$name => $str,)*
+                }
+            }
+        }
+
+        /// Marker types for [`RequirePermission`], one per [`Permission`].
+        pub mod perm {
+            $(
+                $(#[$doc])*
+                #[derive(Debug)]
+                pub struct $name;
+
+                impl super::PermissionMarker for $name {
+                    const PERMISSION: super::Permission = super::Permission::$name;
+                }
+            )*
+        }
+    };
+}
+
+permissions! {
+    /// Create challenges and their services, flags and hints.
+    ManageChallenges => "manage_challenges",
+    /// Access challenges, flags, hints and services that are locked, unreleased or closed.
+    ViewUnreleased => "view_unreleased",
+    /// See who made every solve in the live feed.
+    ViewAllSolves => "view_all_solves",
+    /// Create and delete teams and change their members.
+    ManageTeams => "manage_teams",
+    /// Grant users individual deadlines.
+    ManageOverrides => "manage_overrides",
+    /// List flag submission attempts.
+    ViewAttempts => "view_attempts",
+    /// List submission lockouts.
+    ViewLockouts => "view_lockouts",
+    /// Clear submission lockouts.
+    ResetLockouts => "reset_lockouts",
+    /// List academic integrity incidents.
+    ViewIncidents => "view_incidents",
+    /// Export the gradebook.
+    ExportGradebook => "export_gradebook",
+    /// Query the audit log.
+    ViewAuditLog => "view_audit_log",
+    /// List users and their roles.
+    ViewUsers => "view_users",
+    /// Change the roles of users.
+    ManageRoles => "manage_roles",
+}
+
+/// The permissions granted by each role.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    grants: HashMap<Role, HashSet<Permission>>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        let tutor = [
+            Permission::ViewUnreleased,
+            Permission::ViewAllSolves,
+            Permission::ManageTeams,
+            Permission::ManageOverrides,
+            Permission::ViewAttempts,
+            Permission::ViewLockouts,
+            Permission::ViewUsers,
+        ];
+
+        Self {
+            grants: HashMap::from([
+                (Role::Admin, Permission::ALL.iter().copied().collect()),
+                (Role::Tutor, tutor.into_iter().collect()),
+                (Role::Student, HashSet::new()),
+            ]),
+        }
+    }
+}
+
+impl Policy {
+    /// The default policy, extended with the roles in the `CUSTOM_ROLES` configuration key. See
+    /// [`Policy::with_custom_roles`].
+    pub fn load(loader: &mut Loader) -> Self {
+        loader.parse_with("CUSTOM_ROLES", "", |roles| {
+            if roles.trim().is_empty() {
+                Ok(Self::default())
+            } else {
+                Self::default().with_custom_roles(roles)
+            }
+        })
+    }
+
+    /// Grant permissions to roles from a JSON object mapping role names to lists of permission
+    /// names, e.g. `{"marker": ["view_attempts", "export_gradebook"]}`. Permissions are added to
+    /// any that the role is already granted.
+    ///
+    /// # Errors
+    ///
+    /// Will error if the JSON is malformed or names an unknown permission.
+    pub fn with_custom_roles(mut self, json: &str) -> Result<Self, serde_json::Error> {
+        let custom: HashMap<String, Vec<Permission>> = serde_json::from_str(json)?;
+        for (name, permissions) in custom {
+            self.grants
+                .entry(Role::from(name.as_str()))
+                .or_default()
+                .extend(permissions);
+        }
+
+        Ok(self)
+    }
+
+    /// Whether a role is granted a permission.
+    #[must_use]
+    pub fn grants(&self, role: &Role, permission: Permission) -> bool {
+        self.grants
+            .get(role)
+            .is_some_and(|granted| granted.contains(&permission))
+    }
+
+    /// Resolve the role names held by a user into their roles and permissions.
+    pub fn resolve<I, S>(&self, names: I) -> Roles
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let roles: HashSet<Role> = names.into_iter().map(|n| Role::from(n.as_ref())).collect();
+        let permissions = roles
+            .iter()
+            .filter_map(|r| self.grants.get(r))
+            .flatten()
+            .copied()
+            .collect();
+
+        Roles { roles, permissions }
+    }
+}
+
+/// The roles held by a user and the permissions they grant.
+#[derive(Debug, Clone, Default)]
+pub struct Roles {
+    roles:       HashSet<Role>,
+    permissions: HashSet<Permission>,
+}
+
+impl Roles {
+    /// Whether the user holds a role.
+    #[must_use]
+    pub fn has(&self, role: &Role) -> bool { self.roles.contains(role) }
+
+    /// Whether any of the user's roles grant a permission.
+    #[must_use]
+    pub fn can(&self, permission: Permission) -> bool { self.permissions.contains(&permission) }
+
+    /// Whether the user holds a staff role.
+    #[must_use]
+    pub fn is_staff(&self) -> bool { self.roles.iter().any(Role::is_staff) }
+
+    /// Whether the user holds no roles at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.roles.is_empty() }
+
+    /// The roles held by the user.
+    pub fn iter(&self) -> impl Iterator<Item = &Role> { self.roles.iter() }
+}
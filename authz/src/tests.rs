@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use actix_web::{test::TestRequest, web::Data, Error, FromRequest, HttpRequest};
+use api_error::{ApiError, Code};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{perm, Authz, Permission, Policy, RequireAdmin, RequirePermission, Role, RoleSource};
+
+#[test]
+fn parses_roles() {
+    assert_eq!(Role::from("admin"), Role::Admin);
+    assert_eq!(Role::from("tutor"), Role::Tutor);
+    assert_eq!(Role::from("student"), Role::Student);
+    assert_eq!(Role::from("marker"), Role::Custom("marker".to_string()));
+    assert_eq!(Role::from("marker").to_string(), "marker");
+    assert!(Role::Tutor.is_staff());
+    assert!(!Role::Custom("marker".to_string()).is_staff());
+}
+
+#[test]
+fn default_policy() {
+    let policy = Policy::default();
+    for &permission in Permission::ALL {
+        assert!(policy.grants(&Role::Admin, permission));
+        assert!(!policy.grants(&Role::Student, permission));
+    }
+    assert!(policy.grants(&Role::Tutor, Permission::ViewUnreleased));
+    assert!(!policy.grants(&Role::Tutor, Permission::ManageRoles));
+    assert!(!policy.grants(&Role::Custom("marker".to_string()), Permission::ViewUsers));
+}
+
+#[test]
+fn resolves_permissions_of_all_roles() {
+    let roles = Policy::default().resolve(["student", "tutor"]);
+    assert!(roles.has(&Role::Student));
+    assert!(roles.is_staff());
+    assert!(roles.can(Permission::ViewAttempts));
+    assert!(!roles.can(Permission::ExportGradebook));
+
+    let roles = Policy::default().resolve(Vec::<String>::new());
+    assert!(roles.is_empty());
+    assert!(!roles.is_staff());
+}
+
+#[test]
+fn custom_roles() {
+    let policy = Policy::default()
+        .with_custom_roles(r#"{"marker": ["export_gradebook"], "tutor": ["view_incidents"]}"#)
+        .unwrap();
+
+    let roles = policy.resolve(["marker"]);
+    assert!(roles.can(Permission::ExportGradebook));
+    assert!(!roles.can(Permission::ViewAttempts));
+    assert!(!roles.is_staff());
+
+    assert!(policy.grants(&Role::Tutor, Permission::ViewIncidents));
+    assert!(policy.grants(&Role::Tutor, Permission::ViewUnreleased));
+
+    assert!(Policy::default()
+        .with_custom_roles(r#"{"marker": ["fly"]}"#)
+        .is_err());
+    assert!(Policy::default().with_custom_roles("marker").is_err());
+}
+
+/// Takes the requester's roles from a comma separated header.
+struct HeaderRoles;
+
+impl RoleSource for HeaderRoles {
+    fn roles(&self, req: &HttpRequest) -> LocalBoxFuture<'static, Result<HashSet<String>, Error>> {
+        let roles = req
+            .headers()
+            .get("X-Roles")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(',')
+                    .filter(|r| !r.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            });
+        Box::pin(async move { roles.ok_or_else(|| ApiError::new(Code::Unauthenticated).into()) })
+    }
+}
+
+fn request(roles: &str) -> HttpRequest {
+    TestRequest::default()
+        .app_data(Data::new(Authz::new(Policy::default(), HeaderRoles)))
+        .insert_header(("X-Roles", roles))
+        .to_http_request()
+}
+
+#[actix_web::test]
+async fn extractors() {
+    let req = request("tutor");
+    assert!(RequirePermission::<perm::ViewAttempts>::extract(&req)
+        .await
+        .is_ok());
+    assert!(RequirePermission::<perm::ResetLockouts>::extract(&req)
+        .await
+        .is_err());
+    assert!(RequireAdmin::extract(&req).await.is_err());
+
+    let req = request("admin");
+    assert!(RequireAdmin::extract(&req).await.is_ok());
+    assert!(RequirePermission::<perm::ManageRoles>::extract(&req)
+        .await
+        .is_ok());
+
+    let req = TestRequest::default()
+        .app_data(Data::new(Authz::new(Policy::default(), HeaderRoles)))
+        .to_http_request();
+    assert!(RequireAdmin::extract(&req).await.is_err());
+
+    let req = TestRequest::default().to_http_request();
+    assert!(RequireAdmin::extract(&req).await.is_err());
+}
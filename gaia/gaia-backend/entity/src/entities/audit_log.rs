@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// An administrative action that changed security-relevant state.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub audit_id:  i32,
+    /// The `_scpU` id of the user that performed the action.
+    #[sea_orm(indexed)]
+    pub actor:     String,
+    #[sea_orm(indexed)]
+    pub action:    String,
+    /// What the action was performed on, such as a user id.
+    pub target:    String,
+    /// The state of the target before the action, as JSON.
+    pub before:    Option<String>,
+    /// The state of the target after the action, as JSON.
+    pub after:     Option<String>,
+    #[sea_orm(indexed)]
+    pub timestamp: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    /// The audit log is append-only.
+    fn before_save(self, insert: bool) -> Result<Self, DbErr> {
+        if insert {
+            Ok(self)
+        } else {
+            Err(DbErr::Custom(
+                "audit log entries cannot be modified".to_string(),
+            ))
+        }
+    }
+}
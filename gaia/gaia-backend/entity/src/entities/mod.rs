@@ -1,2 +1,4 @@
+pub mod audit_log;
 pub mod role;
 pub mod user;
+pub mod webhook_delivery;
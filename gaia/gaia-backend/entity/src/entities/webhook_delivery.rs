@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// A webhook call for a platform event, including any retries.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "webhook_deliveries")]
+pub struct Model {
+    /// The id that the delivery was sent with.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub delivery_id:    i64,
+    #[sea_orm(indexed)]
+    pub event:          String,
+    pub url:            String,
+    /// The JSON body that was posted.
+    pub payload:        String,
+    pub attempts:       i32,
+    /// The status code of the last response, if the endpoint responded at all.
+    pub status_code:    Option<i32>,
+    pub success:        bool,
+    /// Why the last attempt failed.
+    pub error:          Option<String>,
+    pub started_time:   DateTimeUtc,
+    pub completed_time: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+/// Drop an index from a table.
+///
+/// sea-query always writes `DROP INDEX ... ON table` for SQLite, which SQLite does not accept, so
+/// the statement is written by hand there.
+pub(crate) async fn drop_index<T>(
+    manager: &SchemaManager<'_>,
+    table: T,
+    name: &str,
+) -> Result<(), DbErr>
+where
+    T: Iden + 'static,
+{
+    match manager.get_database_backend() {
+        DbBackend::Sqlite => {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DbBackend::Sqlite,
+                    format!(r#"DROP INDEX "{name}""#),
+                ))
+                .await?;
+            Ok(())
+        },
+        _ => {
+            manager
+                .drop_index(Index::drop().table(table).name(name).to_owned())
+                .await
+        },
+    }
+}
@@ -1,9 +1,16 @@
 pub use sea_orm_migration::prelude::*;
 
+mod helpers;
 mod m20220101_000001_create_table;
 mod m20220101_000002_create_table;
 mod m20220101_000003_create_index;
 mod m20220101_000004_create_index;
+mod m20220101_000005_create_table;
+mod m20220101_000006_create_table;
+mod m20220101_000007_alter_table;
+
+#[cfg(test)]
+mod tests;
 
 pub struct Migrator;
 
@@ -15,6 +22,9 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000002_create_table::Migration),
             Box::new(m20220101_000003_create_index::Migration),
             Box::new(m20220101_000004_create_index::Migration),
+            Box::new(m20220101_000005_create_table::Migration),
+            Box::new(m20220101_000006_create_table::Migration),
+            Box::new(m20220101_000007_alter_table::Migration),
         ]
     }
 }
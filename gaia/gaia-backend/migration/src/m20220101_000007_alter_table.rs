@@ -0,0 +1,121 @@
+use entity::webhook_delivery::{self, Column};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, EntityName, Statement},
+};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000007_alter_table" }
+}
+
+/// The table that deliveries are copied into while the table is rebuilt.
+fn rebuilt() -> Alias { Alias::new("webhook_deliveries_rebuilt") }
+
+/// Every column other than the delivery id.
+const COLUMNS: &[&str] = &[
+    "event",
+    "url",
+    "payload",
+    "attempts",
+    "status_code",
+    "success",
+    "error",
+    "started_time",
+    "completed_time",
+];
+
+/// Create a delivery table with the given id column.
+async fn create_table(
+    manager: &SchemaManager<'_>,
+    table: Alias,
+    id: &mut ColumnDef,
+) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(table)
+                .col(id.unique_key().not_null().primary_key())
+                .col(ColumnDef::new(Column::Event).string().not_null())
+                .col(ColumnDef::new(Column::Url).string().not_null())
+                .col(ColumnDef::new(Column::Payload).text().not_null())
+                .col(ColumnDef::new(Column::Attempts).integer().not_null())
+                .col(ColumnDef::new(Column::StatusCode).integer())
+                .col(ColumnDef::new(Column::Success).boolean().not_null())
+                .col(ColumnDef::new(Column::Error).string())
+                .col(
+                    ColumnDef::new(Column::StartedTime)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(Column::CompletedTime)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+/// Copy every delivery into the rebuilt table, then replace the table with it.
+async fn replace_table(manager: &SchemaManager<'_>, columns: &[&str]) -> Result<(), DbErr> {
+    let columns = columns
+        .iter()
+        .map(|c| format!(r#""{c}""#))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let conn = manager.get_connection();
+    conn.execute(Statement::from_string(
+        manager.get_database_backend(),
+        format!(
+            r#"INSERT INTO "{}" ({columns}) SELECT {columns} FROM "{}""#,
+            rebuilt().to_string(),
+            webhook_delivery::Entity.table_name(),
+        ),
+    ))
+    .await?;
+
+    manager
+        .drop_table(Table::drop().table(webhook_delivery::Entity).to_owned())
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(rebuilt(), webhook_delivery::Entity)
+                .to_owned(),
+        )
+        .await
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Deliveries keep the snowflake id that they were sent with, which does not fit in 32 bits
+        create_table(
+            manager,
+            rebuilt(),
+            ColumnDef::new(Column::DeliveryId).big_integer(),
+        )
+        .await?;
+
+        let mut columns = vec!["delivery_id"];
+        columns.extend(COLUMNS);
+        replace_table(manager, &columns).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Snowflake ids do not fit in the old column, so deliveries are given new ids
+        create_table(
+            manager,
+            rebuilt(),
+            ColumnDef::new(Column::DeliveryId)
+                .integer()
+                .auto_increment(),
+        )
+        .await?;
+
+        replace_table(manager, COLUMNS).await
+    }
+}
@@ -1,4 +1,62 @@
-use sea_orm_migration::prelude::*;
+use std::{env, process::exit};
 
-#[async_std::main]
-async fn main() { cli::run_cli(migration::Migrator).await; }
+use migration::Migrator;
+use sea_orm_migration::{prelude::*, sea_orm::Database};
+use tracing::Level;
+use tracing_subscriber::{filter::Targets, prelude::*};
+
+const USAGE: &str = "usage: migration [status | up [N] | down [N] | fresh]
+
+    status    List every migration and whether it has been applied
+    up [N]    Apply the next N pending migrations, or all of them. The default.
+    down [N]  Roll back the last N applied migrations, or only the last one
+    fresh     Drop every table, then apply all migrations
+
+The database is read from DATABASE_URL, falling back to DB_URI.";
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    exit(2);
+}
+
+fn steps(arg: Option<&String>) -> Option<u32> { arg.map(|n| n.parse().unwrap_or_else(|_| usage())) }
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .without_time()
+                .with_target(false)
+                .with_level(false),
+        )
+        .with(Targets::new().with_target("sea_orm_migration", Level::INFO))
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() > 2 {
+        usage();
+    }
+
+    let uri = env::var("DATABASE_URL")
+        .or_else(|_| env::var("DB_URI"))
+        .unwrap_or_else(|_| "sqlite://./db.db".to_string());
+    let db = Database::connect(&uri).await.unwrap_or_else(|e| {
+        eprintln!("could not connect to {uri}: {e}");
+        exit(1);
+    });
+
+    let result = match args.first().map(String::as_str) {
+        Some("status") if args.len() == 1 => Migrator::status(&db).await,
+        None => Migrator::up(&db, None).await,
+        Some("up") => Migrator::up(&db, steps(args.get(1))).await,
+        Some("down") => Migrator::down(&db, Some(steps(args.get(1)).unwrap_or(1))).await,
+        Some("fresh") if args.len() == 1 => Migrator::fresh(&db).await,
+        _ => usage(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{e}");
+        exit(1);
+    }
+}
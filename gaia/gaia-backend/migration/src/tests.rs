@@ -0,0 +1,123 @@
+use chrono::{TimeZone, Utc};
+use entity::{audit_log, role, user, webhook_delivery};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ActiveModelTrait, ColumnTrait, Database, EntityTrait, QueryFilter, Set},
+};
+
+use crate::Migrator;
+
+/// Apply every migration to an empty database and check that users, roles and timestamps survive a
+/// round trip.
+async fn round_trip(uri: &str) {
+    let conn = Database::connect(uri).await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    // Postgres stores timestamps with microsecond precision
+    let time = Utc.timestamp(1_700_000_000, 123_456_000);
+
+    user::ActiveModel {
+        user_id: Set("3444884897726725".to_string()),
+        name:    Set(None),
+        email:   Set("z1234567@unsw.edu.au".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    role::ActiveModel {
+        name: Set("student".to_string()),
+        user_id: Set("3444884897726725".to_string()),
+        ..Default::default()
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    let entry = audit_log::ActiveModel {
+        actor: Set("_scpU3444884897726725@unsw.scp.platform".to_string()),
+        action: Set("test".to_string()),
+        target: Set("3444884897726725".to_string()),
+        before: Set(None),
+        after: Set(Some("{}".to_string())),
+        timestamp: Set(time),
+        ..Default::default()
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    let roles = role::Entity::find()
+        .filter(role::Column::UserId.eq("3444884897726725"))
+        .all(&conn)
+        .await
+        .unwrap();
+    assert_eq!(roles.len(), 1);
+
+    let entry = audit_log::Entity::find_by_id(entry.audit_id)
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.timestamp, time);
+
+    // Webhook deliveries are stored under their snowflake id
+    webhook_delivery::ActiveModel {
+        delivery_id:    Set(3_444_884_897_726_725),
+        event:          Set("enrolment".to_string()),
+        url:            Set("http://example.com".to_string()),
+        payload:        Set("{}".to_string()),
+        attempts:       Set(1),
+        status_code:    Set(Some(200)),
+        success:        Set(true),
+        error:          Set(None),
+        started_time:   Set(time),
+        completed_time: Set(time),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    assert!(webhook_delivery::Entity::find_by_id(3_444_884_897_726_725)
+        .one(&conn)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+/// Roll every migration back and apply them again.
+async fn roll_back(uri: &str) {
+    let conn = Database::connect(uri).await.unwrap();
+    Migrator::up(&conn, None).await.unwrap();
+
+    Migrator::down(&conn, None).await.unwrap();
+    assert!(Migrator::get_applied_migrations(&conn)
+        .await
+        .unwrap()
+        .is_empty());
+    let manager = SchemaManager::new(&conn);
+    assert!(!manager.has_table("user").await.unwrap());
+
+    Migrator::up(&conn, None).await.unwrap();
+    assert!(Migrator::get_pending_migrations(&conn)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn migrations_apply_on_sqlite() {
+    round_trip("sqlite::memory:").await;
+    roll_back("sqlite::memory:").await;
+}
+
+/// Runs against the database in `TEST_POSTGRES_URL`, which is wiped. Skipped if it is not set.
+#[cfg(feature = "postgres")]
+#[tokio::test]
+async fn migrations_apply_on_postgres() {
+    match std::env::var("TEST_POSTGRES_URL") {
+        Ok(uri) => {
+            round_trip(&uri).await;
+            roll_back(&uri).await;
+        },
+        Err(_) => eprintln!("TEST_POSTGRES_URL is not set, skipping"),
+    }
+}
@@ -0,0 +1,62 @@
+use once_cell::sync::OnceCell;
+use service_config::{KeyLength, Loader};
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Gaia's configuration, loaded once at startup.
+pub(crate) struct Config {
+    /// The contents of the key used to sign and verify intra-service JWTs.
+    pub(crate) jwt_pem:       String,
+    /// The contents of the CA certificate that client certificates are signed with.
+    pub(crate) ca_cert:       String,
+    /// The contents of the CA certificate's private key.
+    pub(crate) ca_key:        String,
+    /// May contain database credentials, so is redacted by `--print-config`.
+    pub(crate) db_uri:        String,
+    /// Whether pending migrations are applied on startup. Set to `false` when the schema is
+    /// managed with the migration binary instead.
+    pub(crate) auto_migrate:  bool,
+    /// The public address that certificate download links point to.
+    pub(crate) public_addr:   String,
+    pub(crate) from_addr:     String,
+    /// The address of the router, which is told when a user's roles change. Empty to disable.
+    pub(crate) router_addr:   String,
+    pub(crate) smtp_addr:     String,
+    pub(crate) smtp_username: String,
+    pub(crate) smtp_password: String,
+    /// The key that certificate download tokens are encrypted with. Exactly 32 bytes long.
+    pub(crate) paseto_key:    String,
+    pub(crate) policy:        authz::Policy,
+    /// Where and how platform events are delivered.
+    pub(crate) webhooks:      webhooks::Config,
+}
+
+impl Config {
+    fn load(loader: &mut Loader) -> Self {
+        Self {
+            jwt_pem:       loader.file_contents("JWT_PEM_LOC", "../../proxy/certs/jwt-key.pem"),
+            ca_cert:       loader.file_contents("CA_CERT_LOC", "../../proxy/certs/rootCA.pem"),
+            ca_key:        loader.file_contents("CA_KEY_LOC", "../../proxy/certs/rootCA-key.pem"),
+            db_uri:        loader.secret_or("DB_URI", "sqlite://./db.db"),
+            auto_migrate:  loader.get("AUTO_MIGRATE", true),
+            public_addr:   loader.get("PUBLIC_ADDR", "login.local.host:8443".to_string()),
+            from_addr:     loader.get("FROM_ADDR", "noreply@local.host".to_string()),
+            router_addr:   loader.get("ROUTER_ADDR", "router:8082".to_string()),
+            smtp_addr:     loader.required("SMTP_ADDR"),
+            smtp_username: loader.required("SMTP_USERNAME"),
+            smtp_password: loader.secret("SMTP_PASSWORD"),
+            paseto_key:    loader.key("PASETO_KEY", KeyLength::Exactly(32)),
+            policy:        authz::Policy::load(loader),
+            webhooks:      webhooks::Config::load(loader),
+        }
+    }
+}
+
+/// Load and validate the configuration. Exits the process if it is invalid, or if gaia was
+/// started with `--print-config`.
+pub(crate) fn init() -> &'static Config {
+    CONFIG.get_or_init(|| service_config::load(Config::load))
+}
+
+/// The configuration loaded by [`init`].
+pub(crate) fn get() -> &'static Config { CONFIG.get().expect("configuration is loaded at startup") }
@@ -1,5 +1,4 @@
 use actix_web::{
-    error::{ErrorBadRequest, ErrorInternalServerError},
     get,
     http::header::{self, ContentType, DispositionParam},
     post,
@@ -7,6 +6,7 @@ use actix_web::{
     Error,
     HttpResponse,
 };
+use api_error::{ApiError, Code};
 use entity::user;
 use idgenerator::{IdGeneratorOptions, IdInstance};
 use lettre::{smtp::authentication::Credentials, SmtpClient, Transport};
@@ -22,16 +22,11 @@ use sea_orm::{
 };
 use serde::{Deserialize, Serialize};
 use tracing::info;
+use webhooks::{Event, Payload};
 
 use crate::{
-    utils::{self, ise},
-    CA_CERT,
-    CA_KEY,
-    FROM_ADDR,
-    PUBLIC_ADDR,
-    SMTP_ADDR,
-    SMTP_PASSWORD,
-    SMTP_USERNAME,
+    config,
+    utils::{self, audit, ise, notify, router},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,7 +51,12 @@ pub(crate) async fn enrol_user(
     {
         // User already exists
         // Send bad request
-        return Err(ErrorBadRequest("This user has already downloaded their certificates. If this is an error, please contact the administrators."));
+        return Err(ApiError::new(Code::AlreadyEnrolled)
+            .detail(
+                "This user has already downloaded their certificates. If this is an error, please \
+                 contact the administrators.",
+            )
+            .into());
     }
 
     // User has not downloaded the certificates yet. Generate a download link and send them an
@@ -64,23 +64,24 @@ pub(crate) async fn enrol_user(
     let regex = Regex::new(r"(?m)(((z\d{7})@unsw\.edu\.au)|((.+)@cba\.com\.au))").unwrap();
     if !regex.is_match(&data.email) {
         // Invalid email address; refuse
-        return Err(ErrorBadRequest(
-            "An email address that was not authorised by the system was received.",
-        ));
+        return Err(ApiError::new(Code::EmailNotAllowed)
+            .detail("An email address that was not authorised by the system was received.")
+            .into());
     }
 
     // Generate the download link
     let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
     IdInstance::init(generator_options).map_err(ise!("CIG"))?;
     let id = IdInstance::next_id();
+    let config = config::get();
     let token = utils::tokens::create_download_token(
+        config.paseto_key.as_bytes(),
         &format!("_scpU{}@unsw.scp.platform", id),
         &data.email,
     );
     let link = format!(
         "https://{}/api/certificates/download?key={}",
-        PUBLIC_ADDR.as_str(),
-        token
+        config.public_addr, token
     );
 
     // Generate the password
@@ -89,7 +90,7 @@ pub(crate) async fn enrol_user(
     // Send the email
     let email = EmailBuilder::new()
         .to(data.email.clone())
-        .from((FROM_ADDR.to_string(), "UNSW Security Challenges Platform"))
+        .from((config.from_addr.clone(), "UNSW Security Challenges Platform"))
         .subject("COMP6443 Client Certificates").text(format!(r#"
 Attached is your client certificate for COMP6443 at UNSW. You will have to download this certificate archive and import it into your keychain.
 
@@ -100,11 +101,11 @@ Your link to download your certificate package is: {}
 - Do not share these certificates with anyone else, as they will be able to access your account.
         "#, link, hash_result)).build().map_err(ise!("EUBE"))?;
 
-    let mut mailer = SmtpClient::new_simple(SMTP_ADDR.as_str())
+    let mut mailer = SmtpClient::new_simple(config.smtp_addr.as_str())
         .map_err(ise!("EUCM"))?
         .credentials(Credentials::new(
-            SMTP_USERNAME.as_str().to_owned(),
-            SMTP_PASSWORD.as_str().to_owned(),
+            config.smtp_username.clone(),
+            config.smtp_password.clone(),
         ))
         .transport();
 
@@ -125,9 +126,13 @@ pub(crate) async fn download_certs(
     query_params: web::Query<DownloadTokenQueryParams>,
 ) -> Result<HttpResponse, Error> {
     // Extract and validate token claims
-    let claims = utils::tokens::decrypt_download_token(&query_params.key).ok_or_else(|| {
-        ErrorBadRequest("Invalid download token. Please request your certificates again.")
-    })?;
+    let config = config::get();
+    let claims =
+        utils::tokens::decrypt_download_token(config.paseto_key.as_bytes(), &query_params.key)
+            .ok_or_else(|| {
+                ApiError::new(Code::InvalidDownloadToken)
+                    .detail("Invalid download token. Please request your certificates again.")
+            })?;
 
     // Check if the email has already been used
     let already_generated = user::Entity::find()
@@ -146,19 +151,36 @@ pub(crate) async fn download_certs(
             .map_err(ise!("DCCCC"))?;
 
     // Sign certificates
-    let ca_cert = cert_utils::get_ca_cert(&CA_CERT, &CA_KEY).map_err(ise!("DCGCC"))?;
+    let ca_cert =
+        cert_utils::get_ca_cert(&config.ca_cert, &config.ca_key).map_err(ise!("DCGCC"))?;
     let client_pfx = cert_utils::generate_pfx(&cert, &ca_cert, "6443-certificates", &password)
         .map_err(ise!("DCGPX"))?;
 
-    if !already_generated {
+    let uid = claims
+        .user_id
+        .strip_prefix("_scpU")
+        .unwrap()
+        .strip_suffix("@unsw.scp.platform")
+        .unwrap()
+        .to_string();
+    let audited = serde_json::json!({
+        "email": claims.signup_email,
+        "new_user": !already_generated,
+    });
+
+    if already_generated {
+        audit::record(
+            conn.as_ref(),
+            &claims.user_id,
+            "certificate.download",
+            &uid,
+            None,
+            Some(audited),
+        )
+        .await
+        .map_err(ise!("DCRAL"))?;
+    } else {
         let txn = conn.begin().await.map_err(ise!("DCBTX"))?;
-        let uid = claims
-            .user_id
-            .strip_prefix("_scpU")
-            .unwrap()
-            .strip_suffix("@unsw.scp.platform")
-            .unwrap()
-            .to_string();
 
         // Update database
         let user = entity::user::ActiveModel {
@@ -177,8 +199,29 @@ pub(crate) async fn download_certs(
 
         role.insert(&txn).await.map_err(ise!("DCIRR"))?;
 
+        audit::record(
+            &txn,
+            &claims.user_id,
+            "certificate.download",
+            &uid,
+            None,
+            Some(audited),
+        )
+        .await
+        .map_err(ise!("DCRAL"))?;
+
         // Commit transaction
         txn.commit().await.map_err(ise!("DCCTX"))?;
+        router::invalidate_roles(vec![uid.clone()]);
+
+        notify::notify(
+            conn.as_ref(),
+            Payload::new(
+                Event::Enrolment,
+                "A new user has enrolled",
+                &serde_json::json!({ "user_id": uid }),
+            ),
+        );
     }
 
     // Send cert to client
@@ -1,40 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use actix_web::{
-    error::{ErrorForbidden, ErrorInternalServerError},
-    get,
-    post,
-    web,
-    Error,
-    HttpRequest,
-    HttpResponse,
-};
+use actix_web::{get, post, web, Error, FromRequest, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::{perm, RequirePermission};
 use entity::{role, user};
 use sea_orm::{DatabaseConnection, EntityTrait};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{self, get_token_id, ise};
+use crate::utils::{self, ise};
 
+pub mod audit;
 pub mod certificates;
 pub mod self_service;
+pub mod webhooks;
 
 #[get("/user/{id}/roles")]
 pub(crate) async fn get_user_roles(
-    req: HttpRequest,
+    _: RequirePermission<perm::ManageRoles>,
     user_id: web::Path<String>,
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
-    // First ensure that the user making this request has the "admin" role
-    if !utils::get_roles(&get_token_id(&req)?, &conn)
-        .await
-        .map_err(ise!("GR"))?
-        .contains("admin")
-    {
-        return Err(ErrorForbidden(
-            "You do not have permission to perform this action.",
-        ));
-    }
-
     let roles: HashSet<String> = utils::get_roles(&user_id, conn.into_inner().as_ref())
         .await
         .map_err(ise!("GR"))?;
@@ -44,24 +29,15 @@ pub(crate) async fn get_user_roles(
 
 #[post("/user/{id}/roles")]
 pub(crate) async fn set_user_roles(
+    _: RequirePermission<perm::ManageRoles>,
     req: HttpRequest,
     user_id: web::Path<String>,
     new_roles: web::Json<Vec<String>>,
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
-    // First ensure that the user making this request has the "admin" role
-    if !utils::get_roles(&get_token_id(&req)?, &conn)
-        .await
-        .map_err(ise!("GR"))?
-        .contains("admin")
-    {
-        return Err(ErrorForbidden(
-            "You do not have permission to perform this action.",
-        ));
-    }
-
     // Make changes
-    utils::set_roles(&user_id, new_roles.0, &conn).await?;
+    let actor = utils::get_auth_claims(&req)?.user_id;
+    utils::set_roles(&actor, &user_id, new_roles.0, &conn).await?;
 
     Ok(HttpResponse::Ok().finish())
 }
@@ -76,19 +52,9 @@ struct UserWithRole {
 
 #[get("/users")]
 pub(crate) async fn get_users(
-    req: HttpRequest,
+    _: RequirePermission<perm::ViewUsers>,
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
-    // First ensure that the user making this request has the "tutor" or "admin" role
-    let roles = utils::get_roles(&get_token_id(&req)?, &conn)
-        .await
-        .map_err(ise!("GR"))?;
-    if !roles.contains("admin") && !roles.contains("tutor") {
-        return Err(ErrorForbidden(
-            "You do not have permission to perform this action.",
-        ));
-    }
-
     // Get all users and their roles
     let users_with_roles: Vec<UserWithRole> = user::Entity::find()
         .find_with_related(role::Entity)
@@ -106,3 +72,33 @@ pub(crate) async fn get_users(
 
     Ok(HttpResponse::Ok().json(users_with_roles))
 }
+
+/// The most users whose roles can be fetched in a single request.
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Get the roles of many users at once. Available to the router and users that can view users.
+#[post("/users/roles")]
+pub(crate) async fn get_users_roles(
+    req: HttpRequest,
+    ids: web::Json<Vec<String>>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // The router is not a user, so it has no roles to check
+    if utils::get_auth_claims(&req)?.service() != Some("router") {
+        RequirePermission::<perm::ViewUsers>::extract(&req).await?;
+    }
+
+    if ids.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail(format!(
+                "At most {MAX_BATCH_SIZE} users can be requested at once"
+            ))
+            .into());
+    }
+
+    let roles: HashMap<String, HashSet<String>> = utils::get_roles_for(&ids, &conn)
+        .await
+        .map_err(ise!("GUR"))?;
+
+    Ok(HttpResponse::Ok().json(roles))
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use actix_web::{error::ErrorInternalServerError, get, web, Error, HttpRequest, HttpResponse};
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
 use sea_orm::DatabaseConnection;
 
 use crate::utils::{self, get_token_id, ise};
@@ -0,0 +1,39 @@
+use actix_web::{get, web, Error, HttpResponse};
+use authz::RequireAdmin;
+use entity::webhook_delivery;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Deserialize;
+
+use crate::utils::ise;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DeliveryQueryParams {
+    pub event:  Option<String>,
+    /// Only include deliveries that ran out of retries.
+    #[serde(default)]
+    pub failed: bool,
+    /// The maximum number of deliveries to return. Defaults to 100.
+    pub limit:  Option<u64>,
+}
+
+#[get("/deliveries")]
+pub(crate) async fn get_deliveries(
+    _: RequireAdmin,
+    params: web::Query<DeliveryQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let mut query = webhook_delivery::Entity::find()
+        .order_by_desc(webhook_delivery::Column::StartedTime)
+        .limit(params.limit.unwrap_or(100));
+
+    if let Some(event) = &params.event {
+        query = query.filter(webhook_delivery::Column::Event.eq(event.clone()));
+    }
+    if params.failed {
+        query = query.filter(webhook_delivery::Column::Success.eq(false));
+    }
+
+    let deliveries = query.all(conn.as_ref()).await.map_err(ise!("GWDQD"))?;
+
+    Ok(HttpResponse::Ok().json(deliveries))
+}
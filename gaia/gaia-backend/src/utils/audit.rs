@@ -0,0 +1,31 @@
+use entity::audit_log;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use serde_json::Value;
+
+/// Append an entry to the audit log. Should be called in the same transaction as the change
+/// being audited where possible.
+pub(crate) async fn record<C>(
+    conn: &C,
+    actor: &str,
+    action: &str,
+    target: &str,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    audit_log::ActiveModel {
+        actor: Set(actor.to_string()),
+        action: Set(action.to_string()),
+        target: Set(target.to_string()),
+        before: Set(before.map(|v| v.to_string())),
+        after: Set(after.map(|v| v.to_string())),
+        timestamp: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(())
+}
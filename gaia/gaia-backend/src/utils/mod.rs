@@ -1,40 +1,34 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use actix_web::{
-    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
-    Error,
-    HttpRequest,
-};
+use actix_web::{web, Error, HttpRequest};
+pub(crate) use api_error::ise;
+use api_error::{ApiError, Code};
+use authz::RoleSource;
 use entity::{role, user};
+use futures_util::future::LocalBoxFuture;
 use intra_jwt::ClaimsData;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TransactionTrait};
 use sha2::{Digest, Sha256};
 
-use crate::JWT_PEM;
+use crate::config;
+pub mod audit;
+pub mod notify;
+pub mod router;
 pub mod tokens;
 
-/// Macro to quickly construct an internal server error with an error code.
-macro_rules! ise {
-    ($code:expr) => {
-        |_| ErrorInternalServerError(concat!("Internal server error: EC.", $code))
-    };
-}
-
-pub(crate) use ise;
-
 /// Fetch the auth claims from a http request's token.
 pub(crate) fn get_auth_claims(req: &HttpRequest) -> Result<ClaimsData, Error> {
     // Get JWT
     let jwt_str = req
         .headers()
         .get("x-scp-auth")
-        .ok_or_else(|| ErrorUnauthorized("Missing authentication token"))?
+        .ok_or_else(|| ApiError::new(Code::Unauthenticated))?
         .to_str()
-        .map_err(ErrorInternalServerError)?;
+        .map_err(|_| ApiError::new(Code::InvalidToken))?;
 
     // Validate str
-    intra_jwt::verify_jwt(jwt_str, &JWT_PEM)
-        .map_err(|_| ErrorBadRequest("Invalid authentication token"))
+    intra_jwt::verify_jwt(jwt_str, &config::get().jwt_pem)
+        .map_err(|_| ApiError::new(Code::InvalidToken).into())
 }
 
 /// Gets the user's id from the certificate.
@@ -46,9 +40,9 @@ pub(crate) fn get_token_id(req: &HttpRequest) -> Result<String, Error> {
     // Get user id from email
     let id = email
         .strip_prefix("_scpU")
-        .ok_or_else(|| ErrorBadRequest("Invalid authentication token"))?
+        .ok_or_else(|| ApiError::new(Code::InvalidToken))?
         .strip_suffix("@unsw.scp.platform")
-        .ok_or_else(|| ErrorBadRequest("Invalid authentication token"))?
+        .ok_or_else(|| ApiError::new(Code::InvalidToken))?
         .to_owned();
 
     Ok(id)
@@ -68,8 +62,44 @@ pub(crate) async fn get_roles(
         .collect())
 }
 
-/// Set the roles for a user.
+/// Looks up the roles of the user making a request from the database.
+pub(crate) struct DbRoles;
+
+impl RoleSource for DbRoles {
+    fn roles(&self, req: &HttpRequest) -> LocalBoxFuture<'static, Result<HashSet<String>, Error>> {
+        let id = get_token_id(req);
+        let conn = req.app_data::<web::Data<DatabaseConnection>>().cloned();
+
+        Box::pin(async move {
+            let conn = conn.ok_or_else(|| ApiError::internal("RSNC", "no database connection"))?;
+            Ok(get_roles(&id?, &conn).await.map_err(ise!("GR"))?)
+        })
+    }
+}
+
+/// Get roles for many user ids at once. Every id is present in the result, with no roles if the
+/// user does not exist.
+pub(crate) async fn get_roles_for(
+    ids: &[String],
+    conn: &DatabaseConnection,
+) -> anyhow::Result<HashMap<String, HashSet<String>>> {
+    let mut roles: HashMap<String, HashSet<String>> =
+        ids.iter().map(|id| (id.clone(), HashSet::new())).collect();
+
+    for role in role::Entity::find()
+        .filter(role::Column::UserId.is_in(ids.iter().cloned()))
+        .all(conn)
+        .await?
+    {
+        roles.entry(role.user_id).or_default().insert(role.name);
+    }
+
+    Ok(roles)
+}
+
+/// Set the roles for a user. The change is recorded in the audit log against `actor`.
 pub(crate) async fn set_roles(
+    actor: &str,
     id: &str,
     roles: Vec<String>,
     conn: &DatabaseConnection,
@@ -79,10 +109,32 @@ pub(crate) async fn set_roles(
         .one(conn)
         .await
         .map_err(ise!("SRDBQ"))?
-        .ok_or_else(|| ErrorNotFound(format!("The user with id {} does not exist", id)))?;
+        .ok_or_else(|| {
+            ApiError::new(Code::NotFound).detail(format!("The user with id {id} does not exist"))
+        })?;
 
     let txn = conn.begin().await.map_err(ise!("SRBTXN"))?;
 
+    let previous: Vec<String> = role::Entity::find()
+        .filter(role::Column::UserId.eq(id))
+        .all(&txn)
+        .await
+        .map_err(ise!("SRQR"))?
+        .into_iter()
+        .map(|r| r.name)
+        .collect();
+
+    audit::record(
+        &txn,
+        actor,
+        "user.roles.set",
+        id,
+        Some(serde_json::json!(previous)),
+        Some(serde_json::json!(roles)),
+    )
+    .await
+    .map_err(ise!("SRRAL"))?;
+
     // Remove all previous roles
     role::Entity::delete_many()
         .filter(role::Column::UserId.eq(id))
@@ -102,6 +154,7 @@ pub(crate) async fn set_roles(
 
     // Commit transaction
     txn.commit().await.map_err(ise!("SRCTX"))?;
+    router::invalidate_roles(vec![id.to_string()]);
 
     Ok(true)
 }
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+use crate::config;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap_or_default()
+});
+
+/// Tell the router that the roles of some users have changed, so that it drops them from its role
+/// cache. This happens in the background and failures are only logged, as the router's cache
+/// entries expire on their own.
+pub(crate) fn invalidate_roles(ids: Vec<String>) {
+    if config::get().router_addr.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) = send_invalidation(&ids).await {
+            warn!("failed to invalidate cached roles in router: {}", e);
+        }
+    });
+}
+
+async fn send_invalidation(ids: &[String]) -> anyhow::Result<()> {
+    let token = intra_jwt::create_service_jwt("gaia", &config::get().jwt_pem)?;
+    CLIENT
+        .post(format!(
+            "http://{}/api/roles/invalidate",
+            config::get().router_addr
+        ))
+        .header("X-Scp-Auth", token)
+        .json(ids)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -1,25 +1,17 @@
 use chrono::{Duration, Utc};
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::error;
 
-static PASETO_KEY: Lazy<Vec<u8>> = Lazy::new(|| {
-    std::env::var("PASETO_KEY")
-        .unwrap_or_else(|_| panic!("missing PASETO_KEY environment variable"))
-        .as_bytes()
-        .to_owned()
-});
-
-/// Creates a download token for a specific user id (email)
-pub(crate) fn create_download_token(user_id: &str, signup_email: &str) -> String {
+/// Creates a download token for a specific user id (email), encrypted with `key`.
+pub(crate) fn create_download_token(key: &[u8], user_id: &str, signup_email: &str) -> String {
     let current_dt = Utc::now();
     let dt = current_dt
         .checked_add_signed(Duration::minutes(30))
         .unwrap();
 
     match paseto::tokens::PasetoBuilder::new()
-        .set_encryption_key(&PASETO_KEY)
+        .set_encryption_key(key)
         .set_expiration(&dt)
         .set_issuer("SCP")
         .set_not_before(&Utc::now())
@@ -48,8 +40,8 @@ pub(crate) struct PasetoResult {
 }
 
 /// Decrypts a download token and returns the ID from within the token.
-pub(crate) fn decrypt_download_token(token: &str) -> Option<PasetoResult> {
-    let decrypted = match paseto::v2::decrypt_paseto(token, None, &PASETO_KEY) {
+pub(crate) fn decrypt_download_token(key: &[u8], token: &str) -> Option<PasetoResult> {
+    let decrypted = match paseto::v2::decrypt_paseto(token, None, key) {
         Ok(t) => t,
         Err(e) => {
             error!("e = {:#?}", e);
@@ -88,7 +80,11 @@ mod tests {
 
     #[test]
     fn it_creates() {
-        let t = create_download_token("_scpUz1234567@unsw.scp.platform", "some@email.com");
+        let t = create_download_token(
+            b"abcdefabcdefabcdefabcdefabcdefab",
+            "_scpUz1234567@unsw.scp.platform",
+            "some@email.com",
+        );
         error!("t = {:#?}", t);
     }
 }
@@ -15,6 +15,17 @@ pub struct ClaimsData {
     pub user_id:  String,
 }
 
+impl ClaimsData {
+    /// The name of the service that the token was issued to, if it identifies a service rather
+    /// than a user.
+    #[must_use]
+    pub fn service(&self) -> Option<&str> {
+        self.user_id
+            .strip_prefix("_scpS")?
+            .strip_suffix("@scp.platform")
+    }
+}
+
 impl ExtraClaimsData {
     fn new(username: String) -> Self { Self { username } }
 }
@@ -38,6 +49,21 @@ pub fn create_jwt(user_id: String, username: String, key_pair_pem: &str) -> anyh
     key_pair.sign(claims)
 }
 
+/// Create a JWT identifying one of the platform's services, for requests that services make to each
+/// other on their own behalf rather than a user's. Tokens are valid for 60s.
+///
+/// # Errors
+///
+/// Will error if the function is unable to construct an `Ed25519KeyPair` from the supplied PEM
+/// string.
+pub fn create_service_jwt(service: &str, key_pair_pem: &str) -> anyhow::Result<String> {
+    create_jwt(
+        format!("_scpS{service}@scp.platform"),
+        service.to_string(),
+        key_pair_pem,
+    )
+}
+
 /// Verify if a jwt is valid and return the claims contained within.
 ///
 /// # Errors
@@ -0,0 +1,46 @@
+use once_cell::sync::OnceCell;
+use service_config::Loader;
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// The proxy's configuration, loaded once at startup.
+pub(crate) struct Config {
+    /// The contents of the key used to sign intra-service JWTs.
+    pub(crate) jwt_pem:        String,
+    pub(crate) base_domain:    String,
+    pub(crate) router_url:     String,
+    /// The path of the CA certificate that client certificates are verified against.
+    pub(crate) ca_cert:        String,
+    /// The path of the TLS server certificate.
+    pub(crate) server_cert:    String,
+    /// The path of the TLS server certificate's private key.
+    pub(crate) server_key:     String,
+    pub(crate) gaia_be_addr:   String,
+    pub(crate) gaia_fe_addr:   String,
+    pub(crate) dashboard_addr: String,
+}
+
+impl Config {
+    fn load(loader: &mut Loader) -> Self {
+        Self {
+            jwt_pem:        loader.file_contents("JWT_PEM", "certs/jwt-key.pem"),
+            base_domain:    loader.get("BASE_DOMAIN", "local.host:8443".to_string()),
+            router_url:     loader.get("ROUTER_URL", "router:8082".to_string()),
+            ca_cert:        loader.get("CA_CERT", "certs/rootCA.pem".to_string()),
+            server_cert:    loader.get("SERVER_CERT", "certs/server-cert.pem".to_string()),
+            server_key:     loader.get("SERVER_KEY", "certs/server-key.pem".to_string()),
+            gaia_be_addr:   loader.get("GAIA_BE_ADDR", "gaia-backend".to_string()),
+            gaia_fe_addr:   loader.get("GAIA_FE_ADDR", "gaia-frontend".to_string()),
+            dashboard_addr: loader.get("DASHBOARD_ADDR", "dashboard".to_string()),
+        }
+    }
+}
+
+/// Load and validate the configuration. Exits the process if it is invalid, or if the proxy was
+/// started with `--print-config`.
+pub(crate) fn init() -> &'static Config {
+    CONFIG.get_or_init(|| service_config::load(Config::load))
+}
+
+/// The configuration loaded by [`init`].
+pub(crate) fn get() -> &'static Config { CONFIG.get().expect("configuration is loaded at startup") }
@@ -5,11 +5,11 @@ use std::env;
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use awc::Client;
 use middleware::handle_client_cert;
-use once_cell::sync::Lazy;
 use tracing::info;
 
 use crate::tls::create_tls_server_config;
 
+mod config;
 mod middleware;
 mod router_utils;
 mod routes;
@@ -17,16 +17,6 @@ mod tls;
 
 const PORT: u16 = 8080;
 
-// env declarations
-static BASE_DOMAIN: Lazy<String> = env_utils::lazy_env!("BASE_DOMAIN", "local.host:8443");
-static ROUTER_URL: Lazy<String> = env_utils::lazy_env!("ROUTER_URL", "router:8082");
-static CA_CERT: Lazy<String> = env_utils::lazy_env!("CA_CERT", "certs/rootCA.pem");
-static SERVER_CERT: Lazy<String> = env_utils::lazy_env!("SERVER_CERT", "certs/server-cert.pem");
-static SERVER_KEY: Lazy<String> = env_utils::lazy_env!("SERVER_KEY", "certs/server-key.pem");
-static GAIA_BE_ADDR: Lazy<String> = env_utils::lazy_env!("GAIA_BE_ADDR", "gaia-backend");
-static GAIA_FE_ADDR: Lazy<String> = env_utils::lazy_env!("GAIA_FE_ADDR", "gaia-frontend");
-static DASHBOARD_ADDR: Lazy<String> = env_utils::lazy_env!("DASHBOARD_ADDR", "dashboard");
-
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     if env::var("RUST_LOG").is_err() {
@@ -34,7 +24,7 @@ async fn main() -> std::io::Result<()> {
     }
 
     tracing_subscriber::fmt::init();
-    tls::initialise_key_pem();
+    config::init();
 
     info!("Launching SCP proxy version {}", env!("CARGO_PKG_VERSION"));
 
@@ -12,7 +12,7 @@ use futures_util::future::LocalBoxFuture;
 use tracing::error;
 
 use super::Email;
-use crate::BASE_DOMAIN;
+use crate::config;
 
 pub struct CheckCertificate;
 
@@ -71,7 +71,7 @@ where
                 let jwt = match intra_jwt::create_jwt(
                     email,
                     "placeholder-username".to_string(),
-                    crate::tls::EDDSA_KEY_PEM.get().unwrap(),
+                    &crate::config::get().jwt_pem,
                 ) {
                     Ok(token) => token,
                     Err(e) => {
@@ -98,7 +98,7 @@ where
                     let response = HttpResponse::Found()
                         .insert_header((
                             http::header::LOCATION,
-                            format!("https://{}/enrol", BASE_DOMAIN.as_str()),
+                            format!("https://{}/enrol", config::get().base_domain),
                         ))
                         .finish()
                         // constructed responses map to "right" body
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::error;
 
-use crate::ROUTER_URL;
+use crate::config;
 
 #[derive(Debug, Clone, Error)]
 pub(crate) enum EvaluationErrors {
@@ -13,6 +13,8 @@ pub(crate) enum EvaluationErrors {
     NotFound,
     #[error("The URI that was supplied did not have a valid host.")]
     InvalidUriError,
+    #[error("The user's instance of the service could not be started.")]
+    InstanceUnavailable,
     #[error("An internal error occurred.")]
     InternalError,
 }
@@ -37,7 +39,7 @@ pub(crate) async fn get_route(subdomain: &str, token: &str) -> Result<url::Url,
 
     let client = reqwest::Client::new();
     let res = client
-        .post(format!("http://{}/api/evaluate", ROUTER_URL.as_str()))
+        .post(format!("http://{}/api/evaluate", config::get().router_url))
         .header("X-Scp-Auth", token)
         .json(&EvaluateRequestPayload {
             uri: request_uri.to_string(),
@@ -66,6 +68,7 @@ pub(crate) async fn get_route(subdomain: &str, token: &str) -> Result<url::Url,
         StatusCode::FORBIDDEN => Err(EvaluationErrors::Forbidden),
         StatusCode::NOT_FOUND => Err(EvaluationErrors::NotFound),
         StatusCode::BAD_REQUEST => Err(EvaluationErrors::InvalidUriError),
+        StatusCode::SERVICE_UNAVAILABLE => Err(EvaluationErrors::InstanceUnavailable),
         _ => {
             error!("received an error status code: {:?}", res.status());
             Err(EvaluationErrors::InternalError)
@@ -1,43 +1,17 @@
 #![allow(clippy::unused_async)]
 
-use actix_web::{
-    error::{
-        self,
-        ErrorBadRequest,
-        ErrorForbidden,
-        ErrorInternalServerError,
-        ErrorNotFound,
-        ErrorUnauthorized,
-    },
-    web,
-    Error,
-    HttpRequest,
-    HttpResponse,
-};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use api_error::{ise, ApiError, Code};
 use awc::Client;
 use tracing::instrument;
 use url::Url;
 
 use crate::{
+    config,
     middleware::Email,
     router_utils::{self, EvaluationErrors},
-    BASE_DOMAIN,
-    DASHBOARD_ADDR,
-    GAIA_BE_ADDR,
-    GAIA_FE_ADDR,
-    ROUTER_URL,
 };
 
-/// Macro to quickly construct an internal server error with an error code.
-macro_rules! ise {
-    ($code:expr) => {
-        |e| {
-            tracing::error!("exception occurred: {}", e);
-            ErrorInternalServerError(concat!("Internal server error: EC.", $code))
-        }
-    };
-}
-
 #[instrument(skip(payload, client))]
 pub(crate) async fn route_whoami(
     req: HttpRequest,
@@ -54,9 +28,9 @@ pub(crate) async fn route_whoami(
         // return Ok(HttpResponse::Unauthorized().body("You are missing your certificate."));
         // Make a more elegant page
         if req.path() == "/enrol" || req.path().starts_with("/_next") {
-            new_url = Url::parse(&format!("http://{}", GAIA_FE_ADDR.as_str())).unwrap();
+            new_url = Url::parse(&format!("http://{}", config::get().gaia_fe_addr)).unwrap();
         } else {
-            new_url = Url::parse(&format!("http://{}", GAIA_BE_ADDR.as_str())).unwrap();
+            new_url = Url::parse(&format!("http://{}", config::get().gaia_be_addr)).unwrap();
         }
     } else {
         // TODO: grab the subdomain
@@ -65,7 +39,7 @@ pub(crate) async fn route_whoami(
             None => {
                 // This should not be possible
                 eprintln!("req.uri() = {:#?}", req.uri());
-                return Ok(HttpResponse::InternalServerError().body("Internal server error: EC.SM"));
+                return Err(ApiError::internal("SM", "request has no host").into());
             },
         };
 
@@ -74,39 +48,47 @@ pub(crate) async fn route_whoami(
         match subdomain.next() {
             Some("ctf") => match subdomain.next() {
                 Some(s) => {
-                    // Check with the service registry to see if this should be proxied
-                    new_url = router_utils::get_route(
+                    // Check with the service registry to see if this should be proxied. The
+                    // future is boxed, as the request it makes to the router makes it large.
+                    new_url = Box::pin(router_utils::get_route(
                         s,
                         req.headers()
                             .get("X-Scp-Auth")
-                            .ok_or_else(|| ErrorUnauthorized("Missing authentication header"))?
+                            .ok_or_else(|| ApiError::new(Code::Unauthenticated))?
                             .to_str()
                             .map_err(ise!("RWPS"))?,
-                    )
+                    ))
                     .await
                     .map_err(|e| match e {
-                        EvaluationErrors::Forbidden => ErrorForbidden(""),
-                        EvaluationErrors::NotFound => ErrorNotFound(""),
-                        EvaluationErrors::InvalidUriError => ErrorBadRequest(""),
+                        EvaluationErrors::Forbidden => ApiError::new(Code::Forbidden),
+                        EvaluationErrors::NotFound => ApiError::new(Code::NotFound),
+                        EvaluationErrors::InvalidUriError => ApiError::new(Code::InvalidRequest),
+                        EvaluationErrors::InstanceUnavailable => {
+                            ApiError::new(Code::InstanceUnavailable)
+                        },
                         EvaluationErrors::InternalError => {
-                            ErrorInternalServerError("Internal server error: RWGH")
+                            ApiError::internal("RWGH", "evaluation failed")
                         },
                     })?;
                 },
                 None => {
                     if req.path().starts_with("/api") {
-                        new_url = Url::parse(&format!("http://{}", ROUTER_URL.as_str())).unwrap();
+                        new_url =
+                            Url::parse(&format!("http://{}", config::get().router_url)).unwrap();
                     } else {
                         // TODO: Show the dashboard
-                        new_url =
-                            Url::parse(&format!("http://{}", DASHBOARD_ADDR.as_str())).unwrap();
+                        new_url = Url::parse(&format!("http://{}", config::get().dashboard_addr))
+                            .unwrap();
                     }
                 },
             },
             Some(_) | None => {
                 // Redirect to the ctf page
                 return Ok(HttpResponse::Found()
-                    .insert_header(("Location", format!("https://ctf.{}", BASE_DOMAIN.as_str())))
+                    .insert_header((
+                        "Location",
+                        format!("https://ctf.{}", config::get().base_domain),
+                    ))
                     .finish());
             },
         }
@@ -128,7 +110,7 @@ pub(crate) async fn route_whoami(
     let res = forwarded_req
         .send_stream(payload)
         .await
-        .map_err(error::ErrorInternalServerError)?;
+        .map_err(ise!("RWSS"))?;
 
     let mut client_resp = HttpResponse::build(res.status());
     // Remove `Connection` as per
@@ -1,6 +1,5 @@
 use std::{borrow::Cow, fs::File, io::BufReader, vec};
 
-use once_cell::sync::OnceCell;
 use rustls::{
     server::AllowAnyAnonymousOrAuthenticatedClient,
     Certificate,
@@ -11,20 +10,7 @@ use rustls::{
 use tracing::{debug, instrument, warn};
 use x509_parser::extensions::GeneralName;
 
-use crate::{CA_CERT, SERVER_CERT, SERVER_KEY};
-
-pub static EDDSA_KEY_PEM: OnceCell<Cow<str>> = OnceCell::new();
-
-pub fn initialise_key_pem() {
-    let string_data = std::fs::read_to_string(
-        std::env::var("JWT_PEM").unwrap_or_else(|_| "certs/jwt-key.pem".to_string()),
-    )
-    .expect("could not read JWT keys");
-
-    EDDSA_KEY_PEM
-        .set(string_data.into())
-        .expect("could not read EDDSA private keys");
-}
+use crate::config;
 
 /// Get the emails from a certificate. The emails are taken from the `subjectAlternateNames`
 /// component of the certificate.
@@ -65,18 +51,19 @@ pub fn get_emails_from_cert(certificate_data: &[u8]) -> Vec<Cow<str>> {
 
 /// Create the configuration for the TLS server.
 pub fn create_tls_server_config() -> Result<ServerConfig, std::io::Error> {
+    let config = config::get();
     let mut cert_store = RootCertStore::empty();
-    let ca_cert = &mut BufReader::new(File::open(CA_CERT.as_str())?);
+    let ca_cert = &mut BufReader::new(File::open(&config.ca_cert)?);
     let ca_cert = Certificate(rustls_pemfile::certs(ca_cert).unwrap()[0].clone());
     cert_store
         .add(&ca_cert)
         .expect("root CA not added to store");
     let client_auth = AllowAnyAnonymousOrAuthenticatedClient::new(cert_store);
-    let config = ServerConfig::builder()
+    let server_config = ServerConfig::builder()
         .with_safe_defaults()
         .with_client_cert_verifier(client_auth);
-    let cert_file = &mut BufReader::new(File::open(SERVER_CERT.as_str())?);
-    let key_file = &mut BufReader::new(File::open(SERVER_KEY.as_str())?);
+    let cert_file = &mut BufReader::new(File::open(&config.server_cert)?);
+    let key_file = &mut BufReader::new(File::open(&config.server_key)?);
     let cert_chain = rustls_pemfile::certs(cert_file)
         .unwrap()
         .into_iter()
@@ -95,7 +82,9 @@ pub fn create_tls_server_config() -> Result<ServerConfig, std::io::Error> {
             .map(PrivateKey)
             .collect();
     }
-    Ok(config.with_single_cert(cert_chain, keys.remove(0)).unwrap())
+    Ok(server_config
+        .with_single_cert(cert_chain, keys.remove(0))
+        .unwrap())
 }
 
 #[cfg(test)]
@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A file that is provided to users as part of a challenge. The contents of the file are stored in
+/// the blob directory under the attachment's id.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "attachments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub challenge_id: i64,
+    /// The file name presented to users when downloading the attachment.
+    pub name:         String,
+    pub content_type: String,
+    /// The size of the file in bytes.
+    pub size:         i64,
+    /// The hex encoded SHA-256 digest of the file.
+    pub sha256:       String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::challenge::Entity",
+        from = "Column::ChallengeId",
+        to = "super::challenge::Column::Id"
+    )]
+    Challenge,
+}
+
+impl Related<super::challenge::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Challenge.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
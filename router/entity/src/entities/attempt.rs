@@ -0,0 +1,59 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum AttemptOutcome {
+    /// The flag was correct and a submission was recorded.
+    #[serde(rename = "correct")]
+    #[sea_orm(num_value = 0)]
+    Correct,
+    /// The flag was rejected. The reason is stored in `reason`.
+    #[serde(rename = "incorrect")]
+    #[sea_orm(num_value = 1)]
+    Incorrect,
+    /// The flag was correct but the user had already submitted it.
+    #[serde(rename = "duplicate")]
+    #[sea_orm(num_value = 2)]
+    Duplicate,
+    /// The flag was correct but was submitted after the deadline and late submissions are
+    /// rejected.
+    #[serde(rename = "late")]
+    #[sea_orm(num_value = 3)]
+    Late,
+}
+
+/// A single flag submission attempt, regardless of whether it was successful.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "attempts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub user_id:      i64,
+    /// The id of the flag the attempt was made against. This may not refer to an existing flag.
+    #[sea_orm(indexed)]
+    pub flag_id:      String,
+    /// The submitted value. Depending on configuration, this is the raw value, a hash of it or
+    /// nothing at all.
+    pub value:        Option<String>,
+    pub outcome:      AttemptOutcome,
+    /// The numbered reason returned to the user when the flag was rejected. Empty if the flag does
+    /// not exist.
+    pub reason:       Option<i32>,
+    #[sea_orm(indexed)]
+    pub attempt_time: chrono::DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::incident::Entity")]
+    Incident,
+}
+
+impl Related<super::incident::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Incident.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
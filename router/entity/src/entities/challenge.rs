@@ -1,11 +1,52 @@
+use chrono::Utc;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum Difficulty {
+    #[serde(rename = "easy")]
+    #[sea_orm(num_value = 0)]
+    Easy,
+    #[serde(rename = "medium")]
+    #[sea_orm(num_value = 1)]
+    Medium,
+    #[serde(rename = "hard")]
+    #[sea_orm(num_value = 2)]
+    Hard,
+}
+
+/// What happens to flags that are submitted after their submission deadline.
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum LatePolicy {
+    /// Late submissions are rejected.
+    #[serde(rename = "reject")]
+    #[sea_orm(num_value = 0)]
+    Reject,
+    /// Late submissions are accepted, but `late_penalty` percent of the flag's value is deducted.
+    #[serde(rename = "penalty")]
+    #[sea_orm(num_value = 1)]
+    Penalty,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "challenges")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
-    pub id: i64,
+    pub id:           i64,
+    /// Whether flags in this challenge are solved once per team rather than once per user.
+    pub team_mode:    bool,
+    /// A markdown description of the challenge.
+    pub description:  String,
+    pub author:       Option<String>,
+    pub difficulty:   Option<Difficulty>,
+    /// The time after which flags for this challenge are late, unless the flag has its own
+    /// deadline.
+    pub deadline:     Option<chrono::DateTime<Utc>>,
+    pub late_policy:  LatePolicy,
+    /// The percentage of a flag's value that is deducted for late submissions.
+    pub late_penalty: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -14,6 +55,14 @@ pub enum Relation {
     Service,
     #[sea_orm(has_many = "super::flag::Entity")]
     Flag,
+    #[sea_orm(has_many = "super::prerequisite::Entity")]
+    Prerequisite,
+    #[sea_orm(has_many = "super::hint::Entity")]
+    Hint,
+    #[sea_orm(has_many = "super::challenge_tag::Entity")]
+    ChallengeTag,
+    #[sea_orm(has_many = "super::attachment::Entity")]
+    Attachment,
 }
 
 impl Related<super::service::Entity> for Entity {
@@ -24,4 +73,20 @@ impl Related<super::flag::Entity> for Entity {
     fn to() -> RelationDef { Relation::Flag.def() }
 }
 
+impl Related<super::prerequisite::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Prerequisite.def() }
+}
+
+impl Related<super::hint::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Hint.def() }
+}
+
+impl Related<super::challenge_tag::Entity> for Entity {
+    fn to() -> RelationDef { Relation::ChallengeTag.def() }
+}
+
+impl Related<super::attachment::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Attachment.def() }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A free-form label attached to a challenge, such as `sqli` or `xss`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "challenge_tags")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub challenge_id: i64,
+    pub tag:          String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::challenge::Entity",
+        from = "Column::ChallengeId",
+        to = "super::challenge::Column::Id"
+    )]
+    Challenge,
+}
+
+impl Related<super::challenge::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Challenge.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,26 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An individual access window for a user, such as an extension granted through special
+/// consideration. Applies to either a whole challenge or a single service.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "deadline_overrides")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub user_id:      i64,
+    pub challenge_id: Option<i64>,
+    pub service_id:   Option<i64>,
+    /// Replaces the service's `not_before` for this user. If empty, the service's value is used.
+    pub not_before:   Option<chrono::DateTime<Utc>>,
+    /// Replaces the service's `not_after` for this user. If empty, the service's value is used.
+    pub not_after:    Option<chrono::DateTime<Utc>>,
+    pub reason:       Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,3 +1,4 @@
+use chrono::Utc;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -12,20 +13,51 @@ pub enum FlagType {
     Dynamic,
 }
 
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum ScoringMode {
+    /// The flag is always worth `points`.
+    #[serde(rename = "static")]
+    #[sea_orm(num_value = 0)]
+    Static,
+    /// The flag starts at `points` and decays towards `minimum_points` as more users solve it.
+    #[serde(rename = "decay")]
+    #[sea_orm(num_value = 1)]
+    Decay,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "flags")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
-    pub id:           String,
+    pub id:                 String,
     #[sea_orm(indexed)]
-    pub challenge_id: i64,
+    pub challenge_id:       i64,
     #[sea_orm(indexed)]
-    pub category_id:  i64,
+    pub category_id:        i64,
     #[sea_orm(indexed)]
-    pub flag:         String,
-    pub flag_type:    FlagType,
-    pub points:       i32,
-    pub display_name: String,
+    pub flag:               String,
+    pub flag_type:          FlagType,
+    /// The number of points the flag is worth. For decaying flags, this is the initial value.
+    pub points:             i32,
+    pub display_name:       String,
+    pub scoring_mode:       ScoringMode,
+    /// The lowest value a decaying flag can reach.
+    pub minimum_points:     Option<i32>,
+    /// The number of solves after which a decaying flag reaches `minimum_points`.
+    pub decay:              Option<i32>,
+    /// The time after which submissions for this flag are late. Takes precedence over the
+    /// challenge's deadline.
+    pub deadline:           Option<chrono::DateTime<Utc>>,
+    /// How late submissions are handled if the flag has its own deadline.
+    pub late_policy:        super::challenge::LatePolicy,
+    /// The percentage of the flag's value that is deducted for late submissions if the flag has
+    /// its own deadline.
+    pub late_penalty:       i32,
+    /// Bonus points awarded to the first user to solve the flag.
+    pub first_blood_bonus:  i32,
+    pub second_blood_bonus: i32,
+    pub third_blood_bonus:  i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
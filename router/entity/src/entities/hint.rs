@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A hint for a challenge or one of its flags. Users may unlock hints in exchange for points.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "hints")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub challenge_id: i64,
+    /// The flag that this hint is for. If empty, the hint is for the whole challenge.
+    pub flag_id:      Option<String>,
+    pub content:      String,
+    /// The number of points deducted from a user's score when they unlock the hint.
+    pub cost:         i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::challenge::Entity",
+        from = "Column::ChallengeId",
+        to = "super::challenge::Column::Id"
+    )]
+    Challenge,
+    #[sea_orm(has_many = "super::hint_unlock::Entity")]
+    HintUnlock,
+}
+
+impl Related<super::challenge::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Challenge.def() }
+}
+
+impl Related<super::hint_unlock::Entity> for Entity {
+    fn to() -> RelationDef { Relation::HintUnlock.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
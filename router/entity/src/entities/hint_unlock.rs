@@ -0,0 +1,32 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A record of a user unlocking a hint.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "hint_unlocks")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:          i64,
+    #[sea_orm(indexed)]
+    pub hint_id:     i64,
+    #[sea_orm(indexed)]
+    pub user_id:     i64,
+    pub unlock_time: chrono::DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::hint::Entity",
+        from = "Column::HintId",
+        to = "super::hint::Column::Id"
+    )]
+    Hint,
+}
+
+impl Related<super::hint::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Hint.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
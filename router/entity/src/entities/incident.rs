@@ -0,0 +1,38 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A dynamic flag that was generated for one user and submitted by another.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "incidents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:            i64,
+    #[sea_orm(indexed)]
+    pub flag_id:       String,
+    /// The user that submitted the flag.
+    #[sea_orm(indexed)]
+    pub submitter_id:  i64,
+    /// The user that the flag was generated for, or a member of the team it was generated for.
+    #[sea_orm(indexed)]
+    pub owner_id:      i64,
+    /// The attempt in which the flag was submitted.
+    pub attempt_id:    i64,
+    pub incident_time: chrono::DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::attempt::Entity",
+        from = "Column::AttemptId",
+        to = "super::attempt::Column::Id"
+    )]
+    Attempt,
+}
+
+impl Related<super::attempt::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Attempt.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
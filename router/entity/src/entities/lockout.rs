@@ -0,0 +1,25 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Tracks consecutive failed submissions by a user for a flag.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "lockouts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:           i64,
+    #[sea_orm(indexed)]
+    pub user_id:      i64,
+    #[sea_orm(indexed)]
+    pub flag_id:      String,
+    /// The number of failed submissions since the user last submitted the flag correctly or the
+    /// lockout was reset.
+    pub failures:     i32,
+    /// The time before which the user may not submit the flag again.
+    pub locked_until: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
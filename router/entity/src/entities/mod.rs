@@ -1,6 +1,19 @@
+pub mod attachment;
+pub mod attempt;
+pub mod audit_log;
 pub mod category;
 pub mod challenge;
+pub mod challenge_tag;
+pub mod deadline_override;
 pub mod flag;
+pub mod hint;
+pub mod hint_unlock;
+pub mod incident;
+pub mod lockout;
+pub mod prerequisite;
 pub mod service;
 pub mod submission;
+pub mod team;
+pub mod team_member;
 pub mod user;
+pub mod webhook_delivery;
@@ -0,0 +1,48 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum PrerequisiteKind {
+    /// The user must have solved `required_flag_id`.
+    #[serde(rename = "flag")]
+    #[sea_orm(num_value = 0)]
+    SolveFlag,
+    /// The user must have at least `required_points` points in `required_category_id`.
+    #[serde(rename = "category_points")]
+    #[sea_orm(num_value = 1)]
+    CategoryPoints,
+}
+
+/// A rule that must be satisfied before a user can access a challenge or flag.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "prerequisites")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:                   i64,
+    /// The challenge that this rule belongs to.
+    #[sea_orm(indexed)]
+    pub challenge_id:         i64,
+    /// The flag that is locked by this rule. If empty, the whole challenge is locked.
+    pub locked_flag_id:       Option<String>,
+    pub kind:                 PrerequisiteKind,
+    pub required_flag_id:     Option<String>,
+    pub required_category_id: Option<i64>,
+    pub required_points:      Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::challenge::Entity",
+        from = "Column::ChallengeId",
+        to = "super::challenge::Column::Id"
+    )]
+    Challenge,
+}
+
+impl Related<super::challenge::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Challenge.def() }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
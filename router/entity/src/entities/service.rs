@@ -22,6 +22,11 @@ pub struct Model {
     /// Not after: time after which the service is inaccessible to students.
     #[sea_orm(indexed)]
     pub not_after:         Option<chrono::DateTime<Utc>>,
+    /// The container image that each user is given their own instance of. Users share the
+    /// service at `internal_hostname` if this is not set.
+    pub image:             Option<String>,
+    /// The port that instances of the image listen on.
+    pub port:              Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,6 +11,16 @@ pub struct Model {
     pub flag_id:         String,
     #[sea_orm(indexed)]
     pub submission_time: chrono::DateTime<Utc>,
+    /// The team the user was a member of when the flag was submitted, if the flag's challenge is
+    /// in team mode. Each team can only solve a flag once.
+    pub team_id:         Option<i64>,
+    /// How many seconds after the deadline the flag was submitted. Empty for submissions that
+    /// were on time.
+    pub late_seconds:    Option<i64>,
+    /// The percentage of the flag's value that was deducted because the submission was late.
+    pub late_penalty:    i32,
+    /// Whether this was the first, second or third solve of the flag.
+    pub blood:           Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
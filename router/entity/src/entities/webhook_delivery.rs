@@ -0,0 +1,30 @@
+use chrono::Utc;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A webhook call for a platform event, including any retries.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_deliveries")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false, unique, indexed)]
+    pub id:             i64,
+    #[sea_orm(indexed)]
+    pub event:          String,
+    pub url:            String,
+    /// The JSON body that was posted.
+    pub payload:        String,
+    pub attempts:       i32,
+    /// The status code of the last response, if the endpoint responded at all.
+    pub status_code:    Option<i32>,
+    pub success:        bool,
+    /// Why the last attempt failed.
+    pub error:          Option<String>,
+    #[sea_orm(indexed)]
+    pub started_time:   chrono::DateTime<Utc>,
+    pub completed_time: chrono::DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,71 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+/// Drop columns from a table, one statement per column.
+///
+/// sea-query refuses to generate `DROP COLUMN` for SQLite, even though SQLite has supported it
+/// since 3.35, so the statement is written by hand there. The columns must not be part of an index.
+pub(crate) async fn drop_columns<T, C>(
+    manager: &SchemaManager<'_>,
+    table: T,
+    columns: &[C],
+) -> Result<(), DbErr>
+where
+    T: Iden + Copy + 'static,
+    C: Iden + Copy + 'static,
+{
+    for column in columns {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                let sql = format!(
+                    r#"ALTER TABLE "{}" DROP COLUMN "{}""#,
+                    table.to_string(),
+                    column.to_string()
+                );
+                manager
+                    .get_connection()
+                    .execute(Statement::from_string(DbBackend::Sqlite, sql))
+                    .await?;
+            },
+            _ => {
+                manager
+                    .alter_table(Table::alter().table(table).drop_column(*column).to_owned())
+                    .await?;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Drop an index from a table.
+///
+/// sea-query always writes `DROP INDEX ... ON table` for SQLite, which SQLite does not accept, so
+/// the statement is written by hand there.
+pub(crate) async fn drop_index<T>(
+    manager: &SchemaManager<'_>,
+    table: T,
+    name: &str,
+) -> Result<(), DbErr>
+where
+    T: Iden + 'static,
+{
+    match manager.get_database_backend() {
+        DbBackend::Sqlite => {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DbBackend::Sqlite,
+                    format!(r#"DROP INDEX "{name}""#),
+                ))
+                .await?;
+            Ok(())
+        },
+        _ => {
+            manager
+                .drop_index(Index::drop().table(table).name(name).to_owned())
+                .await
+        },
+    }
+}
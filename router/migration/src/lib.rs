@@ -1,5 +1,6 @@
 pub use sea_orm_migration::prelude::*;
 
+mod helpers;
 mod m20220101_000001_create_table;
 mod m20220101_000002_create_table;
 mod m20220101_000003_create_table;
@@ -9,6 +10,37 @@ mod m20220101_000006_create_table;
 mod m20220101_000007_create_index;
 mod m20220101_000008_create_index;
 mod m20220101_000009_create_index;
+mod m20220101_000010_alter_table;
+mod m20220101_000011_create_table;
+mod m20220101_000012_create_index;
+mod m20220101_000013_create_table;
+mod m20220101_000014_create_table;
+mod m20220101_000015_create_index;
+mod m20220101_000016_create_table;
+mod m20220101_000017_create_table;
+mod m20220101_000018_alter_table;
+mod m20220101_000019_alter_table;
+mod m20220101_000020_create_table;
+mod m20220101_000021_create_table;
+mod m20220101_000022_create_table;
+mod m20220101_000023_create_index;
+mod m20220101_000024_alter_table;
+mod m20220101_000025_create_table;
+mod m20220101_000026_create_table;
+mod m20220101_000027_create_table;
+mod m20220101_000028_alter_table;
+mod m20220101_000029_alter_table;
+mod m20220101_000030_alter_table;
+mod m20220101_000031_alter_table;
+mod m20220101_000032_create_index;
+mod m20220101_000033_alter_table;
+mod m20220101_000034_create_table;
+mod m20220101_000035_create_table;
+mod m20220101_000036_alter_table;
+mod m20220101_000037_create_index;
+
+#[cfg(test)]
+mod tests;
 
 pub struct Migrator;
 
@@ -25,6 +57,34 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000007_create_index::Migration),
             Box::new(m20220101_000008_create_index::Migration),
             Box::new(m20220101_000009_create_index::Migration),
+            Box::new(m20220101_000010_alter_table::Migration),
+            Box::new(m20220101_000011_create_table::Migration),
+            Box::new(m20220101_000012_create_index::Migration),
+            Box::new(m20220101_000013_create_table::Migration),
+            Box::new(m20220101_000014_create_table::Migration),
+            Box::new(m20220101_000015_create_index::Migration),
+            Box::new(m20220101_000016_create_table::Migration),
+            Box::new(m20220101_000017_create_table::Migration),
+            Box::new(m20220101_000018_alter_table::Migration),
+            Box::new(m20220101_000019_alter_table::Migration),
+            Box::new(m20220101_000020_create_table::Migration),
+            Box::new(m20220101_000021_create_table::Migration),
+            Box::new(m20220101_000022_create_table::Migration),
+            Box::new(m20220101_000023_create_index::Migration),
+            Box::new(m20220101_000024_alter_table::Migration),
+            Box::new(m20220101_000025_create_table::Migration),
+            Box::new(m20220101_000026_create_table::Migration),
+            Box::new(m20220101_000027_create_table::Migration),
+            Box::new(m20220101_000028_alter_table::Migration),
+            Box::new(m20220101_000029_alter_table::Migration),
+            Box::new(m20220101_000030_alter_table::Migration),
+            Box::new(m20220101_000031_alter_table::Migration),
+            Box::new(m20220101_000032_create_index::Migration),
+            Box::new(m20220101_000033_alter_table::Migration),
+            Box::new(m20220101_000034_create_table::Migration),
+            Box::new(m20220101_000035_create_table::Migration),
+            Box::new(m20220101_000036_alter_table::Migration),
+            Box::new(m20220101_000037_create_index::Migration),
         ]
     }
 }
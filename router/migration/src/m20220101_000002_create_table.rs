@@ -17,7 +17,7 @@ impl MigrationTrait for Migration {
                     .if_not_exists()
                     .col(
                         ColumnDef::new(user::Column::Id)
-                            .integer()
+                            .big_integer()
                             .not_null()
                             .primary_key()
                             .unique_key(),
@@ -26,4 +26,10 @@ impl MigrationTrait for Migration {
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(user::Entity).to_owned())
+            .await
+    }
 }
@@ -17,7 +17,7 @@ impl MigrationTrait for Migration {
                     .if_not_exists()
                     .col(
                         ColumnDef::new(category::Column::Id)
-                            .integer()
+                            .big_integer()
                             .not_null()
                             .primary_key()
                             .unique_key(),
@@ -32,4 +32,10 @@ impl MigrationTrait for Migration {
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(category::Entity).to_owned())
+            .await
+    }
 }
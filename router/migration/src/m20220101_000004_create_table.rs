@@ -17,7 +17,7 @@ impl MigrationTrait for Migration {
                     .if_not_exists()
                     .col(
                         ColumnDef::new(service::Column::Id)
-                            .integer()
+                            .big_integer()
                             .not_null()
                             .primary_key()
                             .unique_key(),
@@ -30,7 +30,7 @@ impl MigrationTrait for Migration {
                     )
                     .col(
                         ColumnDef::new(service::Column::ChallengeId)
-                            .integer()
+                            .big_integer()
                             .not_null(),
                     )
                     .foreign_key(
@@ -40,7 +40,7 @@ impl MigrationTrait for Migration {
                     )
                     .col(
                         ColumnDef::new(service::Column::CategoryId)
-                            .integer()
+                            .big_integer()
                             .not_null(),
                     )
                     .foreign_key(
@@ -59,10 +59,16 @@ impl MigrationTrait for Migration {
                             .string()
                             .not_null(),
                     )
-                    .col(ColumnDef::new(service::Column::NotBefore).timestamp())
-                    .col(ColumnDef::new(service::Column::NotAfter).timestamp())
+                    .col(ColumnDef::new(service::Column::NotBefore).timestamp_with_time_zone())
+                    .col(ColumnDef::new(service::Column::NotAfter).timestamp_with_time_zone())
                     .to_owned(),
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(service::Entity).to_owned())
+            .await
+    }
 }
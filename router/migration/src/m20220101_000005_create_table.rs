@@ -30,7 +30,7 @@ impl MigrationTrait for Migration {
                     )
                     .col(
                         ColumnDef::new(flag::Column::ChallengeId)
-                            .integer()
+                            .big_integer()
                             .not_null(),
                     )
                     .foreign_key(
@@ -40,7 +40,7 @@ impl MigrationTrait for Migration {
                     )
                     .col(
                         ColumnDef::new(flag::Column::CategoryId)
-                            .integer()
+                            .big_integer()
                             .not_null(),
                     )
                     .foreign_key(
@@ -59,4 +59,10 @@ impl MigrationTrait for Migration {
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(flag::Entity).to_owned())
+            .await
+    }
 }
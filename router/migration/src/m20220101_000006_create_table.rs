@@ -17,14 +17,14 @@ impl MigrationTrait for Migration {
                     .if_not_exists()
                     .col(
                         ColumnDef::new(submission::Column::Id)
-                            .integer()
+                            .big_integer()
                             .not_null()
                             .primary_key()
                             .unique_key(),
                     )
                     .col(
                         ColumnDef::new(submission::Column::UserId)
-                            .integer()
+                            .big_integer()
                             .not_null(),
                     )
                     .foreign_key(
@@ -44,11 +44,17 @@ impl MigrationTrait for Migration {
                     )
                     .col(
                         ColumnDef::new(submission::Column::SubmissionTime)
-                            .integer()
+                            .timestamp_with_time_zone()
                             .not_null(),
                     )
                     .to_owned(),
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(submission::Entity).to_owned())
+            .await
+    }
 }
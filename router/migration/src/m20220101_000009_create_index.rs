@@ -1,6 +1,8 @@
 use router_entity::service;
 use sea_orm_migration::prelude::*;
 
+use crate::helpers::drop_index;
+
 pub struct Migration;
 
 impl MigrationName for Migration {
@@ -20,4 +22,8 @@ impl MigrationTrait for Migration {
             )
             .await
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(manager, service::Entity, "idx-services-notafter").await
+    }
 }
@@ -0,0 +1,61 @@
+use router_entity::flag;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_columns;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000010_alter_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per `ALTER TABLE` statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(flag::Entity)
+                    .add_column(
+                        ColumnDef::new(flag::Column::ScoringMode)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(flag::Entity)
+                    .add_column(ColumnDef::new(flag::Column::MinimumPoints).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(flag::Entity)
+                    .add_column(ColumnDef::new(flag::Column::Decay).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_columns(
+            manager,
+            flag::Entity,
+            &[
+                flag::Column::Decay,
+                flag::Column::MinimumPoints,
+                flag::Column::ScoringMode,
+            ],
+        )
+        .await
+    }
+}
@@ -0,0 +1,53 @@
+use router_entity::attempt;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000011_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(attempt::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(attempt::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(attempt::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(attempt::Column::FlagId).string().not_null())
+                    .col(ColumnDef::new(attempt::Column::Value).string())
+                    .col(
+                        ColumnDef::new(attempt::Column::Outcome)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(attempt::Column::Reason).integer())
+                    .col(
+                        ColumnDef::new(attempt::Column::AttemptTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(attempt::Entity).to_owned())
+            .await
+    }
+}
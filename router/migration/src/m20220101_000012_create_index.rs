@@ -0,0 +1,30 @@
+use router_entity::attempt;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_index;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000012_create_index" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .table(attempt::Entity)
+                    .name("idx-attempts-userid-flagid")
+                    .col(attempt::Column::UserId)
+                    .col(attempt::Column::FlagId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(manager, attempt::Entity, "idx-attempts-userid-flagid").await
+    }
+}
@@ -0,0 +1,61 @@
+use router_entity::{attempt, incident};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000013_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(incident::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(incident::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(incident::Column::FlagId).string().not_null())
+                    .col(
+                        ColumnDef::new(incident::Column::SubmitterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(incident::Column::OwnerId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(incident::Column::AttemptId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(attempt::Entity, attempt::Column::Id)
+                            .from_col(incident::Column::AttemptId),
+                    )
+                    .col(
+                        ColumnDef::new(incident::Column::IncidentTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(incident::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use router_entity::lockout;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000014_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(lockout::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(lockout::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(lockout::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(lockout::Column::FlagId).string().not_null())
+                    .col(
+                        ColumnDef::new(lockout::Column::Failures)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(lockout::Column::LockedUntil).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(lockout::Entity).to_owned())
+            .await
+    }
+}
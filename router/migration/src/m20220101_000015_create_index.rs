@@ -0,0 +1,31 @@
+use router_entity::lockout;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_index;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000015_create_index" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .table(lockout::Entity)
+                    .name("idx-lockouts-userid-flagid")
+                    .col(lockout::Column::UserId)
+                    .col(lockout::Column::FlagId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(manager, lockout::Entity, "idx-lockouts-userid-flagid").await
+    }
+}
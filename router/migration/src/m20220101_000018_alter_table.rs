@@ -0,0 +1,33 @@
+use router_entity::challenge;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_columns;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000018_alter_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(challenge::Entity)
+                    .add_column(
+                        ColumnDef::new(challenge::Column::TeamMode)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_columns(manager, challenge::Entity, &[challenge::Column::TeamMode]).await
+    }
+}
@@ -0,0 +1,28 @@
+use router_entity::submission;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_columns;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000019_alter_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(submission::Entity)
+                    .add_column(ColumnDef::new(submission::Column::TeamId).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_columns(manager, submission::Entity, &[submission::Column::TeamId]).await
+    }
+}
@@ -0,0 +1,54 @@
+use router_entity::{challenge, prerequisite};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000020_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(prerequisite::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(prerequisite::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(prerequisite::Column::ChallengeId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(challenge::Entity, challenge::Column::Id)
+                            .from_col(prerequisite::Column::ChallengeId),
+                    )
+                    .col(ColumnDef::new(prerequisite::Column::LockedFlagId).string())
+                    .col(
+                        ColumnDef::new(prerequisite::Column::Kind)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(prerequisite::Column::RequiredFlagId).string())
+                    .col(ColumnDef::new(prerequisite::Column::RequiredCategoryId).big_integer())
+                    .col(ColumnDef::new(prerequisite::Column::RequiredPoints).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(prerequisite::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,53 @@
+use router_entity::{challenge, hint};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000021_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(hint::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(hint::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(hint::Column::ChallengeId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(challenge::Entity, challenge::Column::Id)
+                            .from_col(hint::Column::ChallengeId),
+                    )
+                    .col(ColumnDef::new(hint::Column::FlagId).string())
+                    .col(ColumnDef::new(hint::Column::Content).string().not_null())
+                    .col(
+                        ColumnDef::new(hint::Column::Cost)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(hint::Entity).to_owned())
+            .await
+    }
+}
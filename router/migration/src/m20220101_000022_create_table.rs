@@ -0,0 +1,55 @@
+use router_entity::{hint, hint_unlock};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000022_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(hint_unlock::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(hint_unlock::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(hint_unlock::Column::HintId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(hint::Entity, hint::Column::Id)
+                            .from_col(hint_unlock::Column::HintId),
+                    )
+                    .col(
+                        ColumnDef::new(hint_unlock::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(hint_unlock::Column::UnlockTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(hint_unlock::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use router_entity::hint_unlock;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_index;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000023_create_index" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .table(hint_unlock::Entity)
+                    .name("idx-hint-unlocks-hintid-userid")
+                    .col(hint_unlock::Column::HintId)
+                    .col(hint_unlock::Column::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(
+            manager,
+            hint_unlock::Entity,
+            "idx-hint-unlocks-hintid-userid",
+        )
+        .await
+    }
+}
@@ -0,0 +1,50 @@
+use router_entity::{challenge, challenge_tag};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000025_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(challenge_tag::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(challenge_tag::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(challenge_tag::Column::ChallengeId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(challenge::Entity, challenge::Column::Id)
+                            .from_col(challenge_tag::Column::ChallengeId),
+                    )
+                    .col(
+                        ColumnDef::new(challenge_tag::Column::Tag)
+                            .string()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(challenge_tag::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,61 @@
+use router_entity::{attachment, challenge};
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000026_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(attachment::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(attachment::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(attachment::Column::ChallengeId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .to(challenge::Entity, challenge::Column::Id)
+                            .from_col(attachment::Column::ChallengeId),
+                    )
+                    .col(ColumnDef::new(attachment::Column::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(attachment::Column::ContentType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(attachment::Column::Size)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(attachment::Column::Sha256)
+                            .string()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(attachment::Entity).to_owned())
+            .await
+    }
+}
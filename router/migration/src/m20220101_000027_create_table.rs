@@ -0,0 +1,51 @@
+use router_entity::deadline_override;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000027_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(deadline_override::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(deadline_override::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(deadline_override::Column::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(deadline_override::Column::ChallengeId).big_integer())
+                    .col(ColumnDef::new(deadline_override::Column::ServiceId).big_integer())
+                    .col(
+                        ColumnDef::new(deadline_override::Column::NotBefore)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(deadline_override::Column::NotAfter)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(deadline_override::Column::Reason).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(deadline_override::Entity).to_owned())
+            .await
+    }
+}
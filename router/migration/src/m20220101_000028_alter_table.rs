@@ -0,0 +1,68 @@
+use router_entity::challenge;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_columns;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000028_alter_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per `ALTER TABLE` statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(challenge::Entity)
+                    .add_column(
+                        ColumnDef::new(challenge::Column::Deadline).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(challenge::Entity)
+                    .add_column(
+                        ColumnDef::new(challenge::Column::LatePolicy)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(challenge::Entity)
+                    .add_column(
+                        ColumnDef::new(challenge::Column::LatePenalty)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_columns(
+            manager,
+            challenge::Entity,
+            &[
+                challenge::Column::LatePenalty,
+                challenge::Column::LatePolicy,
+                challenge::Column::Deadline,
+            ],
+        )
+        .await
+    }
+}
@@ -0,0 +1,51 @@
+use router_entity::submission;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_columns;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000030_alter_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports a single column per `ALTER TABLE` statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(submission::Entity)
+                    .add_column(ColumnDef::new(submission::Column::LateSeconds).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(submission::Entity)
+                    .add_column(
+                        ColumnDef::new(submission::Column::LatePenalty)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_columns(
+            manager,
+            submission::Entity,
+            &[
+                submission::Column::LatePenalty,
+                submission::Column::LateSeconds,
+            ],
+        )
+        .await
+    }
+}
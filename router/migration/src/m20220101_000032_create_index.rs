@@ -0,0 +1,32 @@
+use router_entity::submission;
+use sea_orm_migration::prelude::*;
+
+use crate::helpers::drop_index;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000032_create_index" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Each blood can only be awarded once per flag
+        manager
+            .create_index(
+                Index::create()
+                    .table(submission::Entity)
+                    .name("idx-submissions-flagid-blood")
+                    .col(submission::Column::FlagId)
+                    .col(submission::Column::Blood)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(manager, submission::Entity, "idx-submissions-flagid-blood").await
+    }
+}
@@ -0,0 +1,72 @@
+use router_entity::webhook_delivery;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000034_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(webhook_delivery::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Event)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Url)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Payload)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Attempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(webhook_delivery::Column::StatusCode).integer())
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Success)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(webhook_delivery::Column::Error).string())
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::StartedTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::CompletedTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(webhook_delivery::Entity).to_owned())
+            .await
+    }
+}
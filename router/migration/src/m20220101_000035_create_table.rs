@@ -0,0 +1,53 @@
+use router_entity::audit_log;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000035_create_table" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(audit_log::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(audit_log::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(audit_log::Column::Actor).string().not_null())
+                    .col(
+                        ColumnDef::new(audit_log::Column::Action)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(audit_log::Column::Target)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(audit_log::Column::Before).text())
+                    .col(ColumnDef::new(audit_log::Column::After).text())
+                    .col(
+                        ColumnDef::new(audit_log::Column::Timestamp)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(audit_log::Entity).to_owned())
+            .await
+    }
+}
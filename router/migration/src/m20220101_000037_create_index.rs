@@ -0,0 +1,67 @@
+use router_entity::submission;
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, EntityName, Statement},
+};
+
+use crate::helpers::drop_index;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str { "m20220101_000037_create_index" }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let table = submission::Entity.table_name();
+
+        // Concurrent submissions could previously both be stored, so only the first of any
+        // duplicates is kept
+        conn.execute(Statement::from_string(
+            backend,
+            format!(
+                r#"DELETE FROM "{table}" WHERE "id" NOT IN (SELECT MIN("id") FROM "{table}" GROUP BY "flag_id", "user_id")"#
+            ),
+        ))
+        .await?;
+        conn.execute(Statement::from_string(
+            backend,
+            format!(
+                r#"DELETE FROM "{table}" WHERE "team_id" IS NOT NULL AND "id" NOT IN (SELECT MIN("id") FROM "{table}" WHERE "team_id" IS NOT NULL GROUP BY "flag_id", "team_id")"#
+            ),
+        ))
+        .await?;
+
+        // Each user, and each team in team mode, can only solve a flag once
+        manager
+            .create_index(
+                Index::create()
+                    .table(submission::Entity)
+                    .name("idx-submissions-flagid-userid")
+                    .col(submission::Column::FlagId)
+                    .col(submission::Column::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        // sea-query cannot create partial indexes, but both backends accept the same statement
+        conn.execute(Statement::from_string(
+            backend,
+            format!(
+                r#"CREATE UNIQUE INDEX "idx-submissions-flagid-teamid" ON "{table}" ("flag_id", "team_id") WHERE "team_id" IS NOT NULL"#
+            ),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_index(manager, submission::Entity, "idx-submissions-flagid-teamid").await?;
+        drop_index(manager, submission::Entity, "idx-submissions-flagid-userid").await
+    }
+}
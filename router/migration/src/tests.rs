@@ -0,0 +1,108 @@
+use chrono::{TimeZone, Utc};
+use router_entity::{audit_log, lockout, user};
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ActiveModelTrait, Database, EntityTrait, Set},
+};
+
+use crate::Migrator;
+
+/// Ids are snowflakes, which do not fit in 32 bits.
+const SNOWFLAKE: i64 = 3_444_884_897_726_725;
+
+/// Apply every migration to an empty database and check that snowflake ids and timestamps survive
+/// a round trip.
+async fn round_trip(uri: &str) {
+    let conn = Database::connect(uri).await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    // Postgres stores timestamps with microsecond precision
+    let time = Utc.timestamp(1_700_000_000, 123_456_000);
+
+    user::ActiveModel { id: Set(SNOWFLAKE) }
+        .insert(&conn)
+        .await
+        .unwrap();
+    lockout::ActiveModel {
+        id:           Set(SNOWFLAKE + 1),
+        user_id:      Set(SNOWFLAKE),
+        flag_id:      Set("f1".to_string()),
+        failures:     Set(3),
+        locked_until: Set(Some(time)),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    audit_log::ActiveModel {
+        id:        Set(SNOWFLAKE + 2),
+        actor:     Set("_scpU1@unsw.scp.platform".to_string()),
+        action:    Set("test".to_string()),
+        target:    Set(SNOWFLAKE.to_string()),
+        before:    Set(None),
+        after:     Set(Some("{}".to_string())),
+        timestamp: Set(time),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    let lockout = lockout::Entity::find_by_id(SNOWFLAKE + 1)
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lockout.user_id, SNOWFLAKE);
+    assert_eq!(lockout.locked_until, Some(time));
+
+    let entry = audit_log::Entity::find_by_id(SNOWFLAKE + 2)
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.timestamp, time);
+
+    // The audit log is append-only
+    let mut entry: audit_log::ActiveModel = entry.into();
+    entry.action = Set("changed".to_string());
+    assert!(entry.update(&conn).await.is_err());
+}
+
+/// Roll every migration back and apply them again.
+async fn roll_back(uri: &str) {
+    let conn = Database::connect(uri).await.unwrap();
+    Migrator::up(&conn, None).await.unwrap();
+
+    Migrator::down(&conn, None).await.unwrap();
+    assert!(Migrator::get_applied_migrations(&conn)
+        .await
+        .unwrap()
+        .is_empty());
+    let manager = SchemaManager::new(&conn);
+    assert!(!manager.has_table("challenge").await.unwrap());
+
+    Migrator::up(&conn, None).await.unwrap();
+    assert!(Migrator::get_pending_migrations(&conn)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn migrations_apply_on_sqlite() {
+    round_trip("sqlite::memory:").await;
+    roll_back("sqlite::memory:").await;
+}
+
+/// Runs against the database in `TEST_POSTGRES_URL`, which is wiped. Skipped if it is not set.
+#[cfg(feature = "postgres")]
+#[tokio::test]
+async fn migrations_apply_on_postgres() {
+    match std::env::var("TEST_POSTGRES_URL") {
+        Ok(uri) => {
+            round_trip(&uri).await;
+            roll_back(&uri).await;
+        },
+        Err(_) => eprintln!("TEST_POSTGRES_URL is not set, skipping"),
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use crate::config;
+
+/// Get the location in the blob directory where the contents of an attachment are stored.
+pub(crate) fn blob_path(attachment_id: i64) -> PathBuf {
+    PathBuf::from(config::get().attachment_dir.as_str()).join(attachment_id.to_string())
+}
+
+/// Get the location in the blob directory where the contents of an attachment are written before
+/// it is recorded in the database.
+fn staged_path(attachment_id: i64) -> PathBuf {
+    PathBuf::from(config::get().attachment_dir.as_str()).join(format!("{attachment_id}.staged"))
+}
+
+/// Contents of attachments that have been written to the blob directory, but are not stored under
+/// their ids until the attachments are recorded in the database. Contents that have not been
+/// published are removed when this is dropped, so that a failed upload leaves nothing behind.
+#[derive(Debug, Default)]
+pub(crate) struct StagedBlobs {
+    ids: Vec<i64>,
+}
+
+impl StagedBlobs {
+    /// Write the contents of an attachment, creating the blob directory if it does not exist.
+    pub(crate) async fn write(&mut self, attachment_id: i64, data: &[u8]) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(config::get().attachment_dir.as_str()).await?;
+        self.ids.push(attachment_id);
+        tokio::fs::write(staged_path(attachment_id), data).await
+    }
+
+    /// Store every written attachment under its id. Call once the attachments are recorded.
+    pub(crate) async fn publish(mut self) -> std::io::Result<()> {
+        while let Some(&attachment_id) = self.ids.last() {
+            tokio::fs::rename(staged_path(attachment_id), blob_path(attachment_id)).await?;
+            self.ids.pop();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedBlobs {
+    fn drop(&mut self) {
+        for attachment_id in &self.ids {
+            // The contents may not have been written if writing them failed
+            let _ = std::fs::remove_file(staged_path(*attachment_id));
+        }
+    }
+}
+
+/// Read the contents of an attachment from the blob directory.
+pub(crate) async fn read_blob(attachment_id: i64) -> std::io::Result<Vec<u8>> {
+    tokio::fs::read(blob_path(attachment_id)).await
+}
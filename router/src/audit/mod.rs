@@ -0,0 +1,41 @@
+use chrono::Utc;
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use router_entity::audit_log;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use serde_json::Value;
+
+/// Append an entry to the audit log. Should be called in the same transaction as the change
+/// being audited where possible.
+///
+/// # Params
+///
+/// - `actor`: the `_scpU` id of the user performing the action.
+/// - `before`/`after`: the state of the target before and after the action, if applicable.
+pub(crate) async fn record<C>(
+    conn: &C,
+    actor: &str,
+    action: &str,
+    target: impl ToString,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    audit_log::ActiveModel {
+        id:        Set(IdInstance::next_id()),
+        actor:     Set(actor.to_string()),
+        action:    Set(action.to_string()),
+        target:    Set(target.to_string()),
+        before:    Set(before.map(|v| v.to_string())),
+        after:     Set(after.map(|v| v.to_string())),
+        timestamp: Set(Utc::now()),
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::{fmt, str::FromStr};
+
+use once_cell::sync::OnceCell;
+use service_config::{KeyLength, Loader};
+
+use crate::{instances::BackendKind, keyring::Keyring};
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// How submitted values are stored for flag submission attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AttemptValueMode {
+    Plain,
+    Hash,
+    Redact,
+}
+
+impl FromStr for AttemptValueMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "hash" => Ok(Self::Hash),
+            "redact" => Ok(Self::Redact),
+            _ => Err(format!("expected one of plain, hash or redact, got {s}")),
+        }
+    }
+}
+
+impl fmt::Display for AttemptValueMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Plain => "plain",
+            Self::Hash => "hash",
+            Self::Redact => "redact",
+        })
+    }
+}
+
+/// The router's configuration, loaded once at startup.
+pub(crate) struct Config {
+    /// The contents of the key used to sign and verify intra-service JWTs.
+    pub(crate) jwt_pem:              String,
+    /// The keys used for authenticating dynamic flags.
+    pub(crate) keyring:              Keyring,
+    pub(crate) attempt_value_mode:   AttemptValueMode,
+    /// The directory that challenge attachments are stored in.
+    pub(crate) attachment_dir:       String,
+    /// May contain database credentials, so is redacted by `--print-config`.
+    pub(crate) db_uri:               String,
+    /// Whether pending migrations are applied on startup. Set to `false` when the schema is
+    /// managed with the migration binary instead.
+    pub(crate) auto_migrate:         bool,
+    pub(crate) gaia_addr:            String,
+    /// How long roles fetched from gaia are used for before being fetched again, in seconds. Zero
+    /// disables the cache.
+    pub(crate) role_cache_ttl_secs:  u64,
+    /// The number of failed submissions a user may make for a flag before they are locked out.
+    pub(crate) submit_free_attempts: i32,
+    /// The length of the first lockout in seconds. Each subsequent failure doubles the lockout.
+    pub(crate) submit_backoff_base:  i64,
+    /// The longest a user can be locked out of a flag for, in seconds.
+    pub(crate) submit_backoff_max:   i64,
+    /// The number of submissions a user may make across all flags per minute.
+    pub(crate) submit_user_limit:    usize,
+    pub(crate) policy:               authz::Policy,
+    /// Where and how platform events are delivered.
+    pub(crate) webhooks:             webhooks::Config,
+    /// What runs the per-user instances of services that have an image.
+    pub(crate) instance_backend:     BackendKind,
+    /// How long an instance may go unused before it is stopped, in seconds.
+    pub(crate) instance_idle_secs:   u64,
+    /// The network that instances are attached to, if not the default.
+    pub(crate) instance_network:     Option<String>,
+    /// The path to the Docker Engine API socket.
+    pub(crate) docker_socket:        String,
+    /// The memory each instance may use, in megabytes. Zero removes the limit.
+    pub(crate) instance_memory_mb:   u64,
+    /// The CPUs each instance may use, which may be fractional. Zero removes the limit.
+    pub(crate) instance_cpus:        f64,
+    /// The processes each instance may run. Zero removes the limit.
+    pub(crate) instance_pids:        u64,
+}
+
+impl Config {
+    fn load(loader: &mut Loader) -> Self {
+        Self {
+            jwt_pem:              loader.file_contents("JWT_PEM_LOC", "/certs/jwt-key.pem"),
+            keyring:              keyring(loader),
+            attempt_value_mode:   loader.get("ATTEMPT_VALUE_MODE", AttemptValueMode::Hash),
+            attachment_dir:       loader.get("ATTACHMENT_DIR", "./attachments".to_string()),
+            db_uri:               loader.secret_or("DB_URI", "sqlite://./db.db"),
+            auto_migrate:         loader.get("AUTO_MIGRATE", true),
+            gaia_addr:            loader.get("GAIA_ADDR", "gaia-backend:8081".to_string()),
+            role_cache_ttl_secs:  loader.get("ROLE_CACHE_TTL_SECS", 60),
+            submit_free_attempts: loader.get("SUBMIT_FREE_ATTEMPTS", 5),
+            submit_backoff_base:  loader.get("SUBMIT_BACKOFF_BASE_SECS", 30),
+            submit_backoff_max:   loader.get("SUBMIT_BACKOFF_MAX_SECS", 3600),
+            submit_user_limit:    loader.get("SUBMIT_USER_LIMIT", 30),
+            policy:               authz::Policy::load(loader),
+            webhooks:             webhooks::Config::load(loader),
+            instance_backend:     loader.get("INSTANCE_BACKEND", BackendKind::None),
+            instance_idle_secs:   loader.get("INSTANCE_IDLE_SECS", 1800),
+            instance_network:     Some(loader.get("INSTANCE_NETWORK", String::new()))
+                .filter(|network| !network.is_empty()),
+            docker_socket:        loader.get("DOCKER_SOCKET", "/var/run/docker.sock".to_string()),
+            instance_memory_mb:   loader.get("INSTANCE_MEMORY_MB", 512),
+            instance_cpus:        loader.get("INSTANCE_CPUS", 1.0),
+            instance_pids:        loader.get("INSTANCE_PIDS", 256),
+        }
+    }
+}
+
+/// Read the flag keys from `HMAC_KEY` and their retirement deadlines from `HMAC_KEY_RETIRE`. See
+/// [`Keyring::parse`].
+fn keyring(loader: &mut Loader) -> Keyring {
+    let keys = loader.secret("HMAC_KEY");
+    let retire = loader.get("HMAC_KEY_RETIRE", String::new());
+    if keys.is_empty() {
+        return Keyring::default();
+    }
+
+    match Keyring::parse(&keys, &retire) {
+        Ok(keyring) => {
+            for key in keyring.iter() {
+                loader.check_key("HMAC_KEY", key.secret(), KeyLength::AtLeast(32));
+            }
+            keyring
+        },
+        Err(e) => {
+            loader.invalid(format!("HMAC_KEY: {e}"));
+            Keyring::default()
+        },
+    }
+}
+
+/// Load and validate the configuration. Exits the process if it is invalid, or if the router was
+/// started with `--print-config`.
+pub(crate) fn init() -> &'static Config {
+    CONFIG.get_or_init(|| service_config::load(Config::load))
+}
+
+/// The configuration loaded by [`init`].
+pub(crate) fn get() -> &'static Config { CONFIG.get().expect("configuration is loaded at startup") }
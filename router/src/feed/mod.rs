@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of events that are buffered for slow subscribers before they start missing events.
+const FEED_CAPACITY: usize = 256;
+
+/// A correct flag submission.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct SolveEvent {
+    /// The user that submitted the flag. Empty if the event has been anonymised.
+    pub(crate) user_id:      Option<i64>,
+    /// The team that the flag was solved for, if the challenge is in team mode.
+    pub(crate) team_id:      Option<i64>,
+    pub(crate) flag_id:      String,
+    pub(crate) challenge_id: i64,
+    pub(crate) timestamp:    DateTime<Utc>,
+    /// Whether this was the first solve of the flag.
+    pub(crate) first_blood:  bool,
+}
+
+/// Who is subscribed to the feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Viewer {
+    /// Tutors and admins see every event.
+    Staff,
+    /// Students see their own events, their team's events and anonymised first bloods.
+    Student { user_id: i64, team_id: Option<i64> },
+}
+
+impl SolveEvent {
+    /// Get the version of the event that a viewer is allowed to see, if any.
+    pub(crate) fn visible_to(&self, viewer: Viewer) -> Option<SolveEvent> {
+        match viewer {
+            Viewer::Staff => Some(self.clone()),
+            Viewer::Student { user_id, team_id } => {
+                let own = self.user_id == Some(user_id)
+                    || (self.team_id.is_some() && self.team_id == team_id);
+
+                if own {
+                    Some(self.clone())
+                } else if self.first_blood {
+                    Some(SolveEvent {
+                        user_id: None,
+                        team_id: None,
+                        ..self.clone()
+                    })
+                } else {
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Broadcasts solve events to every connected subscriber.
+#[derive(Debug, Clone)]
+pub(crate) struct SolveFeed {
+    sender: broadcast::Sender<SolveEvent>,
+}
+
+impl SolveFeed {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = broadcast::channel(FEED_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event to all current subscribers. Events are dropped if nobody is subscribed.
+    pub(crate) fn publish(&self, event: SolveEvent) { let _ = self.sender.send(event); }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<SolveEvent> { self.sender.subscribe() }
+}
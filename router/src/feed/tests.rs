@@ -0,0 +1,77 @@
+use chrono::Utc;
+
+use super::{SolveEvent, Viewer};
+
+fn event(user_id: i64, team_id: Option<i64>, first_blood: bool) -> SolveEvent {
+    SolveEvent {
+        user_id: Some(user_id),
+        team_id,
+        flag_id: "test".to_string(),
+        challenge_id: 1,
+        timestamp: Utc::now(),
+        first_blood,
+    }
+}
+
+#[test]
+fn staff_see_everything() {
+    let e = event(5, None, false);
+
+    assert_eq!(e.visible_to(Viewer::Staff), Some(e.clone()));
+}
+
+#[test]
+fn students_see_their_own_solves() {
+    let e = event(5, None, false);
+
+    assert_eq!(
+        e.visible_to(Viewer::Student {
+            user_id: 5,
+            team_id: None,
+        }),
+        Some(e.clone())
+    );
+    assert_eq!(
+        e.visible_to(Viewer::Student {
+            user_id: 6,
+            team_id: None,
+        }),
+        None
+    );
+}
+
+#[test]
+fn students_see_their_teams_solves() {
+    let e = event(5, Some(1), false);
+
+    assert_eq!(
+        e.visible_to(Viewer::Student {
+            user_id: 6,
+            team_id: Some(1),
+        }),
+        Some(e.clone())
+    );
+    assert_eq!(
+        e.visible_to(Viewer::Student {
+            user_id: 7,
+            team_id: Some(2),
+        }),
+        None
+    );
+}
+
+#[test]
+fn first_bloods_are_anonymised() {
+    let e = event(5, Some(1), true);
+    let seen = e
+        .visible_to(Viewer::Student {
+            user_id: 7,
+            team_id: None,
+        })
+        .unwrap();
+
+    assert_eq!(seen.user_id, None);
+    assert_eq!(seen.team_id, None);
+    assert!(seen.first_blood);
+    assert_eq!(seen.flag_id, e.flag_id);
+}
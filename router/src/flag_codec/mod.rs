@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use hmac::Mac;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    handler_utils,
+    keyring::{FlagKey, Keyring, SignatureError},
+    teams,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The prefix of flags in the current format.
+const VERSION: &str = "v2";
+
+/// The length of the synthetic IV at the start of an owner binding.
+const IV_LEN: usize = 12;
+
+/// Why a submitted dynamic flag could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DecodeError {
+    /// The flag does not have the components of a dynamic flag.
+    Malformed,
+    /// The name in the flag does not match the flag.
+    WrongName,
+    /// The owner or signature is not valid base64.
+    BadEncoding,
+    /// The owner is not a user or team.
+    BadIdentity,
+    Signature(SignatureError),
+}
+
+/// A decoded dynamic flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DecodedFlag {
+    /// The identity of the user or team that the flag was generated for.
+    pub(crate) owner:    String,
+    /// Whether the signature is valid. Legacy flags reveal their owner even when it is not.
+    pub(crate) verified: Result<(), SignatureError>,
+}
+
+/// Compare two strings in constant time, so that secret flags cannot be guessed from how long a
+/// comparison takes. Only the lengths of the strings are revealed.
+pub(crate) fn secure_eq(a: &str, b: &str) -> bool { a.as_bytes().ct_eq(b.as_bytes()).into() }
+
+/// Generate the dynamic flag with the given name for a user or team, in the form
+/// `v2.name.owner.key id.signature`. The owner is encrypted, so that flags do not reveal who they
+/// were generated for, and the owner and signature are encoded with URL-safe base64. Returns
+/// `None` if the identity is not a user or team.
+pub(crate) fn encode(key: &FlagKey, name: &str, flag_id: &str, identity: &str) -> Option<String> {
+    let owner = pack_identity(identity)?;
+
+    // The IV is derived from the owner, so the same flag is always generated for a user, but
+    // owners cannot be compared across flags
+    let iv = key
+        .mac(&message(&["binding iv", flag_id], &owner))
+        .finalize()
+        .into_bytes();
+    let mut binding = iv[..IV_LEN].to_vec();
+    binding.extend(xor(&owner, &binding_pad(key, &binding)));
+
+    let unsigned = format!(
+        "{VERSION}.{name}.{}.{}",
+        base64::encode_config(&binding, base64::URL_SAFE_NO_PAD),
+        key.id
+    );
+    let signature = key
+        .mac(&message(&[flag_id], unsigned.as_bytes()))
+        .finalize();
+
+    Some(format!(
+        "{unsigned}.{}",
+        base64::encode_config(signature.into_bytes(), base64::URL_SAFE_NO_PAD)
+    ))
+}
+
+/// Decode the contents of a submitted dynamic flag, i.e. the text between `COMP6443{` and `}`.
+/// Flags in the current format must name a key in the keyring, while legacy flags of the form
+/// `name.base64 identity[.key id].signature` are checked against every key if they do not.
+///
+/// Legacy flags may be named after the version, so flags that start with the expected name and have
+/// fewer components than a current flag with that name are always decoded as legacy flags.
+pub(crate) fn decode(
+    keyring: &Keyring,
+    submitted: &str,
+    name: &str,
+    flag_id: &str,
+    now: DateTime<Utc>,
+) -> Result<DecodedFlag, DecodeError> {
+    let legacy = submitted.starts_with(&format!("{name}."))
+        && submitted.split('.').count() < name.split('.').count() + 4;
+    if submitted.starts_with(&format!("{VERSION}.")) && !legacy {
+        decode_current(keyring, submitted, name, flag_id, now)
+    } else {
+        decode_legacy(keyring, submitted, name, flag_id, now)
+    }
+}
+
+fn decode_current(
+    keyring: &Keyring,
+    submitted: &str,
+    name: &str,
+    flag_id: &str,
+    now: DateTime<Utc>,
+) -> Result<DecodedFlag, DecodeError> {
+    // Split from the end, so that names may contain dots
+    let components = submitted
+        .rsplit_once('.')
+        .and_then(|(unsigned, signature)| {
+            let (rest, key_id) = unsigned.rsplit_once('.')?;
+            let (rest, binding) = rest.rsplit_once('.')?;
+            let submitted_name = rest.strip_prefix(VERSION)?.strip_prefix('.')?;
+            Some((unsigned, submitted_name, binding, key_id, signature))
+        });
+    let Some((unsigned, submitted_name, binding, key_id, signature)) = components else {
+        return Err(DecodeError::Malformed);
+    };
+    if !secure_eq(submitted_name, name) {
+        return Err(DecodeError::WrongName);
+    }
+
+    let decode =
+        |s| base64::decode_config(s, base64::URL_SAFE_NO_PAD).map_err(|_| DecodeError::BadEncoding);
+    let binding = decode(binding)?;
+    let signature = decode(signature)?;
+    if binding.len() <= IV_LEN {
+        return Err(DecodeError::BadIdentity);
+    }
+
+    // The owner can only be read once the signature is known to be genuine
+    let key = keyring
+        .verify(
+            &message(&[flag_id], unsigned.as_bytes()),
+            Some(key_id),
+            &signature,
+            now,
+        )
+        .map_err(DecodeError::Signature)?;
+
+    let (iv, owner) = binding.split_at(IV_LEN);
+    let owner =
+        unpack_identity(&xor(owner, &binding_pad(key, iv))).ok_or(DecodeError::BadIdentity)?;
+
+    Ok(DecodedFlag {
+        owner,
+        verified: Ok(()),
+    })
+}
+
+fn decode_legacy(
+    keyring: &Keyring,
+    submitted: &str,
+    name: &str,
+    flag_id: &str,
+    now: DateTime<Utc>,
+) -> Result<DecodedFlag, DecodeError> {
+    // Flags generated before key ids were embedded have no key id component
+    let components: Vec<_> = submitted.split('.').collect();
+    let (submitted_name, owner, key_id, signature) = match components[..] {
+        [submitted_name, owner, signature] => (submitted_name, owner, None, signature),
+        [submitted_name, owner, key_id, signature] => {
+            (submitted_name, owner, Some(key_id), signature)
+        },
+        _ => return Err(DecodeError::Malformed),
+    };
+    if !secure_eq(submitted_name, name) {
+        return Err(DecodeError::WrongName);
+    }
+
+    let owner = base64::decode(owner).map_err(|_| DecodeError::BadEncoding)?;
+    let owner = String::from_utf8(owner).map_err(|_| DecodeError::BadIdentity)?;
+    let verified = match base64::decode(signature) {
+        Ok(signature) => keyring
+            .verify(
+                format!("{owner}_{flag_id}").as_bytes(),
+                key_id,
+                &signature,
+                now,
+            )
+            .map(|_| ()),
+        Err(_) => Err(SignatureError::Invalid),
+    };
+
+    Ok(DecodedFlag { owner, verified })
+}
+
+/// Join the parts of a message that is signed with a flag key.
+fn message(context: &[&str], data: &[u8]) -> Vec<u8> {
+    let mut message = context.join("\0").into_bytes();
+    message.push(0);
+    message.extend_from_slice(data);
+    message
+}
+
+/// The key stream that the owner in a binding is encrypted with.
+fn binding_pad(key: &FlagKey, iv: &[u8]) -> Vec<u8> {
+    key.mac(&message(&["binding pad"], iv))
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+fn xor(data: &[u8], pad: &[u8]) -> Vec<u8> { data.iter().zip(pad).map(|(a, b)| a ^ b).collect() }
+
+/// Pack a user or team identity into a type byte followed by its id.
+fn pack_identity(identity: &str) -> Option<Vec<u8>> {
+    let (kind, id) = match handler_utils::parse_user_id(identity) {
+        Some(id) => (b'U', id),
+        None => (b'T', teams::parse_team_identity(identity)?),
+    };
+
+    let mut packed = vec![kind];
+    packed.extend_from_slice(&id.to_be_bytes());
+    Some(packed)
+}
+
+fn unpack_identity(packed: &[u8]) -> Option<String> {
+    let (kind, id) = packed.split_first()?;
+    let id = i64::from_be_bytes(id.try_into().ok()?);
+
+    match kind {
+        b'U' => Some(format!("_scpU{id}@unsw.scp.platform")),
+        b'T' => Some(teams::team_identity(id)),
+        _ => None,
+    }
+}
@@ -0,0 +1,200 @@
+use chrono::{TimeZone, Utc};
+use hmac::Mac;
+
+use super::{decode, encode, secure_eq, DecodeError, DecodedFlag};
+use crate::keyring::{Keyring, SignatureError};
+
+const USER: &str = "_scpU42@unsw.scp.platform";
+const TEAM: &str = "_scpT7@unsw.scp.platform";
+
+fn keyring() -> Keyring {
+    Keyring::parse(
+        "new:0b7e4c29d15a8f63e2c07b94a1d5f836,old:9f2c1e7a4b8d03f6a5e1c9d7b2f4086e",
+        "old=2024-06-01T00:00:00Z",
+    )
+    .unwrap()
+}
+
+fn verified(owner: &str) -> DecodedFlag {
+    DecodedFlag {
+        owner:    owner.to_string(),
+        verified: Ok(()),
+    }
+}
+
+#[test]
+fn encodes_flags() {
+    let keyring = keyring();
+    let now = Utc.ymd(2024, 1, 1).and_hms(0, 0, 0);
+    let flag = encode(keyring.signing_key(), "abc", "f1", USER).unwrap();
+
+    assert!(flag.starts_with("v2.abc."));
+    assert_eq!(flag.split('.').nth(3), Some("new"));
+    assert!(flag
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)));
+    assert_eq!(
+        encode(keyring.signing_key(), "abc", "f1", USER),
+        Some(flag.clone())
+    );
+    assert_eq!(
+        decode(&keyring, &flag, "abc", "f1", now),
+        Ok(verified(USER))
+    );
+
+    let flag = encode(keyring.signing_key(), "a.b", "f1", TEAM).unwrap();
+    assert_eq!(
+        decode(&keyring, &flag, "a.b", "f1", now),
+        Ok(verified(TEAM))
+    );
+
+    assert_eq!(encode(keyring.signing_key(), "abc", "f1", "someone"), None);
+}
+
+#[test]
+fn hides_owners() {
+    let key = keyring();
+    let key = key.signing_key();
+    let binding = |flag_id, identity| {
+        let flag = encode(key, "abc", flag_id, identity).unwrap();
+        flag.split('.').nth(2).unwrap().to_string()
+    };
+
+    let decoded = base64::decode_config(binding("f1", USER), base64::URL_SAFE_NO_PAD).unwrap();
+    assert!(!decoded.windows(8).any(|w| w == 42_i64.to_be_bytes()));
+    assert_ne!(binding("f1", USER), binding("f2", USER));
+    assert_ne!(
+        binding("f1", USER),
+        binding("f1", "_scpU43@unsw.scp.platform")
+    );
+}
+
+#[test]
+fn rejects_invalid_flags() {
+    let keyring = keyring();
+    let now = Utc.ymd(2024, 1, 1).and_hms(0, 0, 0);
+    let flag = encode(keyring.signing_key(), "abc", "f1", USER).unwrap();
+    let parts: Vec<_> = flag.split('.').collect();
+
+    assert_eq!(
+        decode(&keyring, "v2.abc.new", "abc", "f1", now),
+        Err(DecodeError::Malformed)
+    );
+    assert_eq!(
+        decode(&keyring, &flag, "abd", "f1", now),
+        Err(DecodeError::WrongName)
+    );
+    assert_eq!(
+        decode(&keyring, &flag, "abc", "f2", now),
+        Err(DecodeError::Signature(SignatureError::Invalid))
+    );
+    assert_eq!(
+        decode(&keyring, &flag.replace(".new.", ".old."), "abc", "f1", now),
+        Err(DecodeError::Signature(SignatureError::Invalid))
+    );
+
+    // Swapping in the owner of another flag invalidates the signature
+    let other = encode(keyring.signing_key(), "abc", "f1", TEAM).unwrap();
+    let swapped = flag.replace(parts[2], other.split('.').nth(2).unwrap());
+    assert_eq!(
+        decode(&keyring, &swapped, "abc", "f1", now),
+        Err(DecodeError::Signature(SignatureError::Invalid))
+    );
+    assert_eq!(
+        decode(&keyring, &flag.replace(parts[4], "a+b/"), "abc", "f1", now),
+        Err(DecodeError::BadEncoding)
+    );
+}
+
+#[test]
+fn accepts_legacy_flags() {
+    let keyring = keyring();
+    let before = Utc.ymd(2024, 1, 1).and_hms(0, 0, 0);
+    let after = Utc.ymd(2024, 7, 1).and_hms(0, 0, 0);
+    let mac = keyring
+        .get("old")
+        .unwrap()
+        .mac(format!("{USER}_f1").as_bytes())
+        .finalize();
+    let signature = base64::encode(mac.into_bytes());
+    let owner = base64::encode(USER);
+
+    let without_id = format!("abc.{owner}.{signature}");
+    let with_id = format!("abc.{owner}.old.{signature}");
+    assert_eq!(
+        decode(&keyring, &without_id, "abc", "f1", before),
+        Ok(verified(USER))
+    );
+    assert_eq!(
+        decode(&keyring, &with_id, "abc", "f1", before),
+        Ok(verified(USER))
+    );
+    assert_eq!(
+        decode(&keyring, &with_id, "abc", "f1", after),
+        Ok(DecodedFlag {
+            owner:    USER.to_string(),
+            verified: Err(SignatureError::Retired),
+        })
+    );
+
+    // Legacy flags reveal their owner even if they are forged
+    assert_eq!(
+        decode(
+            &keyring,
+            &format!("abc.{owner}.new.{signature}"),
+            "abc",
+            "f1",
+            before
+        ),
+        Ok(DecodedFlag {
+            owner:    USER.to_string(),
+            verified: Err(SignatureError::Invalid),
+        })
+    );
+    assert_eq!(
+        decode(&keyring, &format!("abc.{owner}"), "abc", "f1", before),
+        Err(DecodeError::Malformed)
+    );
+    assert_eq!(
+        decode(&keyring, &format!("abc.!.{signature}"), "abc", "f1", before),
+        Err(DecodeError::BadEncoding)
+    );
+}
+
+#[test]
+fn accepts_legacy_flags_named_after_the_version() {
+    let keyring = keyring();
+    let now = Utc.ymd(2024, 1, 1).and_hms(0, 0, 0);
+    let mac = keyring
+        .get("old")
+        .unwrap()
+        .mac(format!("{USER}_f1").as_bytes())
+        .finalize();
+    let signature = base64::encode(mac.into_bytes());
+    let owner = base64::encode(USER);
+
+    for legacy in [
+        format!("v2.{owner}.{signature}"),
+        format!("v2.{owner}.old.{signature}"),
+    ] {
+        assert_eq!(
+            decode(&keyring, &legacy, "v2", "f1", now),
+            Ok(verified(USER))
+        );
+    }
+
+    // Current flags with the same name are still decoded in the current format
+    let current = encode(keyring.signing_key(), "v2", "f1", USER).unwrap();
+    assert_eq!(
+        decode(&keyring, &current, "v2", "f1", now),
+        Ok(verified(USER))
+    );
+}
+
+#[test]
+fn compares_securely() {
+    assert!(secure_eq("flag", "flag"));
+    assert!(!secure_eq("flag", "flap"));
+    assert!(!secure_eq("flag", "flags"));
+    assert!(!secure_eq("", "flag"));
+}
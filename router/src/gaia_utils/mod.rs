@@ -1,19 +1,193 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::RwLock,
+    time::{Duration, Instant},
+};
 
-use crate::GAIA_ADDR;
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
 
-/// Get the roles for a user.
+use crate::config;
+
+#[cfg(test)]
+mod tests;
+
+/// The most expired cache entries that are refreshed alongside a cache miss.
+const REFRESH_BATCH_SIZE: usize = 100;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+static ROLE_CACHE: Lazy<RoleCache> =
+    Lazy::new(|| RoleCache::new(Duration::from_secs(config::get().role_cache_ttl_secs)));
+
+/// Roles fetched from gaia, keyed by user id.
+pub(crate) struct RoleCache {
+    ttl:         Duration,
+    entries:     RwLock<HashMap<String, (Instant, HashSet<String>)>>,
+    /// How many times each user's roles have been invalidated. Fetches that started before an
+    /// invalidation are dropped, since they may have read the roles from before it. Only locked
+    /// while `entries` is locked for writing.
+    generations: RwLock<HashMap<String, u64>>,
+}
+
+impl RoleCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+            generations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the roles of a user, unless they were fetched longer than the TTL ago.
+    pub(crate) fn get(&self, id: &str, now: Instant) -> Option<HashSet<String>> {
+        let entries = self.entries.read().unwrap();
+        let (fetched, roles) = entries.get(id)?;
+        (now.saturating_duration_since(*fetched) < self.ttl).then(|| roles.clone())
+    }
+
+    /// Get up to `limit` users whose roles have expired.
+    pub(crate) fn expired(&self, now: Instant, limit: usize) -> Vec<String> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, (fetched, _))| now.saturating_duration_since(*fetched) >= self.ttl)
+            .map(|(id, _)| id.clone())
+            .take(limit)
+            .collect()
+    }
+
+    /// Get the generation of a user's roles, to be passed to [`RoleCache::insert`] once they
+    /// have been fetched.
+    pub(crate) fn generation(&self, id: &str) -> u64 {
+        self.generations
+            .read()
+            .unwrap()
+            .get(id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Store the roles of a user, fetched at `fetched`. The roles are dropped if they were
+    /// invalidated since `generation` was read.
+    pub(crate) fn insert(
+        &self,
+        id: String,
+        roles: HashSet<String>,
+        fetched: Instant,
+        generation: u64,
+    ) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.write().unwrap();
+        if self.generation(&id) == generation {
+            entries.insert(id, (fetched, roles));
+        }
+    }
+
+    /// Forget the roles of some users, so that they are fetched again when next needed.
+    pub(crate) fn invalidate(&self, ids: &[String]) {
+        let mut entries = self.entries.write().unwrap();
+        let mut generations = self.generations.write().unwrap();
+        for id in ids {
+            entries.remove(id);
+            *generations.entry(id.clone()).or_default() += 1;
+        }
+    }
+}
+
+/// Get the roles for a user. Roles are cached for `ROLE_CACHE_TTL_SECS`, and any expired entries
+/// are refreshed in the same request to gaia whenever the cache misses.
 ///
 /// # Params
 ///
 /// - `token`: the JWT token used to identify the current user.
 pub(crate) async fn get_roles(token: &str) -> anyhow::Result<HashSet<String>> {
-    let client = reqwest::Client::new();
-    let res = client
-        .get(format!("http://{}/api/selfserve/roles", GAIA_ADDR.as_str()))
+    let claims = intra_jwt::verify_jwt(token, config::get().jwt_pem.as_str())?;
+    let id = claims
+        .user_id
+        .strip_prefix("_scpU")
+        .and_then(|id| id.strip_suffix("@unsw.scp.platform"))
+        .context("token does not belong to a user")?
+        .to_string();
+
+    let now = Instant::now();
+    if let Some(roles) = ROLE_CACHE.get(&id, now) {
+        return Ok(roles);
+    }
+
+    let mut ids = ROLE_CACHE.expired(now, REFRESH_BATCH_SIZE);
+    ids.retain(|i| *i != id);
+    ids.push(id.clone());
+
+    let generations: HashMap<_, _> = ids
+        .iter()
+        .map(|id| (id.clone(), ROLE_CACHE.generation(id)))
+        .collect();
+    let mut roles = get_users_roles(&ids).await?;
+    for (id, user_roles) in &roles {
+        if let Some(generation) = generations.get(id) {
+            ROLE_CACHE.insert(id.clone(), user_roles.clone(), now, *generation);
+        }
+    }
+
+    Ok(roles.remove(&id).unwrap_or_default())
+}
+
+/// Forget the cached roles of some users.
+pub(crate) fn invalidate_roles(ids: &[String]) { ROLE_CACHE.invalidate(ids); }
+
+/// Get the roles of many users in a single request to gaia, bypassing the cache.
+///
+/// # Params
+///
+/// - `ids`: the ids of the users, as used in `_scpU{id}@unsw.scp.platform`.
+pub(crate) async fn get_users_roles(
+    ids: &[String],
+) -> anyhow::Result<HashMap<String, HashSet<String>>> {
+    let token = intra_jwt::create_service_jwt("router", config::get().jwt_pem.as_str())?;
+    let res = CLIENT
+        .post(format!(
+            "http://{}/api/users/roles",
+            config::get().gaia_addr.as_str()
+        ))
+        .header("X-Scp-Auth", token)
+        .json(ids)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(res.json::<HashMap<String, HashSet<String>>>().await?)
+}
+
+/// A user known to gaia.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GaiaUser {
+    /// The numeric user id, as used in `_scpU{id}@unsw.scp.platform`.
+    pub(crate) id:    String,
+    /// The email that the user enrolled with.
+    pub(crate) email: String,
+    pub(crate) name:  Option<String>,
+    pub(crate) roles: Vec<String>,
+}
+
+/// Get all users and their roles. The token must belong to a tutor or admin.
+///
+/// # Params
+///
+/// - `token`: the JWT token used to identify the current user.
+pub(crate) async fn get_users(token: &str) -> anyhow::Result<Vec<GaiaUser>> {
+    let res = CLIENT
+        .get(format!(
+            "http://{}/api/users",
+            config::get().gaia_addr.as_str()
+        ))
         .header("X-Scp-Auth", token)
         .send()
-        .await?;
+        .await?
+        .error_for_status()?;
 
-    Ok(res.json::<HashSet<String>>().await?)
+    Ok(res.json::<Vec<GaiaUser>>().await?)
 }
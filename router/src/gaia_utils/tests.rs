@@ -0,0 +1,83 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use super::RoleCache;
+
+fn roles(names: &[&str]) -> HashSet<String> { names.iter().map(ToString::to_string).collect() }
+
+#[test]
+fn hits_within_ttl() {
+    let cache = RoleCache::new(Duration::from_secs(30));
+    let now = Instant::now();
+    cache.insert("1".to_string(), roles(&["student"]), now, 0);
+
+    assert_eq!(cache.get("1", now), Some(roles(&["student"])));
+    assert_eq!(
+        cache.get("1", now + Duration::from_secs(29)),
+        Some(roles(&["student"]))
+    );
+    assert_eq!(cache.get("2", now), None);
+}
+
+#[test]
+fn expires_after_ttl() {
+    let cache = RoleCache::new(Duration::from_secs(30));
+    let now = Instant::now();
+    cache.insert("1".to_string(), roles(&["student"]), now, 0);
+    cache.insert(
+        "2".to_string(),
+        roles(&["tutor"]),
+        now + Duration::from_secs(15),
+        0,
+    );
+
+    let later = now + Duration::from_secs(30);
+    assert_eq!(cache.get("1", later), None);
+    assert_eq!(cache.expired(later, 10), vec!["1".to_string()]);
+    assert!(cache.expired(later, 0).is_empty());
+}
+
+#[test]
+fn invalidation_removes_entries() {
+    let cache = RoleCache::new(Duration::from_secs(30));
+    let now = Instant::now();
+    cache.insert("1".to_string(), roles(&["student"]), now, 0);
+    cache.insert("2".to_string(), roles(&[]), now, 0);
+
+    cache.invalidate(&["1".to_string(), "3".to_string()]);
+    assert_eq!(cache.get("1", now), None);
+    assert_eq!(cache.get("2", now), Some(roles(&[])));
+}
+
+#[test]
+fn zero_ttl_disables_cache() {
+    let cache = RoleCache::new(Duration::ZERO);
+    let now = Instant::now();
+    cache.insert("1".to_string(), roles(&["admin"]), now, 0);
+
+    assert_eq!(cache.get("1", now), None);
+    assert!(cache.expired(now, 10).is_empty());
+}
+
+#[test]
+fn drops_fetches_from_before_invalidation() {
+    let cache = RoleCache::new(Duration::from_secs(30));
+    let now = Instant::now();
+
+    // Roles are fetched, then changed and invalidated before the fetch returns
+    let generation = cache.generation("1");
+    cache.invalidate(&["1".to_string()]);
+    cache.insert("1".to_string(), roles(&["student"]), now, generation);
+    assert_eq!(cache.get("1", now), None);
+
+    // Fetches that start after the invalidation are stored
+    cache.insert(
+        "1".to_string(),
+        roles(&["tutor"]),
+        now,
+        cache.generation("1"),
+    );
+    assert_eq!(cache.get("1", now), Some(roles(&["tutor"])));
+}
@@ -0,0 +1,275 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use authz::Role;
+use chrono::{DateTime, Utc};
+use router_entity::{category, flag, service, submission};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{gaia_utils::GaiaUser, scoring};
+
+#[cfg(test)]
+mod tests;
+
+/// How marks are grouped in the gradebook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GroupBy {
+    #[default]
+    Category,
+    Challenge,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct GroupMark {
+    pub(crate) points:          i64,
+    pub(crate) last_submission: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct StudentMarks {
+    pub(crate) user_id:         i64,
+    pub(crate) zid:             Option<String>,
+    pub(crate) email:           Option<String>,
+    pub(crate) name:            Option<String>,
+    pub(crate) groups:          BTreeMap<String, GroupMark>,
+    /// The total cost of the hints the student has unlocked.
+    pub(crate) hint_costs:      i64,
+    pub(crate) total:           i64,
+    pub(crate) last_submission: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Gradebook {
+    /// The names of every category or challenge, in the order they appear in the CSV export.
+    pub(crate) groups:   Vec<String>,
+    pub(crate) students: Vec<StudentMarks>,
+}
+
+/// Extract a zID from an enrolment email such as `z1234567@unsw.edu.au`.
+pub(crate) fn parse_zid(email: &str) -> Option<String> {
+    let local = email.split('@').next()?;
+    let valid = local.len() == 8
+        && local.starts_with(['z', 'Z'])
+        && local[1..].chars().all(|c| c.is_ascii_digit());
+
+    valid.then(|| local.to_lowercase())
+}
+
+/// Get the name of the gradebook group that each flag belongs to.
+async fn get_flag_groups<C>(conn: &C, by: GroupBy) -> Result<HashMap<String, String>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let flags = flag::Entity::find().all(conn).await?;
+
+    let group_names: HashMap<i64, String> = match by {
+        GroupBy::Category => category::Entity::find()
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|c| (c.id, c.name))
+            .collect(),
+        // Challenges do not have a name, so they are identified by the names of their services
+        GroupBy::Challenge => {
+            let mut names: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+            for s in service::Entity::find().all(conn).await? {
+                names.entry(s.challenge_id).or_default().push(s.name);
+            }
+
+            names
+                .into_iter()
+                .map(|(id, mut n)| {
+                    n.sort();
+                    (id, n.join("/"))
+                })
+                .collect()
+        },
+    };
+
+    Ok(flags
+        .into_iter()
+        .map(|f| {
+            let key = match by {
+                GroupBy::Category => f.category_id,
+                GroupBy::Challenge => f.challenge_id,
+            };
+            let name = group_names
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| key.to_string());
+
+            (f.id, name)
+        })
+        .collect())
+}
+
+/// Calculate the marks of every student. Students are taken from the supplied gaia users, along
+/// with any other users that have submitted a flag. Tutors and admins are excluded, even if they
+/// have submitted flags. Submissions are credited to students as given by
+/// [`scoring::credited_users`].
+pub(crate) async fn build<C>(conn: &C, users: &[GaiaUser], by: GroupBy) -> Result<Gradebook, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let flag_groups = get_flag_groups(conn, by).await?;
+
+    let solve_counts = scoring::get_solve_counts(conn).await?;
+    let flags: HashMap<String, (flag::Model, i32)> = flag::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|f| {
+            let value = scoring::flag_value(&f, *solve_counts.get(&f.id).unwrap_or(&0));
+            (f.id.clone(), (f, value))
+        })
+        .collect();
+
+    let team_members = scoring::get_team_members(conn).await?;
+
+    let mut students: BTreeMap<i64, StudentMarks> = BTreeMap::new();
+    let new_student = |user_id: i64| StudentMarks {
+        user_id,
+        zid: None,
+        email: None,
+        name: None,
+        groups: BTreeMap::new(),
+        hint_costs: 0,
+        total: 0,
+        last_submission: None,
+    };
+
+    // Staff may solve flags to test challenges, but are not students
+    let mut staff: HashSet<i64> = HashSet::new();
+    for u in users {
+        let Ok(user_id) = u.id.parse::<i64>() else {
+            continue;
+        };
+        if u.roles.iter().any(|r| Role::from(r.as_str()).is_staff()) {
+            staff.insert(user_id);
+        } else {
+            let student = students
+                .entry(user_id)
+                .or_insert_with(|| new_student(user_id));
+            student.zid = parse_zid(&u.email);
+            student.email = Some(u.email.clone());
+            student.name.clone_from(&u.name);
+        }
+    }
+
+    for sub in submission::Entity::find().all(conn).await? {
+        let Some(group) = flag_groups.get(&sub.flag_id) else {
+            continue;
+        };
+        let value = match flags.get(&sub.flag_id) {
+            Some((flag, value)) => scoring::submission_points(flag, *value, &sub),
+            None => 0,
+        };
+
+        for user_id in scoring::credited_users(&sub, &team_members) {
+            if staff.contains(&user_id) {
+                continue;
+            }
+            let student = students
+                .entry(user_id)
+                .or_insert_with(|| new_student(user_id));
+            let mark = student.groups.entry(group.clone()).or_default();
+
+            mark.points += value;
+            mark.last_submission = mark.last_submission.max(Some(sub.submission_time));
+            student.last_submission = student.last_submission.max(Some(sub.submission_time));
+        }
+    }
+
+    for (user_id, cost) in scoring::get_hint_costs(conn).await? {
+        if let Some(student) = students.get_mut(&user_id) {
+            student.hint_costs = cost;
+        }
+    }
+
+    for student in students.values_mut() {
+        student.total = student.groups.values().map(|g| g.points).sum::<i64>() - student.hint_costs;
+    }
+
+    let mut groups: Vec<String> = flag_groups.into_values().collect();
+    groups.sort();
+    groups.dedup();
+
+    Ok(Gradebook {
+        groups,
+        students: students.into_values().collect(),
+    })
+}
+
+/// Quote a CSV field if it contains any special characters. Fields that spreadsheets would read as
+/// a formula are prefixed with `'`, except for numbers such as negative totals.
+fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) && field.parse::<i64>().is_err() {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Render a gradebook as CSV with one row per student. Each group has a column for the points
+/// earned and a column for the time of the most recent submission.
+pub(crate) fn to_csv(gradebook: &Gradebook) -> String {
+    let mut header = vec![
+        "user_id".to_string(),
+        "zid".to_string(),
+        "email".to_string(),
+        "name".to_string(),
+    ];
+    for group in &gradebook.groups {
+        header.push(group.clone());
+        header.push(format!("{group} last submission"));
+    }
+    header.extend(["hint_costs", "total", "last_submission"].map(String::from));
+
+    let mut lines = vec![header];
+    for student in &gradebook.students {
+        let mut row = vec![
+            student.user_id.to_string(),
+            student.zid.clone().unwrap_or_default(),
+            student.email.clone().unwrap_or_default(),
+            student.name.clone().unwrap_or_default(),
+        ];
+        for group in &gradebook.groups {
+            let mark = student.groups.get(group).cloned().unwrap_or_default();
+            row.push(mark.points.to_string());
+            row.push(
+                mark.last_submission
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default(),
+            );
+        }
+        row.push(student.hint_costs.to_string());
+        row.push(student.total.to_string());
+        row.push(
+            student
+                .last_submission
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+        );
+
+        lines.push(row);
+    }
+
+    lines
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|f| escape(f))
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n")
+        + "\r\n"
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use chrono::{TimeZone, Utc};
+use migration::{Migrator, MigratorTrait};
+use router_entity::{
+    category,
+    challenge::{self, LatePolicy},
+    flag::{self, FlagType, ScoringMode},
+    submission,
+    user,
+};
+use sea_orm::{ActiveModelTrait, Database, Set};
+
+use super::{build, escape, parse_zid, to_csv, Gradebook, GroupBy, GroupMark, StudentMarks};
+use crate::gaia_utils::GaiaUser;
+
+#[test]
+fn zids_are_parsed_from_emails() {
+    assert_eq!(
+        parse_zid("z1234567@unsw.edu.au"),
+        Some("z1234567".to_string())
+    );
+    assert_eq!(
+        parse_zid("Z1234567@ad.unsw.edu.au"),
+        Some("z1234567".to_string())
+    );
+    assert_eq!(parse_zid("someone@example.com"), None);
+    assert_eq!(parse_zid("z123@unsw.edu.au"), None);
+}
+
+#[test]
+fn special_characters_are_quoted() {
+    assert_eq!(escape("plain"), "plain");
+    assert_eq!(escape("Smith, Jane"), "\"Smith, Jane\"");
+    assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+fn formulas_are_escaped() {
+    assert_eq!(escape("=1+1"), "'=1+1");
+    assert_eq!(escape("+61 400"), "'+61 400");
+    assert_eq!(escape("@SUM(A1)"), "'@SUM(A1)");
+    assert_eq!(escape("-2+3,A1"), "\"'-2+3,A1\"");
+    assert_eq!(escape("-10"), "-10");
+}
+
+#[test]
+fn csv_has_a_column_per_group() {
+    let submitted = Utc.ymd(2022, 6, 1).and_hms(12, 0, 0);
+    let mut groups = BTreeMap::new();
+    groups.insert(
+        "web".to_string(),
+        GroupMark {
+            points:          150,
+            last_submission: Some(submitted),
+        },
+    );
+
+    let gradebook = Gradebook {
+        groups:   vec!["crypto".to_string(), "web".to_string()],
+        students: vec![StudentMarks {
+            user_id: 5,
+            zid: Some("z1234567".to_string()),
+            email: Some("z1234567@unsw.edu.au".to_string()),
+            name: Some("Smith, Jane".to_string()),
+            groups,
+            hint_costs: 10,
+            total: 140,
+            last_submission: Some(submitted),
+        }],
+    };
+
+    assert_eq!(
+        to_csv(&gradebook),
+        "user_id,zid,email,name,crypto,crypto last submission,web,web last \
+         submission,hint_costs,total,last_submission\r\n5,z1234567,z1234567@unsw.edu.au,\"Smith, \
+         Jane\",0,,150,2022-06-01T12:00:00+00:00,10,140,2022-06-01T12:00:00+00:00\r\n"
+    );
+}
+
+#[tokio::test]
+async fn staff_are_not_students() {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    challenge::ActiveModel {
+        id:           Set(1),
+        team_mode:    Set(false),
+        description:  Set(String::new()),
+        author:       Set(None),
+        difficulty:   Set(None),
+        deadline:     Set(None),
+        late_policy:  Set(LatePolicy::Reject),
+        late_penalty: Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    category::ActiveModel {
+        id:   Set(1),
+        name: Set("web".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    flag::ActiveModel {
+        id:                 Set("f1".to_string()),
+        challenge_id:       Set(1),
+        category_id:        Set(1),
+        flag:               Set("flag".to_string()),
+        flag_type:          Set(FlagType::Static),
+        points:             Set(100),
+        display_name:       Set("Flag".to_string()),
+        scoring_mode:       Set(ScoringMode::Static),
+        minimum_points:     Set(None),
+        decay:              Set(None),
+        deadline:           Set(None),
+        late_policy:        Set(LatePolicy::Reject),
+        late_penalty:       Set(0),
+        first_blood_bonus:  Set(0),
+        second_blood_bonus: Set(0),
+        third_blood_bonus:  Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    // User 1 is a student and user 2 a tutor testing the challenge
+    for id in [1, 2] {
+        user::ActiveModel { id: Set(id) }
+            .insert(&conn)
+            .await
+            .unwrap();
+        submission::ActiveModel {
+            id:              Set(id),
+            user_id:         Set(id),
+            flag_id:         Set("f1".to_string()),
+            submission_time: Set(Utc.ymd(2022, 6, 1).and_hms(12, 0, 0)),
+            team_id:         Set(None),
+            late_seconds:    Set(None),
+            late_penalty:    Set(0),
+            blood:           Set(None),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    let users = [("1", "student"), ("2", "tutor")].map(|(id, role)| GaiaUser {
+        id:    id.to_string(),
+        email: format!("z000000{id}@unsw.edu.au"),
+        name:  None,
+        roles: vec![role.to_string()],
+    });
+
+    let gradebook = build(&conn, &users, GroupBy::Category).await.unwrap();
+    let students: Vec<_> = gradebook
+        .students
+        .iter()
+        .map(|s| (s.user_id, s.total))
+        .collect();
+    assert_eq!(students, [(1, 100)]);
+}
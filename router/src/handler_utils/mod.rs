@@ -1,22 +1,13 @@
-/// Macro to quickly construct an internal server error with an error code.
-macro_rules! ise {
-    ($code:expr) => {
-        |e| {
-            tracing::error!("exception occurred: {}", e);
-            actix_web::error::ErrorInternalServerError(concat!("Internal server error: EC.", $code))
-        }
-    };
-}
+use std::collections::HashSet;
 
-use actix_web::{
-    error::{ErrorForbidden, ErrorUnauthorized},
-    Error,
-    HttpRequest,
-};
+use actix_web::{Error, FromRequest, HttpRequest};
+pub(crate) use api_error::ise;
+use api_error::{ApiError, Code};
+use authz::{RoleSource, Roles};
+use futures_util::future::LocalBoxFuture;
 use intra_jwt::ClaimsData;
-pub(crate) use ise;
 
-use crate::JWT_PEM;
+use crate::config;
 
 /// Get claims from a HTTP request's auth token.
 pub(crate) fn get_claims(req: &HttpRequest) -> Result<ClaimsData, Error> {
@@ -24,11 +15,46 @@ pub(crate) fn get_claims(req: &HttpRequest) -> Result<ClaimsData, Error> {
     let token = req
         .headers()
         .get("X-Scp-Auth")
-        .ok_or_else(|| ErrorUnauthorized("Missing authentication"))?
+        .ok_or_else(|| ApiError::new(Code::Unauthenticated))?
         .to_str()
-        .map_err(ise!("GCEAH"))?;
+        .map_err(|_| ApiError::new(Code::InvalidToken))?;
 
     // Process the token into claims
-    intra_jwt::verify_jwt(token, JWT_PEM.as_str())
-        .map_err(|_| ErrorForbidden("Unable to get claims from auth token"))
+    intra_jwt::verify_jwt(token, config::get().jwt_pem.as_str())
+        .map_err(|_| ApiError::new(Code::InvalidToken).into())
+}
+
+/// Looks up the roles of the user making a request from gaia, through the role cache.
+pub(crate) struct GaiaRoles;
+
+impl RoleSource for GaiaRoles {
+    fn roles(&self, req: &HttpRequest) -> LocalBoxFuture<'static, Result<HashSet<String>, Error>> {
+        // Get the auth token
+        let token = req
+            .headers()
+            .get("X-Scp-Auth")
+            .ok_or_else(|| ApiError::new(Code::Unauthenticated))
+            .and_then(|t| t.to_str().map_err(|_| ApiError::new(Code::InvalidToken)))
+            .map(ToString::to_string);
+
+        Box::pin(async move {
+            Ok(crate::gaia_utils::get_roles(&token?)
+                .await
+                .map_err(ise!("GRRGR"))?)
+        })
+    }
+}
+
+/// Get the roles of the user making a HTTP request.
+pub(crate) async fn get_request_roles(req: &HttpRequest) -> Result<Roles, Error> {
+    Roles::extract(req).await
+}
+
+/// Parse the numeric user id out of a user's email.
+pub(crate) fn parse_user_id(email: &str) -> Option<i64> {
+    email
+        .strip_prefix("_scpU")?
+        .strip_suffix("@unsw.scp.platform")?
+        .parse::<i64>()
+        .ok()
 }
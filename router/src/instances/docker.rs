@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use futures_util::future::BoxFuture;
+use hyper::{body::Bytes, Body, Client, Method, Request, StatusCode};
+use hyperlocal::{UnixClientExt, UnixConnector};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{Backend, Instance, InstanceSpec};
+
+/// The label that marks containers as instances started by the router.
+const LABEL: &str = "scp.instance";
+
+/// Runs instances as containers through the Docker Engine API on a local socket.
+#[derive(Clone)]
+pub(crate) struct Docker {
+    client:  Client<UnixConnector>,
+    socket:  PathBuf,
+    /// The network that containers are attached to. Instances are addressed by name on the
+    /// network if it is set, or by their IP address on the default bridge if it is not.
+    network: Option<String>,
+    limits:  Limits,
+}
+
+/// The resources that each container may use. A limit of zero removes it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Limits {
+    /// Memory in megabytes.
+    pub(crate) memory_mb: u64,
+    /// CPUs, which may be fractional.
+    pub(crate) cpus:      f64,
+    /// Processes, which stops fork bombs from exhausting the host.
+    pub(crate) pids:      u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Created {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Inspected {
+    state:            State,
+    network_settings: NetworkSettings,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct State {
+    running: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NetworkSettings {
+    #[serde(rename = "IPAddress")]
+    ip_address: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorMessage {
+    message: String,
+}
+
+impl Docker {
+    pub(crate) fn new(socket: impl AsRef<Path>, network: Option<String>, limits: Limits) -> Self {
+        Self {
+            client: Client::unix(),
+            socket: socket.as_ref().to_path_buf(),
+            network,
+            limits,
+        }
+    }
+
+    /// Make a request to the Docker Engine API, returning its status and body. Fails unless the
+    /// request succeeded or its status is in `allowed`.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+        allowed: &[StatusCode],
+    ) -> anyhow::Result<(StatusCode, Bytes)> {
+        let uri: hyper::Uri = hyperlocal::Uri::new(&self.socket, path).into();
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .with_context(|| format!("failed to connect to {}", self.socket.display()))?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+
+        if !status.is_success() && !allowed.contains(&status) {
+            let message = serde_json::from_slice::<ErrorMessage>(&body).map_or_else(
+                |_| String::from_utf8_lossy(&body).into_owned(),
+                |e| e.message,
+            );
+            bail!("docker {path} returned {status}: {message}");
+        }
+
+        Ok((status, body))
+    }
+
+    async fn create(&self, spec: &InstanceSpec) -> anyhow::Result<String> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let nano_cpus = (self.limits.cpus * 1e9) as u64;
+        let mut host_config = json!({
+            "Memory": self.limits.memory_mb * 1024 * 1024,
+            // Without a swap limit, containers could use as much swap again as their memory limit
+            "MemorySwap": self.limits.memory_mb * 1024 * 1024,
+            "NanoCpus": nano_cpus,
+            "PidsLimit": self.limits.pids,
+        });
+        if let Some(network) = &self.network {
+            host_config["NetworkMode"] = json!(network);
+        }
+        let body = json!({
+            "Image": spec.image,
+            "Labels": {
+                LABEL: "true",
+                "scp.service": spec.service_id.to_string(),
+                "scp.user": spec.user_id.to_string(),
+            },
+            "HostConfig": host_config,
+        });
+        let path = format!("/containers/create?name={}", spec.name());
+
+        let (status, created) = self
+            .request(
+                Method::POST,
+                &path,
+                Some(body.clone()),
+                &[StatusCode::NOT_FOUND, StatusCode::CONFLICT],
+            )
+            .await?;
+        let created = match status {
+            // The image has not been pulled yet
+            StatusCode::NOT_FOUND => {
+                let image: String =
+                    url::form_urlencoded::byte_serialize(spec.image.as_bytes()).collect();
+                self.request(
+                    Method::POST,
+                    &format!("/images/create?fromImage={image}"),
+                    None,
+                    &[],
+                )
+                .await?;
+                self.request(Method::POST, &path, Some(body), &[]).await?.1
+            },
+            // A container was left behind by a previous run of the router
+            StatusCode::CONFLICT => {
+                self.remove(&spec.name()).await?;
+                self.request(Method::POST, &path, Some(body), &[]).await?.1
+            },
+            _ => created,
+        };
+
+        Ok(serde_json::from_slice::<Created>(&created)?.id)
+    }
+
+    async fn start_instance(&self, spec: &InstanceSpec) -> anyhow::Result<Instance> {
+        let id = self.create(spec).await?;
+        self.request(
+            Method::POST,
+            &format!("/containers/{id}/start"),
+            None,
+            &[StatusCode::NOT_MODIFIED],
+        )
+        .await?;
+
+        let host = if self.network.is_some() {
+            spec.name()
+        } else {
+            let (_, inspected) = self
+                .request(Method::GET, &format!("/containers/{id}/json"), None, &[])
+                .await?;
+            serde_json::from_slice::<Inspected>(&inspected)?
+                .network_settings
+                .ip_address
+        };
+
+        Ok(Instance {
+            id,
+            address: format!("{host}:{}", spec.port),
+        })
+    }
+
+    /// Check whether a container is running. Containers that no longer exist are not.
+    async fn is_container_running(&self, id: &str) -> anyhow::Result<bool> {
+        let (status, inspected) = self
+            .request(
+                Method::GET,
+                &format!("/containers/{id}/json"),
+                None,
+                &[StatusCode::NOT_FOUND],
+            )
+            .await?;
+        if status == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        Ok(serde_json::from_slice::<Inspected>(&inspected)?
+            .state
+            .running)
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.request(
+            Method::DELETE,
+            &format!("/containers/{id}?force=true"),
+            None,
+            &[StatusCode::NOT_FOUND],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn prune_instances(&self) -> anyhow::Result<usize> {
+        let filters = json!({ "label": [LABEL] }).to_string();
+        let filters: String = url::form_urlencoded::byte_serialize(filters.as_bytes()).collect();
+        let (_, containers) = self
+            .request(
+                Method::GET,
+                &format!("/containers/json?all=true&filters={filters}"),
+                None,
+                &[],
+            )
+            .await?;
+
+        let containers = serde_json::from_slice::<Vec<Created>>(&containers)?;
+        for container in &containers {
+            self.remove(&container.id).await?;
+        }
+
+        Ok(containers.len())
+    }
+}
+
+impl Backend for Docker {
+    fn start(&self, spec: &InstanceSpec) -> BoxFuture<'static, anyhow::Result<Instance>> {
+        let (docker, spec) = (self.clone(), spec.clone());
+        Box::pin(async move { docker.start_instance(&spec).await })
+    }
+
+    fn stop(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<()>> {
+        let (docker, id) = (self.clone(), instance.id.clone());
+        Box::pin(async move { docker.remove(&id).await })
+    }
+
+    fn is_running(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<bool>> {
+        let (docker, id) = (self.clone(), instance.id.clone());
+        Box::pin(async move { docker.is_container_running(&id).await })
+    }
+
+    fn prune(&self) -> BoxFuture<'static, anyhow::Result<usize>> {
+        let docker = self.clone();
+        Box::pin(async move { docker.prune_instances().await })
+    }
+}
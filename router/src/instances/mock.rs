@@ -0,0 +1,67 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+    Mutex,
+};
+
+use futures_util::future::BoxFuture;
+
+use super::{Backend, Instance, InstanceSpec};
+
+/// Records the instances that it is asked to start and stop, without running anything.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mock {
+    /// The names of the instances that are running.
+    pub(crate) running: Arc<Mutex<Vec<String>>>,
+    /// The number of instances that have been started.
+    pub(crate) started: Arc<Mutex<usize>>,
+    /// Whether starting and stopping instances fails.
+    pub(crate) failing: Arc<AtomicBool>,
+}
+
+impl Backend for Mock {
+    fn start(&self, spec: &InstanceSpec) -> BoxFuture<'static, anyhow::Result<Instance>> {
+        let (mock, spec) = (self.clone(), spec.clone());
+        Box::pin(async move {
+            // Give concurrent requests a chance to race
+            tokio::task::yield_now().await;
+            if mock.failing.load(Ordering::SeqCst) {
+                anyhow::bail!("failed to start {}", spec.name());
+            }
+
+            *mock.started.lock().unwrap() += 1;
+            mock.running.lock().unwrap().push(spec.name());
+            Ok(Instance {
+                id:      spec.name(),
+                address: format!("{}:{}", spec.name(), spec.port),
+            })
+        })
+    }
+
+    fn stop(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<()>> {
+        let (mock, id) = (self.clone(), instance.id.clone());
+        Box::pin(async move {
+            if mock.failing.load(Ordering::SeqCst) {
+                anyhow::bail!("failed to stop {id}");
+            }
+
+            mock.running.lock().unwrap().retain(|name| *name != id);
+            Ok(())
+        })
+    }
+
+    fn is_running(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<bool>> {
+        let (mock, id) = (self.clone(), instance.id.clone());
+        Box::pin(async move { Ok(mock.running.lock().unwrap().contains(&id)) })
+    }
+
+    fn prune(&self) -> BoxFuture<'static, anyhow::Result<usize>> {
+        let mock = self.clone();
+        Box::pin(async move {
+            let mut running = mock.running.lock().unwrap();
+            let pruned = running.len();
+            running.clear();
+            Ok(pruned)
+        })
+    }
+}
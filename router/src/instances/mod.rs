@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use tracing::{info, warn};
+
+use crate::config;
+
+pub(crate) mod docker;
+#[cfg(test)]
+pub(crate) mod mock;
+#[cfg(test)]
+mod tests;
+
+/// How often idle instances are looked for.
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+/// How long an instance is trusted to be running before a request checks it again. The reaper
+/// also removes instances that have exited, so this only shortens how long a crash is noticed in.
+const LIVENESS_INTERVAL: Duration = Duration::from_secs(10);
+
+static INSTANCES: Lazy<InstanceManager> = Lazy::new(|| {
+    let config = config::get();
+    let backend: Option<Box<dyn Backend>> = match config.instance_backend {
+        BackendKind::None => None,
+        BackendKind::Docker => Some(Box::new(docker::Docker::new(
+            &config.docker_socket,
+            config.instance_network.clone(),
+            docker::Limits {
+                memory_mb: config.instance_memory_mb,
+                cpus:      config.instance_cpus,
+                pids:      config.instance_pids,
+            },
+        ))),
+    };
+
+    InstanceManager::new(backend, Duration::from_secs(config.instance_idle_secs))
+});
+
+/// Which backend runs per-user instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BackendKind {
+    /// Services with an image cannot be accessed.
+    None,
+    /// Instances are containers started through the local Docker socket.
+    Docker,
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "docker" => Ok(Self::Docker),
+            _ => Err(format!("expected one of none or docker, got {s}")),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Docker => "docker",
+        })
+    }
+}
+
+/// A user's instance of a service that should be started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InstanceSpec {
+    pub(crate) service_id: i64,
+    pub(crate) user_id:    i64,
+    /// The container image to run.
+    pub(crate) image:      String,
+    /// The port that the image listens on.
+    pub(crate) port:       u16,
+}
+
+impl InstanceSpec {
+    /// The name of the instance, which is unique to the service and user.
+    pub(crate) fn name(&self) -> String { format!("scp-{}-{}", self.service_id, self.user_id) }
+}
+
+/// A running instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Instance {
+    /// The backend's id for the instance.
+    pub(crate) id:      String,
+    /// The `host:port` that requests to the instance are proxied to.
+    pub(crate) address: String,
+}
+
+/// Starts and stops instances. Implementations should label what they start, so that instances
+/// left behind by a previous run of the router can be found by [`Backend::prune`].
+pub(crate) trait Backend: Send + Sync + 'static {
+    fn start(&self, spec: &InstanceSpec) -> BoxFuture<'static, anyhow::Result<Instance>>;
+
+    /// Stop an instance and remove it. Stopping an instance that no longer exists succeeds.
+    fn stop(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<()>>;
+
+    /// Check whether an instance is still running. Instances that have exited, crashed or been
+    /// removed are not.
+    fn is_running(&self, instance: &Instance) -> BoxFuture<'static, anyhow::Result<bool>>;
+
+    /// Stop every instance that was started by the router, returning how many were stopped.
+    fn prune(&self) -> BoxFuture<'static, anyhow::Result<usize>>;
+}
+
+/// The instance of a service held by a user.
+struct Slot {
+    instance:  Instance,
+    last_used: Instant,
+    /// When the instance was last known to be running.
+    checked:   Instant,
+}
+
+/// A slot is locked while its instance is started or stopped, so that concurrent requests from a
+/// user share one instance.
+type SharedSlot = Arc<tokio::sync::Mutex<Option<Slot>>>;
+
+/// Starts instances on demand, one per user and service, and stops them once they are idle.
+pub(crate) struct InstanceManager {
+    backend: Option<Box<dyn Backend>>,
+    idle:    Duration,
+    /// The slots of each service and user.
+    slots:   Mutex<HashMap<(i64, i64), SharedSlot>>,
+}
+
+impl InstanceManager {
+    pub(crate) fn new(backend: Option<Box<dyn Backend>>, idle: Duration) -> Self {
+        Self {
+            backend,
+            idle,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the user's instance of a service at `now`, starting it if it is not running. Instances
+    /// that have not been checked for `LIVENESS_INTERVAL` are replaced if they have exited.
+    pub(crate) async fn get(&self, spec: InstanceSpec, now: Instant) -> anyhow::Result<Instance> {
+        let Some(backend) = &self.backend else {
+            anyhow::bail!(
+                "service {} needs per-user instances, but INSTANCE_BACKEND is none",
+                spec.service_id
+            );
+        };
+
+        let slot = self
+            .slots
+            .lock()
+            .unwrap()
+            .entry((spec.service_id, spec.user_id))
+            .or_default()
+            .clone();
+        let mut slot = slot.lock().await;
+
+        if let Some(running) = slot.as_mut() {
+            let trusted = now.saturating_duration_since(running.checked) < LIVENESS_INTERVAL;
+            if trusted || backend.is_running(&running.instance).await? {
+                if !trusted {
+                    running.checked = now;
+                }
+                running.last_used = now;
+                return Ok(running.instance.clone());
+            }
+
+            warn!(
+                "instance {} of service {} for user {} has exited, replacing it",
+                running.instance.id, spec.service_id, spec.user_id
+            );
+            backend.stop(&running.instance).await?;
+            *slot = None;
+        }
+
+        let instance = backend.start(&spec).await?;
+        info!(
+            "started instance {} of service {} for user {}",
+            instance.id, spec.service_id, spec.user_id
+        );
+        *slot = Some(Slot {
+            instance:  instance.clone(),
+            last_used: now,
+            checked:   now,
+        });
+
+        Ok(instance)
+    }
+
+    /// The number of running instances.
+    pub(crate) fn len(&self) -> usize {
+        self.slots
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|slot| slot.try_lock().map_or(true, |slot| slot.is_some()))
+            .count()
+    }
+
+    /// Stop every instance that has not been used since `idle` before `now`, and remove every
+    /// instance that has exited, returning how many were stopped. Instances that fail to stop are
+    /// tried again on the next call.
+    pub(crate) async fn reap(&self, now: Instant) -> usize {
+        let Some(backend) = &self.backend else {
+            return 0;
+        };
+
+        let slots: Vec<_> = self.slots.lock().unwrap().values().cloned().collect();
+        let mut reaped = 0;
+        for slot in slots {
+            // Instances that are being started are in use
+            let Ok(mut slot) = slot.try_lock() else {
+                continue;
+            };
+            let Some(current) = slot.take() else {
+                continue;
+            };
+            let idle = now.saturating_duration_since(current.last_used) >= self.idle;
+            if !idle {
+                match backend.is_running(&current.instance).await {
+                    Ok(true) => {
+                        *slot = Some(Slot {
+                            checked: now,
+                            ..current
+                        });
+                        continue;
+                    },
+                    Ok(false) => warn!("instance {} has exited", current.instance.id),
+                    Err(e) => {
+                        warn!("failed to check instance {}: {:#}", current.instance.id, e);
+                        *slot = Some(current);
+                        continue;
+                    },
+                }
+            }
+
+            match backend.stop(&current.instance).await {
+                Ok(()) => reaped += 1,
+                Err(e) => {
+                    warn!("failed to stop instance {}: {:#}", current.instance.id, e);
+                    *slot = Some(current);
+                },
+            }
+        }
+
+        // Forget empty slots, unless a request is about to start an instance in them
+        self.slots.lock().unwrap().retain(|_, slot| {
+            Arc::strong_count(slot) > 1 || slot.try_lock().map_or(true, |s| s.is_some())
+        });
+
+        reaped
+    }
+}
+
+/// Get the user's instance of a service, starting it if it is not running.
+pub(crate) async fn get(spec: InstanceSpec) -> anyhow::Result<Instance> {
+    INSTANCES.get(spec, Instant::now()).await
+}
+
+/// Stop instances left behind by a previous run of the router, then stop instances that have been
+/// idle for `INSTANCE_IDLE_SECS` or have exited in the background.
+pub(crate) async fn start_reaper() {
+    if let Some(backend) = &INSTANCES.backend {
+        match backend.prune().await {
+            Ok(0) => {},
+            Ok(pruned) => info!("stopped {} instances left by a previous run", pruned),
+            Err(e) => warn!("failed to stop instances left by a previous run: {:#}", e),
+        }
+    }
+
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(REAP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let reaped = INSTANCES.reap(Instant::now()).await;
+            if reaped > 0 {
+                info!(
+                    "stopped {} idle or exited instances, {} still running",
+                    reaped,
+                    INSTANCES.len()
+                );
+            }
+        }
+    });
+}
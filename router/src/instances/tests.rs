@@ -0,0 +1,125 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use super::{mock::Mock, InstanceManager, InstanceSpec, LIVENESS_INTERVAL};
+
+const IDLE: Duration = Duration::from_secs(90);
+
+fn spec(service_id: i64, user_id: i64) -> InstanceSpec {
+    InstanceSpec {
+        service_id,
+        user_id,
+        image: "challenge:latest".to_string(),
+        port: 8080,
+    }
+}
+
+fn manager() -> (InstanceManager, Mock) {
+    let mock = Mock::default();
+    (
+        InstanceManager::new(Some(Box::new(mock.clone())), IDLE),
+        mock,
+    )
+}
+
+#[tokio::test]
+async fn starts_an_instance_per_user() {
+    let (manager, mock) = manager();
+
+    let first = manager.get(spec(1, 10), Instant::now()).await.unwrap();
+    assert_eq!(first.address, "scp-1-10:8080");
+    assert_eq!(
+        manager.get(spec(1, 10), Instant::now()).await.unwrap(),
+        first
+    );
+
+    manager.get(spec(1, 11), Instant::now()).await.unwrap();
+    manager.get(spec(2, 10), Instant::now()).await.unwrap();
+    assert_eq!(*mock.started.lock().unwrap(), 3);
+    assert_eq!(manager.len(), 3);
+}
+
+#[tokio::test]
+async fn shares_concurrent_starts() {
+    let (manager, mock) = manager();
+
+    let (a, b) = tokio::join!(
+        manager.get(spec(1, 10), Instant::now()),
+        manager.get(spec(1, 10), Instant::now())
+    );
+    assert_eq!(a.unwrap(), b.unwrap());
+    assert_eq!(*mock.started.lock().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn reaps_idle_instances() {
+    let (manager, mock) = manager();
+    manager.get(spec(1, 10), Instant::now()).await.unwrap();
+    manager.get(spec(1, 11), Instant::now()).await.unwrap();
+
+    assert_eq!(manager.reap(Instant::now()).await, 0);
+    assert_eq!(manager.reap(Instant::now() + IDLE).await, 2);
+    assert!(mock.running.lock().unwrap().is_empty());
+    assert_eq!(manager.len(), 0);
+
+    // Reaped instances are started again when next used
+    manager.get(spec(1, 10), Instant::now()).await.unwrap();
+    assert_eq!(*mock.started.lock().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn replaces_exited_instances() {
+    let (manager, mock) = manager();
+    manager.get(spec(1, 10), Instant::now()).await.unwrap();
+    manager.get(spec(1, 11), Instant::now()).await.unwrap();
+
+    // The first instance crashes, and is started again once it is next checked
+    mock.running
+        .lock()
+        .unwrap()
+        .retain(|name| name != "scp-1-10");
+    manager.get(spec(1, 10), Instant::now()).await.unwrap();
+    assert_eq!(*mock.started.lock().unwrap(), 2);
+    manager
+        .get(spec(1, 10), Instant::now() + LIVENESS_INTERVAL)
+        .await
+        .unwrap();
+    assert_eq!(*mock.started.lock().unwrap(), 3);
+
+    // The second instance crashes, and is removed before it becomes idle
+    mock.running
+        .lock()
+        .unwrap()
+        .retain(|name| name != "scp-1-11");
+    assert_eq!(manager.reap(Instant::now()).await, 1);
+    assert_eq!(manager.len(), 1);
+}
+
+#[tokio::test]
+async fn retries_failures() {
+    let (manager, mock) = manager();
+    mock.failing.store(true, Ordering::SeqCst);
+    assert!(manager.get(spec(1, 10), Instant::now()).await.is_err());
+    assert_eq!(manager.len(), 0);
+
+    mock.failing.store(false, Ordering::SeqCst);
+    manager.get(spec(1, 10), Instant::now()).await.unwrap();
+
+    // Instances that fail to stop are kept until they can be stopped
+    mock.failing.store(true, Ordering::SeqCst);
+    assert_eq!(manager.reap(Instant::now() + IDLE).await, 0);
+    assert_eq!(manager.len(), 1);
+
+    mock.failing.store(false, Ordering::SeqCst);
+    assert_eq!(manager.reap(Instant::now() + IDLE).await, 1);
+}
+
+#[tokio::test]
+async fn requires_a_backend() {
+    let manager = InstanceManager::new(None, IDLE);
+
+    assert!(manager.get(spec(1, 10), Instant::now()).await.is_err());
+    assert_eq!(manager.reap(Instant::now() + IDLE).await, 0);
+}
@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::RngCore;
+
+use super::valid_id;
+use crate::config;
+
+const USAGE: &str = "usage: router keys (generate [ID] | status | retire ID DEADLINE)";
+
+/// Run `router keys`, which manages the keys that sign dynamic flags.
+pub(crate) fn run(args: &[String]) -> anyhow::Result<()> {
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["generate"] => generate(&Utc::now().format("k%Y%m%d").to_string()),
+        ["generate", id] => generate(id),
+        ["status"] => {
+            status();
+            Ok(())
+        },
+        ["retire", id, deadline] => retire(id, deadline),
+        _ => bail!(USAGE),
+    }
+}
+
+/// Print a new random key, ready to be added to `HMAC_KEY`.
+fn generate(id: &str) -> anyhow::Result<()> {
+    if !valid_id(id) {
+        bail!("ID must be 1 to 16 letters, digits, `-` or `_`");
+    }
+
+    let mut secret = [0_u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let secret = secret.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    });
+
+    println!("{id}:{secret}");
+    eprintln!(
+        "Put the new key first in HMAC_KEY to sign new flags with it, and keep the old keys after \
+         it until they are retired."
+    );
+    Ok(())
+}
+
+/// Print what each key in the keyring is used for.
+fn status() {
+    let keyring = &config::init().keyring;
+    let now = Utc::now();
+
+    for (i, key) in keyring.iter().enumerate() {
+        let state = match key.retire_at {
+            _ if i == 0 => "signs new flags".to_string(),
+            None => "verifies flags".to_string(),
+            Some(at) if at <= now => format!("retired at {}, can be removed", timestamp(at)),
+            Some(at) => format!("verifies flags until {}", timestamp(at)),
+        };
+        println!("{:<16} {state}", key.id);
+    }
+}
+
+/// Print the `HMAC_KEY_RETIRE` value that retires a key at a deadline.
+fn retire(id: &str, deadline: &str) -> anyhow::Result<()> {
+    let keyring = &config::init().keyring;
+    let deadline = if deadline == "now" {
+        Utc::now()
+    } else {
+        DateTime::parse_from_rfc3339(deadline)
+            .context("DEADLINE must be an RFC 3339 timestamp or `now`")?
+            .with_timezone(&Utc)
+    };
+
+    let key = keyring
+        .get(id)
+        .with_context(|| format!("there is no key `{id}` in HMAC_KEY"))?;
+    if key.id == keyring.signing_key().id {
+        bail!("`{id}` signs new flags. Put a new key first in HMAC_KEY before retiring it");
+    }
+
+    let retirements: Vec<_> = keyring
+        .iter()
+        .filter_map(|k| {
+            let at = if k.id == id {
+                Some(deadline)
+            } else {
+                k.retire_at
+            };
+            at.map(|at| format!("{}={}", k.id, timestamp(at)))
+        })
+        .collect();
+
+    println!("HMAC_KEY_RETIRE={}", retirements.join(","));
+    eprintln!(
+        "Once this is set, flags signed with `{id}` are rejected after {}.",
+        timestamp(deadline)
+    );
+    Ok(())
+}
+
+fn timestamp(at: DateTime<Utc>) -> String { at.to_rfc3339_opts(SecondsFormat::Secs, true) }
@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub(crate) mod cli;
+#[cfg(test)]
+mod tests;
+
+pub(crate) type HmacSha256 = Hmac<Sha256>;
+
+/// A key used to sign dynamic flags.
+#[derive(Debug, Clone)]
+pub(crate) struct FlagKey {
+    /// The id embedded in flags signed with the key.
+    pub(crate) id:        String,
+    secret:               String,
+    /// When flags signed with the key stop being accepted.
+    pub(crate) retire_at: Option<DateTime<Utc>>,
+}
+
+impl FlagKey {
+    pub(crate) fn secret(&self) -> &str { &self.secret }
+
+    /// Compute the HMAC of a message with the key.
+    pub(crate) fn mac(&self, message: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes()).unwrap();
+        mac.update(message);
+        mac
+    }
+
+    pub(crate) fn is_retired(&self, now: DateTime<Utc>) -> bool {
+        self.retire_at.is_some_and(|retire_at| retire_at <= now)
+    }
+}
+
+/// Why the signature of a dynamic flag was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureError {
+    /// The signature does not match, or names a key that does not exist.
+    Invalid,
+    /// The signature was made with a key that has been retired.
+    Retired,
+}
+
+/// The keys used to sign and verify dynamic flags. New flags are signed with the first key.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Keyring {
+    keys: Vec<FlagKey>,
+}
+
+impl Keyring {
+    /// Parse keys from `[id:]key` entries separated by commas or new lines, and their retirement
+    /// deadlines from `id=deadline` entries separated by commas. Keys without an id are given the
+    /// first 8 hex digits of their SHA-256 digest.
+    pub(crate) fn parse(keys: &str, retire: &str) -> Result<Self, String> {
+        let mut ids = HashSet::new();
+        let keys = keys
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, secret) = match entry.split_once(':') {
+                    Some((id, secret)) => (id.to_string(), secret.to_string()),
+                    None => (derive_id(entry), entry.to_string()),
+                };
+                if !valid_id(&id) {
+                    return Err(format!(
+                        "key id `{id}` must be 1 to 16 letters, digits, `-` or `_`"
+                    ));
+                }
+                if !ids.insert(id.clone()) {
+                    return Err(format!("key id `{id}` is used more than once"));
+                }
+
+                Ok(FlagKey {
+                    id,
+                    secret,
+                    retire_at: None,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut keyring = Self { keys };
+        for (id, retire_at) in parse_retirements(retire)? {
+            let key = keyring
+                .keys
+                .iter_mut()
+                .find(|k| k.id == id)
+                .ok_or_else(|| format!("cannot retire unknown key `{id}`"))?;
+            key.retire_at = Some(retire_at);
+        }
+
+        match keyring.keys.first() {
+            None => Err("no keys are set".to_string()),
+            Some(key) if key.retire_at.is_some() => Err(format!(
+                "the signing key `{}` cannot be retired. Put a new key first",
+                key.id
+            )),
+            Some(_) => Ok(keyring),
+        }
+    }
+
+    /// The key that new flags are signed with.
+    pub(crate) fn signing_key(&self) -> &FlagKey { &self.keys[0] }
+
+    pub(crate) fn get(&self, id: &str) -> Option<&FlagKey> { self.keys.iter().find(|k| k.id == id) }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &FlagKey> { self.keys.iter() }
+
+    /// Find the key that signed a message, comparing signatures in constant time. Messages
+    /// without a key id were signed before key ids were embedded in flags, and are checked against
+    /// every key.
+    pub(crate) fn verify(
+        &self,
+        message: &[u8],
+        key_id: Option<&str>,
+        signature: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<&FlagKey, SignatureError> {
+        let signed = |k: &&FlagKey| k.mac(message).verify_slice(signature).is_ok();
+        let key = match key_id {
+            Some(id) => self.get(id).filter(signed),
+            None => self.iter().find(signed),
+        }
+        .ok_or(SignatureError::Invalid)?;
+
+        if key.is_retired(now) {
+            return Err(SignatureError::Retired);
+        }
+
+        Ok(key)
+    }
+}
+
+/// Parse `id=deadline` entries separated by commas, where deadlines are RFC 3339 timestamps.
+fn parse_retirements(retire: &str) -> Result<HashMap<String, DateTime<Utc>>, String> {
+    retire
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (id, deadline) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("`{entry}` should be of the form id=deadline"))?;
+            let deadline = DateTime::parse_from_rfc3339(deadline.trim())
+                .map_err(|e| format!("the deadline of `{id}` is not an RFC 3339 timestamp: {e}"))?;
+
+            Ok((id.trim().to_string(), deadline.with_timezone(&Utc)))
+        })
+        .collect()
+}
+
+fn derive_id(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))[..8].to_string()
+}
+
+fn valid_id(id: &str) -> bool {
+    (1..=16).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -0,0 +1,79 @@
+use chrono::{Duration, TimeZone, Utc};
+use hmac::Mac;
+
+use super::{Keyring, SignatureError};
+
+const A: &str = "0b7e4c29d15a8f63e2c07b94a1d5f836";
+const B: &str = "9f2c1e7a4b8d03f6a5e1c9d7b2f4086e";
+
+#[test]
+fn parses_keys() {
+    let keyring = Keyring::parse(&format!("new:{A},\n{B}"), "").unwrap();
+    let ids: Vec<_> = keyring.iter().map(|k| k.id.as_str()).collect();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(ids[0], "new");
+    assert_eq!(ids[1].len(), 8);
+    assert_eq!(keyring.signing_key().secret(), A);
+    assert_eq!(keyring.get(ids[1]).unwrap().secret(), B);
+
+    assert!(Keyring::parse("", "").is_err());
+    assert!(Keyring::parse(&format!("a:{A},a:{B}"), "").is_err());
+    assert!(Keyring::parse(&format!("a.b:{A}"), "").is_err());
+}
+
+#[test]
+fn parses_retirements() {
+    let keys = format!("new:{A},old:{B}");
+    let keyring = Keyring::parse(&keys, "old=2024-06-01T00:00:00Z").unwrap();
+    assert_eq!(
+        keyring.get("old").unwrap().retire_at,
+        Some(Utc.ymd(2024, 6, 1).and_hms(0, 0, 0))
+    );
+    assert_eq!(keyring.get("new").unwrap().retire_at, None);
+
+    assert!(Keyring::parse(&keys, "new=2024-06-01T00:00:00Z").is_err());
+    assert!(Keyring::parse(&keys, "gone=2024-06-01T00:00:00Z").is_err());
+    assert!(Keyring::parse(&keys, "old=tomorrow").is_err());
+    assert!(Keyring::parse(&keys, "old").is_err());
+}
+
+#[test]
+fn verifies_signatures() {
+    let deadline = Utc.ymd(2024, 6, 1).and_hms(0, 0, 0);
+    let keyring = Keyring::parse(&format!("new:{A},old:{B}"), "old=2024-06-01T00:00:00Z").unwrap();
+    let sign = |id| {
+        let mac = keyring.get(id).unwrap().mac(b"message").finalize();
+        mac.into_bytes().to_vec()
+    };
+    let (new, old) = (sign("new"), sign("old"));
+    let before = deadline - Duration::days(1);
+    let verify = |key_id, signature: &[u8], now| {
+        keyring
+            .verify(b"message", key_id, signature, now)
+            .map(|k| k.id.as_str())
+    };
+
+    assert_eq!(verify(Some("new"), &new, deadline), Ok("new"));
+    assert_eq!(verify(Some("old"), &old, before), Ok("old"));
+    assert_eq!(
+        verify(Some("old"), &old, deadline),
+        Err(SignatureError::Retired)
+    );
+
+    // Messages without a key id are checked against every key
+    assert_eq!(verify(None, &old, before), Ok("old"));
+    assert_eq!(verify(None, &old, deadline), Err(SignatureError::Retired));
+
+    assert_eq!(
+        verify(Some("old"), &new, before),
+        Err(SignatureError::Invalid)
+    );
+    assert_eq!(
+        verify(Some("new"), &new[..16], before),
+        Err(SignatureError::Invalid)
+    );
+    assert_eq!(
+        verify(Some("gone"), &new, before),
+        Err(SignatureError::Invalid)
+    );
+}
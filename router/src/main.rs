@@ -1,39 +1,31 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::unused_async)]
 
-use std::env;
-
 use actix_web::{
     web::{self, Data},
     App,
     HttpServer,
 };
 use migration::{Migrator, MigratorTrait};
-use once_cell::sync::Lazy;
 
+mod attachments;
+mod audit;
+mod config;
+mod feed;
+mod flag_codec;
 mod gaia_utils;
+mod gradebook;
 mod handler_utils;
+mod instances;
+mod keyring;
+mod notify;
+mod overrides;
+mod prerequisites;
+mod rate_limit;
 mod registry;
 mod routes;
-
-static JWT_PEM: Lazy<String> = once_cell::sync::Lazy::new(|| match env::var("JWT_PEM_LOC") {
-    Ok(v) => std::fs::read_to_string(v).unwrap_or_else(|_| panic!("JWT PEM missing")),
-    Err(_) => {
-        std::fs::read_to_string("/certs/jwt-key.pem").unwrap_or_else(|_| panic!("JWT PEM missing"))
-    },
-});
-
-/// HMAC key used for authenticating flags
-#[allow(clippy::match_wild_err_arm)]
-static HMAC_KEY: Lazy<String> = once_cell::sync::Lazy::new(|| match env::var("HMAC_KEY") {
-    Ok(v) => v,
-    Err(_) => {
-        panic!("HMAC_KEY env var missing")
-    },
-});
-
-static DB_URI: Lazy<String> = env_utils::lazy_env!("DB_URI", "sqlite://./db.db");
-static GAIA_ADDR: Lazy<String> = env_utils::lazy_env!("GAIA_ADDR", "gaia-backend:8081");
+mod scoring;
+mod teams;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -42,21 +34,91 @@ async fn main() -> anyhow::Result<()> {
     }
     tracing_subscriber::fmt::init();
 
-    let connection = sea_orm::Database::connect(DB_URI.as_str()).await?;
-    Migrator::up(&connection, None).await?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "keys") {
+        return keyring::cli::run(&args[1..]);
+    }
+    let config = config::init();
+
+    let connection = sea_orm::Database::connect(config.db_uri.as_str()).await?;
+    if config.auto_migrate {
+        Migrator::up(&connection, None).await?;
+    } else {
+        let pending = Migrator::get_pending_migrations(&connection).await?;
+        if !pending.is_empty() {
+            tracing::warn!(
+                "{} migrations are pending and AUTO_MIGRATE is disabled",
+                pending.len()
+            );
+        }
+    }
+
+    instances::start_reaper().await;
+
+    let solve_feed = Data::new(feed::SolveFeed::new());
+    let authz = Data::new(authz::Authz::new(
+        config.policy.clone(),
+        handler_utils::GaiaRoles,
+    ));
 
     Ok(HttpServer::new(move || {
-        App::new().app_data(Data::new(connection.clone())).service(
-            web::scope("/api")
-                .service(routes::evaluation::evaluate)
-                .service(routes::create_service::create_service)
-                .service(
-                    web::scope("/flags")
-                        .service(routes::flags::generate_flag)
-                        .service(routes::flags::submit_flag),
-                )
-                .service(web::scope("/challenges").service(routes::challenges::get_all)),
-        )
+        App::new()
+            .app_data(Data::new(connection.clone()))
+            .app_data(solve_feed.clone())
+            .app_data(authz.clone())
+            .app_data(api_error::json_config())
+            .app_data(api_error::query_config())
+            .app_data(api_error::path_config())
+            .service(
+                web::scope("/api")
+                    .service(routes::evaluation::evaluate)
+                    .service(routes::create_service::create_service)
+                    .service(routes::scoreboard::get_scoreboard)
+                    .service(routes::scoreboard::get_team_scoreboard)
+                    .service(
+                        web::scope("/teams")
+                            .service(routes::teams::get_teams)
+                            .service(routes::teams::create_team)
+                            .service(routes::teams::delete_team)
+                            .service(routes::teams::add_member)
+                            .service(routes::teams::remove_member),
+                    )
+                    .service(
+                        web::scope("/lockouts")
+                            .service(routes::lockouts::get_lockouts)
+                            .service(routes::lockouts::reset_lockouts),
+                    )
+                    .service(web::scope("/feed").service(routes::feed::get_feed))
+                    .service(web::scope("/gradebook").service(routes::gradebook::export_gradebook))
+                    .service(web::scope("/hints").service(routes::hints::unlock_hint))
+                    .service(
+                        web::scope("/overrides")
+                            .service(routes::overrides::get_overrides)
+                            .service(routes::overrides::create_override)
+                            .service(routes::overrides::delete_override),
+                    )
+                    .service(web::scope("/incidents").service(routes::incidents::get_incidents))
+                    .service(web::scope("/webhooks").service(routes::webhooks::get_deliveries))
+                    .service(web::scope("/audit").service(routes::audit::get_audit_log))
+                    .service(web::scope("/roles").service(routes::roles::invalidate_roles))
+                    .service(web::scope("/errors").service(routes::errors::get_catalog))
+                    .service(
+                        web::scope("/attempts")
+                            .service(routes::attempts::get_attempt_summary)
+                            .service(routes::attempts::get_attempts),
+                    )
+                    .service(
+                        web::scope("/flags")
+                            .service(routes::flags::generate_flag)
+                            .service(routes::flags::submit_flag),
+                    )
+                    .service(
+                        web::scope("/challenges")
+                            .service(routes::challenges::get_prerequisites)
+                            .service(routes::challenges::download_attachment)
+                            .service(routes::challenges::get_all),
+                    ),
+            )
     })
     .bind(("0.0.0.0", 8082))?
     .run()
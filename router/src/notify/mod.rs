@@ -0,0 +1,43 @@
+use router_entity::webhook_delivery;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use tracing::warn;
+use webhooks::{Delivery, Payload};
+
+use crate::config;
+
+/// Deliver an event to the configured webhooks in the background. Every delivery is recorded in
+/// the delivery log once it either succeeds or runs out of retries.
+pub(crate) fn notify(conn: &DatabaseConnection, payload: Payload) {
+    let config = &config::get().webhooks;
+    if !config.wants(payload.event) {
+        return;
+    }
+
+    let conn = conn.clone();
+    tokio::spawn(async move {
+        for delivery in webhooks::deliver(config, &payload).await {
+            if let Err(e) = record(&conn, delivery).await {
+                warn!("failed to record webhook delivery: {}", e);
+            }
+        }
+    });
+}
+
+async fn record(conn: &DatabaseConnection, delivery: Delivery) -> anyhow::Result<()> {
+    webhook_delivery::ActiveModel {
+        id:             Set(delivery.id),
+        event:          Set(delivery.event.as_str().to_string()),
+        url:            Set(delivery.url),
+        payload:        Set(delivery.body),
+        attempts:       Set(delivery.attempts.try_into()?),
+        status_code:    Set(delivery.status_code.map(i32::from)),
+        success:        Set(delivery.success),
+        error:          Set(delivery.error),
+        started_time:   Set(delivery.started_at),
+        completed_time: Set(delivery.completed_at),
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(())
+}
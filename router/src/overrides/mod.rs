@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use router_entity::{
+    challenge::{self, LatePolicy},
+    deadline_override,
+    flag,
+    service,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+#[cfg(test)]
+mod tests;
+
+/// The period in which a user may access a service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Window {
+    pub(crate) not_before: Option<DateTime<Utc>>,
+    pub(crate) not_after:  Option<DateTime<Utc>>,
+}
+
+impl Window {
+    /// Whether the window has opened at the supplied time.
+    pub(crate) fn has_started(&self, now: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|dt| now >= dt)
+    }
+}
+
+/// The time after which submissions for a flag are late and how late submissions are handled.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Deadline {
+    pub(crate) time:    DateTime<Utc>,
+    pub(crate) policy:  LatePolicy,
+    /// The percentage of the flag's value that is deducted for late submissions.
+    pub(crate) penalty: i32,
+}
+
+impl Deadline {
+    /// How late a submission made at the supplied time is, if at all.
+    pub(crate) fn lateness(&self, now: DateTime<Utc>) -> Option<Duration> {
+        (now > self.time).then(|| now - self.time)
+    }
+}
+
+/// The deadline overrides that have been granted to a user.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Overrides {
+    challenges: HashMap<i64, deadline_override::Model>,
+    services:   HashMap<i64, deadline_override::Model>,
+}
+
+impl Overrides {
+    /// Get the window in which the user may access a service. Overrides for the service take
+    /// precedence over overrides for the service's challenge.
+    pub(crate) fn window(&self, service: &service::Model) -> Window {
+        let o = self
+            .services
+            .get(&service.id)
+            .or_else(|| self.challenges.get(&service.challenge_id));
+
+        Window {
+            not_before: o.and_then(|o| o.not_before).or(service.not_before),
+            not_after:  o.and_then(|o| o.not_after).or(service.not_after),
+        }
+    }
+
+    /// Get the submission deadline for a flag. Flags with their own deadline take precedence over
+    /// the challenge's deadline. A challenge override with a `not_after` replaces the deadline for
+    /// the user.
+    pub(crate) fn deadline(
+        &self,
+        challenge: &challenge::Model,
+        flag: &flag::Model,
+    ) -> Option<Deadline> {
+        let mut deadline = match (flag.deadline, challenge.deadline) {
+            (Some(time), _) => Deadline {
+                time,
+                policy: flag.late_policy.clone(),
+                penalty: flag.late_penalty,
+            },
+            (None, Some(time)) => Deadline {
+                time,
+                policy: challenge.late_policy.clone(),
+                penalty: challenge.late_penalty,
+            },
+            (None, None) => return None,
+        };
+
+        if let Some(extended) = self.challenges.get(&challenge.id).and_then(|o| o.not_after) {
+            deadline.time = extended;
+        }
+
+        Some(deadline)
+    }
+}
+
+/// Get all deadline overrides for a user. If there are multiple overrides for the same challenge or
+/// service, the most recently created one is used.
+pub(crate) async fn get_overrides<C>(conn: &C, user_id: i64) -> Result<Overrides, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut overrides = Overrides::default();
+    for o in deadline_override::Entity::find()
+        .filter(deadline_override::Column::UserId.eq(user_id))
+        .order_by_asc(deadline_override::Column::Id)
+        .all(conn)
+        .await?
+    {
+        if let Some(service_id) = o.service_id {
+            overrides.services.insert(service_id, o);
+        } else if let Some(challenge_id) = o.challenge_id {
+            overrides.challenges.insert(challenge_id, o);
+        }
+    }
+
+    Ok(overrides)
+}
+
+/// Determine whether at least one of a challenge's services has opened for a user.
+pub(crate) async fn has_started<C>(conn: &C, user_id: i64, challenge_id: i64) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let overrides = get_overrides(conn, user_id).await?;
+    let now = Utc::now();
+
+    Ok(service::Entity::find()
+        .filter(service::Column::ChallengeId.eq(challenge_id))
+        .all(conn)
+        .await?
+        .iter()
+        .any(|s| overrides.window(s).has_started(now)))
+}
@@ -0,0 +1,91 @@
+use chrono::{Duration, TimeZone, Utc};
+use router_entity::{deadline_override, service};
+
+use super::{Overrides, Window};
+
+fn service(id: i64, challenge_id: i64) -> service::Model {
+    service::Model {
+        id,
+        challenge_id,
+        category_id: 1,
+        name: "test".to_string(),
+        internal_hostname: format!("{id}.internal"),
+        external_hostname: format!("{id}.external"),
+        not_before: Some(Utc.ymd(2022, 6, 1).and_hms(0, 0, 0)),
+        not_after: Some(Utc.ymd(2022, 6, 8).and_hms(0, 0, 0)),
+        image: None,
+        port: None,
+    }
+}
+
+fn extension(
+    challenge_id: Option<i64>,
+    service_id: Option<i64>,
+    days: i64,
+) -> deadline_override::Model {
+    deadline_override::Model {
+        id: 1,
+        user_id: 1,
+        challenge_id,
+        service_id,
+        not_before: None,
+        not_after: Some(Utc.ymd(2022, 6, 8).and_hms(0, 0, 0) + Duration::days(days)),
+        reason: None,
+    }
+}
+
+#[test]
+fn no_overrides_uses_service_window() {
+    let s = service(1, 1);
+
+    assert_eq!(
+        Overrides::default().window(&s),
+        Window {
+            not_before: s.not_before,
+            not_after:  s.not_after,
+        }
+    );
+}
+
+#[test]
+fn challenge_override_replaces_deadline() {
+    let s = service(1, 1);
+    let mut overrides = Overrides::default();
+    overrides.challenges.insert(1, extension(Some(1), None, 3));
+
+    let window = overrides.window(&s);
+    assert_eq!(window.not_before, s.not_before);
+    assert_eq!(
+        window.not_after,
+        Some(Utc.ymd(2022, 6, 11).and_hms(0, 0, 0))
+    );
+}
+
+#[test]
+fn service_override_takes_precedence() {
+    let s = service(1, 1);
+    let mut overrides = Overrides::default();
+    overrides.challenges.insert(1, extension(Some(1), None, 3));
+    overrides.services.insert(1, extension(None, Some(1), 7));
+
+    assert_eq!(
+        overrides.window(&s).not_after,
+        Some(Utc.ymd(2022, 6, 15).and_hms(0, 0, 0))
+    );
+    assert_eq!(
+        overrides.window(&service(2, 1)).not_after,
+        Some(Utc.ymd(2022, 6, 11).and_hms(0, 0, 0))
+    );
+}
+
+#[test]
+fn window_start() {
+    let window = Window {
+        not_before: Some(Utc.ymd(2022, 6, 1).and_hms(0, 0, 0)),
+        not_after:  None,
+    };
+
+    assert!(!window.has_started(Utc.ymd(2022, 5, 31).and_hms(23, 59, 59)));
+    assert!(window.has_started(Utc.ymd(2022, 6, 1).and_hms(0, 0, 0)));
+    assert!(Window::default().has_started(Utc::now()));
+}
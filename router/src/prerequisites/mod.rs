@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use router_entity::{
+    flag,
+    prerequisite::{self, PrerequisiteKind},
+    submission,
+};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+
+use crate::{scoring, teams};
+
+#[cfg(test)]
+mod tests;
+
+/// A flag that cannot be solved until another flag has been, as `(locked, required)`.
+pub(crate) type Dependency = (String, String);
+
+/// The challenges and flags that a user has not unlocked yet.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Locked {
+    pub(crate) challenges: HashSet<i64>,
+    pub(crate) flags:      HashSet<String>,
+}
+
+/// The flags a user has solved and the points they have earned in each category.
+#[derive(Debug, Clone, Default)]
+struct Progress {
+    solved:          HashSet<String>,
+    category_points: HashMap<i64, i64>,
+}
+
+impl Progress {
+    fn satisfies(&self, rule: &prerequisite::Model) -> bool {
+        match rule.kind {
+            PrerequisiteKind::SolveFlag => rule
+                .required_flag_id
+                .as_ref()
+                .is_none_or(|f| self.solved.contains(f)),
+            PrerequisiteKind::CategoryPoints => match rule.required_category_id {
+                Some(category_id) => {
+                    self.category_points.get(&category_id).copied().unwrap_or(0)
+                        >= i64::from(rule.required_points.unwrap_or(0))
+                },
+                None => true,
+            },
+        }
+    }
+}
+
+/// Calculate a user's progress. Flags submitted by the user's team count towards their progress.
+///
+/// Flags count towards category points with their base points, rather than the value they have
+/// decayed to, so that other users' solves cannot take away what a user has unlocked.
+async fn get_progress<C>(conn: &C, user_id: i64) -> Result<Progress, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut condition = Condition::any().add(submission::Column::UserId.eq(user_id));
+    if let Some(team_id) = teams::get_team_id(conn, user_id).await? {
+        condition = condition.add(submission::Column::TeamId.eq(team_id));
+    }
+
+    let solved_flags: Vec<(submission::Model, flag::Model)> = submission::Entity::find()
+        .filter(condition)
+        .find_also_related(flag::Entity)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|(s, f)| Some((s, f?)))
+        .collect();
+
+    let mut progress = Progress::default();
+    for (s, f) in solved_flags {
+        if !progress.solved.insert(f.id.clone()) {
+            continue;
+        }
+
+        *progress.category_points.entry(f.category_id).or_insert(0) +=
+            scoring::submission_points(&f, f.points, &s);
+    }
+
+    Ok(progress)
+}
+
+/// Determine which challenges and flags a user has not unlocked yet.
+pub(crate) async fn get_locked<C>(conn: &C, user_id: i64) -> Result<Locked, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let rules = prerequisite::Entity::find().all(conn).await?;
+    if rules.is_empty() {
+        return Ok(Locked::default());
+    }
+
+    let progress = get_progress(conn, user_id).await?;
+
+    let mut locked = Locked::default();
+    for rule in rules.iter().filter(|r| !progress.satisfies(r)) {
+        match &rule.locked_flag_id {
+            Some(flag_id) => {
+                locked.flags.insert(flag_id.clone());
+            },
+            None => {
+                locked.challenges.insert(rule.challenge_id);
+            },
+        }
+    }
+
+    Ok(locked)
+}
+
+/// Determine which flags existing rules make depend on other flags. Rules that lock a whole
+/// challenge make each of the challenge's flags depend on the required flag.
+pub(crate) async fn get_dependencies<C>(conn: &C) -> Result<Vec<Dependency>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let rules = prerequisite::Entity::find()
+        .filter(prerequisite::Column::Kind.eq(PrerequisiteKind::SolveFlag))
+        .all(conn)
+        .await?;
+
+    let locked_challenges: HashSet<i64> = rules
+        .iter()
+        .filter(|r| r.locked_flag_id.is_none())
+        .map(|r| r.challenge_id)
+        .collect();
+    let mut challenge_flags: HashMap<i64, Vec<String>> = HashMap::new();
+    if !locked_challenges.is_empty() {
+        for f in flag::Entity::find()
+            .filter(flag::Column::ChallengeId.is_in(locked_challenges))
+            .all(conn)
+            .await?
+        {
+            challenge_flags
+                .entry(f.challenge_id)
+                .or_default()
+                .push(f.id);
+        }
+    }
+
+    let mut dependencies = Vec::new();
+    for rule in rules {
+        let Some(required) = rule.required_flag_id else {
+            continue;
+        };
+        match rule.locked_flag_id {
+            Some(locked) => dependencies.push((locked, required)),
+            None => dependencies.extend(
+                challenge_flags
+                    .get(&rule.challenge_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|locked| (locked.clone(), required.clone())),
+            ),
+        }
+    }
+
+    Ok(dependencies)
+}
+
+/// Whether flags depend on each other in a cycle, in which case none of the flags in it could
+/// ever be unlocked.
+pub(crate) fn has_cycle(dependencies: &[Dependency]) -> bool {
+    let mut graph: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (locked, required) in dependencies {
+        graph.entry(locked).or_default().push(required);
+    }
+
+    // Depth first search from each flag, where a cycle is found if a flag on the current path is
+    // reached again
+    let mut finished = HashSet::new();
+    for &start in graph.keys() {
+        if finished.contains(start) {
+            continue;
+        }
+
+        let mut path = HashSet::from([start]);
+        let mut stack = vec![(start, 0)];
+        while let Some((flag, next)) = stack.last_mut() {
+            let Some(&required) = graph.get(flag).and_then(|r| r.get(*next)) else {
+                path.remove(*flag);
+                finished.insert(*flag);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+
+            if path.contains(required) {
+                return true;
+            }
+            if !finished.contains(required) {
+                path.insert(required);
+                stack.push((required, 0));
+            }
+        }
+    }
+
+    false
+}
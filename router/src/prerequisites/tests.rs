@@ -0,0 +1,134 @@
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use router_entity::{
+    category,
+    challenge::{self, LatePolicy},
+    flag::{self, FlagType, ScoringMode},
+    prerequisite::{self, PrerequisiteKind},
+    submission,
+    user,
+};
+use sea_orm::{ActiveModelTrait, Database, Set};
+
+use super::{get_locked, has_cycle, Dependency};
+
+fn dependencies(edges: &[(&str, &str)]) -> Vec<Dependency> {
+    edges
+        .iter()
+        .map(|(locked, required)| ((*locked).to_string(), (*required).to_string()))
+        .collect()
+}
+
+#[test]
+fn allows_chains() {
+    assert!(!has_cycle(&[]));
+    assert!(!has_cycle(&dependencies(&[
+        ("b", "a"),
+        ("c", "b"),
+        ("d", "b")
+    ])));
+    assert!(!has_cycle(&dependencies(&[
+        ("c", "a"),
+        ("c", "b"),
+        ("b", "a"),
+        ("d", "c"),
+    ])));
+}
+
+#[test]
+fn finds_cycles() {
+    assert!(has_cycle(&dependencies(&[("a", "a")])));
+    assert!(has_cycle(&dependencies(&[("a", "b"), ("b", "a")])));
+    assert!(has_cycle(&dependencies(&[
+        ("x", "a"),
+        ("a", "b"),
+        ("b", "c"),
+        ("c", "a"),
+    ])));
+}
+
+#[tokio::test]
+async fn decay_does_not_relock_challenges() {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    for id in [1, 2] {
+        challenge::ActiveModel {
+            id:           Set(id),
+            team_mode:    Set(false),
+            description:  Set(String::new()),
+            author:       Set(None),
+            difficulty:   Set(None),
+            deadline:     Set(None),
+            late_policy:  Set(LatePolicy::Reject),
+            late_penalty: Set(0),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    category::ActiveModel {
+        id:   Set(1),
+        name: Set("web".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    flag::ActiveModel {
+        id:                 Set("f1".to_string()),
+        challenge_id:       Set(1),
+        category_id:        Set(1),
+        flag:               Set("flag".to_string()),
+        flag_type:          Set(FlagType::Static),
+        points:             Set(100),
+        display_name:       Set("Flag".to_string()),
+        scoring_mode:       Set(ScoringMode::Decay),
+        minimum_points:     Set(Some(10)),
+        decay:              Set(Some(2)),
+        deadline:           Set(None),
+        late_policy:        Set(LatePolicy::Reject),
+        late_penalty:       Set(0),
+        first_blood_bonus:  Set(0),
+        second_blood_bonus: Set(0),
+        third_blood_bonus:  Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    // The second challenge needs 100 points in the category
+    prerequisite::ActiveModel {
+        id:                   Set(1),
+        challenge_id:         Set(2),
+        locked_flag_id:       Set(None),
+        kind:                 Set(PrerequisiteKind::CategoryPoints),
+        required_flag_id:     Set(None),
+        required_category_id: Set(Some(1)),
+        required_points:      Set(Some(100)),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    for id in 1..=3 {
+        user::ActiveModel { id: Set(id) }
+            .insert(&conn)
+            .await
+            .unwrap();
+        submission::ActiveModel {
+            id:              Set(id),
+            user_id:         Set(id),
+            flag_id:         Set("f1".to_string()),
+            submission_time: Set(Utc::now()),
+            team_id:         Set(None),
+            late_seconds:    Set(None),
+            late_penalty:    Set(0),
+            blood:           Set(None),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+
+        // The flag decays with every solve, but the first solver keeps access
+        assert!(get_locked(&conn, 1).await.unwrap().challenges.is_empty());
+    }
+}
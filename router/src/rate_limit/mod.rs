@@ -0,0 +1,127 @@
+use chrono::{Duration, Utc};
+use idgenerator::IdInstance;
+use router_entity::{attempt, lockout};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DbErr,
+    EntityTrait,
+    PaginatorTrait,
+    QueryFilter,
+    QueryOrder,
+    Set,
+};
+
+use crate::config;
+
+#[cfg(test)]
+mod tests;
+
+/// Calculate how long a user should be locked out of a flag for after a number of consecutive
+/// failures.
+pub(crate) fn backoff(failures: i32, free_attempts: i32, base: i64, max: i64) -> Option<Duration> {
+    if failures < free_attempts {
+        return None;
+    }
+
+    // Cap the exponent so the multiplication cannot overflow
+    let exponent = (failures - free_attempts).min(32);
+    let seconds = base.saturating_mul(1 << exponent).min(max);
+
+    Some(Duration::seconds(seconds))
+}
+
+/// Determine whether a user may submit a flag right now. Returns how long the user must wait if
+/// they may not.
+pub(crate) async fn check<C>(
+    conn: &C,
+    user_id: i64,
+    flag_id: &str,
+) -> Result<Option<Duration>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let now = Utc::now();
+
+    // Per flag lockout
+    if let Some(locked_until) = lockout::Entity::find()
+        .filter(lockout::Column::UserId.eq(user_id))
+        .filter(lockout::Column::FlagId.eq(flag_id))
+        .one(conn)
+        .await?
+        .and_then(|l| l.locked_until)
+    {
+        if locked_until > now {
+            return Ok(Some(locked_until - now));
+        }
+    }
+
+    // Per user limit across all flags
+    let window_start = now - Duration::minutes(1);
+    let recent = attempt::Entity::find()
+        .filter(attempt::Column::UserId.eq(user_id))
+        .filter(attempt::Column::AttemptTime.gt(window_start))
+        .order_by_asc(attempt::Column::AttemptTime);
+
+    if recent.clone().count(conn).await? >= config::get().submit_user_limit {
+        if let Some(oldest) = recent.one(conn).await? {
+            return Ok(Some(oldest.attempt_time - window_start));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Record a failed submission and lock the user out of the flag if they have made too many.
+pub(crate) async fn record_failure<C>(conn: &C, user_id: i64, flag_id: &str) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let existing = lockout::Entity::find()
+        .filter(lockout::Column::UserId.eq(user_id))
+        .filter(lockout::Column::FlagId.eq(flag_id))
+        .one(conn)
+        .await?;
+
+    let failures = existing.as_ref().map_or(0, |l| l.failures) + 1;
+    let config = config::get();
+    let locked_until = backoff(
+        failures,
+        config.submit_free_attempts,
+        config.submit_backoff_base,
+        config.submit_backoff_max,
+    )
+    .map(|duration| Utc::now() + duration);
+
+    if let Some(l) = existing {
+        let mut l: lockout::ActiveModel = l.into();
+        l.failures = Set(failures);
+        l.locked_until = Set(locked_until);
+        l.update(conn).await?;
+    } else {
+        let new_lockout = lockout::ActiveModel {
+            id:           Set(IdInstance::next_id()),
+            user_id:      Set(user_id),
+            flag_id:      Set(flag_id.to_string()),
+            failures:     Set(failures),
+            locked_until: Set(locked_until),
+        };
+        new_lockout.insert(conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Clear any lockouts for a user. If no flag is provided, lockouts for all flags are cleared.
+pub(crate) async fn clear<C>(conn: &C, user_id: i64, flag_id: Option<&str>) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut query = lockout::Entity::delete_many().filter(lockout::Column::UserId.eq(user_id));
+    if let Some(flag_id) = flag_id {
+        query = query.filter(lockout::Column::FlagId.eq(flag_id));
+    }
+
+    Ok(query.exec(conn).await?.rows_affected)
+}
@@ -0,0 +1,25 @@
+use chrono::Duration;
+
+use super::backoff;
+
+#[test]
+fn allows_free_attempts() {
+    assert_eq!(backoff(0, 5, 30, 3600), None);
+    assert_eq!(backoff(4, 5, 30, 3600), None);
+}
+
+#[test]
+fn doubles_after_each_failure() {
+    assert_eq!(backoff(5, 5, 30, 3600), Some(Duration::seconds(30)));
+    assert_eq!(backoff(6, 5, 30, 3600), Some(Duration::seconds(60)));
+    assert_eq!(backoff(8, 5, 30, 3600), Some(Duration::seconds(240)));
+}
+
+#[test]
+fn caps_at_maximum() {
+    assert_eq!(backoff(12, 5, 30, 3600), Some(Duration::seconds(3600)));
+    assert_eq!(
+        backoff(i32::MAX, 5, 30, 3600),
+        Some(Duration::seconds(3600))
+    );
+}
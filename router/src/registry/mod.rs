@@ -1,9 +1,16 @@
+use authz::{Permission, Roles};
 use router_entity::entities::service;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use thiserror::Error;
-use tracing::{error, warn};
+use tracing::error;
 
-use crate::gaia_utils;
+use crate::{
+    config,
+    handler_utils,
+    instances::{self, InstanceSpec},
+    overrides::{self, Window},
+    prerequisites,
+};
 
 pub mod services;
 
@@ -15,31 +22,29 @@ pub(crate) enum EvaluationErrors {
     NotFound,
     #[error("The URI that was supplied did not have a valid host.")]
     InvalidUriError,
-    #[error("User did not have relevant roles.")]
-    NoRoles,
     #[error("An internal error occurred.")]
     InternalError,
+    #[error("The user's instance of the service could not be started.")]
+    InstanceUnavailable,
 }
 
 /// Determine which address a supplied URI should be proxied to.
+///
+/// # Params
+///
+/// - `token`: the JWT token used to identify the current user.
+/// - `roles`: the roles of the current user.
 #[tracing::instrument]
 pub(crate) async fn evaluate_uri(
     uri: url::Url,
     token: &str,
+    roles: &Roles,
     conn: &DatabaseConnection,
 ) -> Result<url::Url, EvaluationErrors> {
     if !uri.has_host() {
         return Err(EvaluationErrors::InvalidUriError);
     }
 
-    let roles = gaia_utils::get_roles(token).await.map_err(|e| {
-        warn!(
-            "user has no roles or there was an error fetching them: {:?}",
-            e
-        );
-        EvaluationErrors::NoRoles
-    })?;
-
     // If a user does not have any roles at all, they are blacklisted from the system.
     if roles.is_empty() {
         return Err(EvaluationErrors::Forbidden);
@@ -56,36 +61,78 @@ pub(crate) async fn evaluate_uri(
         })?
         .ok_or(EvaluationErrors::NotFound)?;
 
-    // Create a new modified url that has the new host set appropriately and no path components
-    let new_uri =
-        url::Url::parse(&format!("http://{}", service.internal_hostname)).map_err(|e| {
-            error!("failed to parse destination hostname: {}", e);
-            EvaluationErrors::InternalError
-        })?;
-
-    let not_admin = !roles.contains("tutor") && !roles.contains("admin");
+    let user_id = || {
+        intra_jwt::verify_jwt(token, config::get().jwt_pem.as_str())
+            .ok()
+            .and_then(|claims| handler_utils::parse_user_id(&claims.user_id))
+            .ok_or(EvaluationErrors::Forbidden)
+    };
+    let not_admin = !roles.can(Permission::ViewUnreleased);
+    let mut window = Window {
+        not_before: service.not_before,
+        not_after:  service.not_after,
+    };
 
-    // Determine if the user is allowed to access this service
-    if let Some(dt) = service.not_before {
-        if chrono::offset::Utc::now().le(&dt) {
-            // Determine if the user has appropriate access rights
-            if not_admin {
-                // The user should not be able to access the service right now
-                // Return not allowed
-                return Err(EvaluationErrors::Forbidden);
-            }
+    // Determine if the user has unlocked the challenge that the service belongs to
+    if not_admin {
+        let uid = user_id()?;
 
-            // Otherwise they should be allowed to pass
-            return Ok(new_uri);
+        let locked = prerequisites::get_locked(conn, uid).await.map_err(|e| {
+            error!("failed to determine locked challenges: {}", e);
+            EvaluationErrors::InternalError
+        })?;
+        if locked.challenges.contains(&service.challenge_id) {
+            return Err(EvaluationErrors::Forbidden);
         }
+
+        // Students may have been granted an individual window for the service
+        let overrides = overrides::get_overrides(conn, uid).await.map_err(|e| {
+            error!("failed to fetch deadline overrides: {}", e);
+            EvaluationErrors::InternalError
+        })?;
+        window = overrides.window(&service);
     }
 
-    // Determine if we are passed the last date that submissions could be entered
-    if let Some(dt) = service.not_after {
-        if chrono::offset::Utc::now().ge(&dt) && not_admin {
-            return Err(EvaluationErrors::Forbidden);
-        }
+    // Determine if the user is allowed to access this service. Users that can see unreleased
+    // challenges may access it at any time
+    let now = chrono::offset::Utc::now();
+    let before = window.not_before.is_some_and(|dt| now.le(&dt));
+    let after = window.not_after.is_some_and(|dt| now.ge(&dt));
+    if not_admin && (before || after) {
+        return Err(EvaluationErrors::Forbidden);
     }
 
-    Ok(new_uri)
+    // Services with an image are run separately for each user, so that one user cannot break the
+    // service for everyone else
+    let host = match service.image.clone() {
+        Some(image) => {
+            let spec = InstanceSpec {
+                service_id: service.id,
+                user_id: user_id()?,
+                image,
+                port: service
+                    .port
+                    .and_then(|port| u16::try_from(port).ok())
+                    .unwrap_or(80),
+            };
+
+            instances::get(spec)
+                .await
+                .map_err(|e| {
+                    error!(
+                        "failed to start instance of service {}: {:#}",
+                        service.id, e
+                    );
+                    EvaluationErrors::InstanceUnavailable
+                })?
+                .address
+        },
+        None => service.internal_hostname,
+    };
+
+    // Create a new modified url that has the new host set appropriately and no path components
+    url::Url::parse(&format!("http://{host}")).map_err(|e| {
+        error!("failed to parse destination hostname: {}", e);
+        EvaluationErrors::InternalError
+    })
 }
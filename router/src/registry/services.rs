@@ -1,6 +1,9 @@
 use std::collections::HashSet;
 
-use crate::routes::create_service::{NewFlag, NewService};
+use crate::{
+    prerequisites::{self, Dependency},
+    routes::create_service::{NewAttachment, NewFlag, NewHint, NewPrerequisite, NewService},
+};
 
 /// Valiadate a list of new services. Returns whether or not the service definitions are valid.
 pub(crate) fn validate_services(service_definitions: &[NewService]) -> bool {
@@ -28,6 +31,13 @@ pub(crate) fn validate_services(service_definitions: &[NewService]) -> bool {
         return false;
     }
 
+    // Ensure that instances have an image and a port to connect to
+    if !service_definitions.iter().all(|service| {
+        service.image.as_ref().is_none_or(|image| !image.is_empty()) && service.port != Some(0)
+    }) {
+        return false;
+    }
+
     // Ensure that timestamps are valid
     service_definitions.iter().all(|svc| {
         if svc.naf.is_none() || svc.nbf.is_none() {
@@ -63,5 +73,143 @@ pub(crate) fn validate_flags(flag_definitions: &[NewFlag]) -> bool {
         return false;
     }
 
-    true
+    // Ensure all late submission policies are valid
+    if !flag_definitions
+        .iter()
+        .all(|f| validate_late_policy(f.late_policy.as_deref(), f.late_penalty))
+    {
+        return false;
+    }
+
+    // Ensure that there are at most three non-negative blood bonuses
+    if !flag_definitions
+        .iter()
+        .all(|f| f.blood_bonus.len() <= 3 && f.blood_bonus.iter().all(|b| *b >= 0))
+    {
+        return false;
+    }
+
+    // Ensure all scoring modes are valid and decaying flags have sensible parameters
+    flag_definitions
+        .iter()
+        .all(|f| match f.scoring_mode.as_deref() {
+            None | Some("static") => true,
+            Some("decay") => match (f.minimum, f.decay) {
+                (Some(minimum), Some(decay)) => minimum >= 0 && minimum <= f.points && decay > 0,
+                _ => false,
+            },
+            Some(_) => false,
+        })
+}
+
+/// Validates a list of new prerequisites for the flags of a new challenge. Returns whether or not
+/// the prerequisite definitions are valid.
+pub(crate) fn validate_prerequisites(
+    prerequisite_definitions: &[NewPrerequisite],
+    flag_definitions: &[NewFlag],
+) -> bool {
+    let flag_ids: HashSet<&str> = flag_definitions.iter().map(|f| f.id.as_str()).collect();
+
+    let valid = prerequisite_definitions.iter().all(|p| {
+        // Locked flags must be part of the challenge
+        if let Some(locked) = &p.flag {
+            if !flag_ids.contains(locked.as_str()) {
+                return false;
+            }
+        }
+
+        match (&p.requires_flag, &p.requires_category, p.requires_points) {
+            (Some(required), None, None) => match &p.flag {
+                // A flag cannot require itself
+                Some(locked) => locked != required,
+                // A locked challenge cannot require one of its own flags
+                None => !flag_ids.contains(required.as_str()),
+            },
+            (None, Some(category), Some(points)) => !category.is_empty() && points > 0,
+            _ => false,
+        }
+    });
+
+    // Ensure that the new flags do not depend on each other in a cycle
+    valid
+        && !prerequisites::has_cycle(&prerequisite_dependencies(
+            prerequisite_definitions,
+            flag_definitions,
+        ))
+}
+
+/// Determine which flags the prerequisites for a new challenge make depend on other flags.
+/// Prerequisites that lock the whole challenge make each of its flags depend on the required flag.
+pub(crate) fn prerequisite_dependencies(
+    prerequisite_definitions: &[NewPrerequisite],
+    flag_definitions: &[NewFlag],
+) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    for p in prerequisite_definitions {
+        let Some(required) = &p.requires_flag else {
+            continue;
+        };
+        match &p.flag {
+            Some(locked) => dependencies.push((locked.clone(), required.clone())),
+            None => dependencies.extend(
+                flag_definitions
+                    .iter()
+                    .map(|f| (f.id.clone(), required.clone())),
+            ),
+        }
+    }
+
+    dependencies
+}
+
+/// Validates a list of new hints for the flags of a new challenge. Returns whether or not the hint
+/// definitions are valid.
+pub(crate) fn validate_hints(hint_definitions: &[NewHint], flag_definitions: &[NewFlag]) -> bool {
+    hint_definitions.iter().all(|h| {
+        !h.content.is_empty()
+            && h.cost >= 0
+            && h.flag
+                .as_ref()
+                .is_none_or(|f| flag_definitions.iter().any(|d| &d.id == f))
+    })
+}
+
+/// Validates the descriptive details of a new challenge. Returns whether or not the details are
+/// valid.
+pub(crate) fn validate_details(difficulty: Option<&str>, tags: &[String]) -> bool {
+    if !matches!(difficulty, None | Some("easy" | "medium" | "hard")) {
+        return false;
+    }
+
+    tags.iter().all(|t| !t.is_empty())
+}
+
+/// Validates a list of new attachments for a new challenge. Returns whether or not the attachment
+/// definitions are valid.
+pub(crate) fn validate_attachments(attachment_definitions: &[NewAttachment]) -> bool {
+    // Ensure that all attachment names are unique
+    if attachment_definitions
+        .iter()
+        .map(|a| a.name.as_str())
+        .collect::<HashSet<&str>>()
+        .len()
+        != attachment_definitions.len()
+    {
+        return false;
+    }
+
+    // Names are used as the download file name, so they must not contain paths or quotes
+    attachment_definitions.iter().all(|a| {
+        !a.name.is_empty()
+            && !a
+                .name
+                .chars()
+                .any(|c| matches!(c, '/' | '\\' | '"') || c.is_control())
+            && a.content_type.as_ref().is_none_or(|c| !c.is_empty())
+    })
+}
+
+/// Validates a late submission policy. Returns whether or not the policy is valid.
+pub(crate) fn validate_late_policy(policy: Option<&str>, penalty: i32) -> bool {
+    matches!(policy, None | Some("reject" | "penalty")) && (0..=100).contains(&penalty)
 }
@@ -0,0 +1,219 @@
+use actix_web::{get, web, Error, HttpResponse};
+use authz::{perm, RequirePermission};
+use chrono::Utc;
+use router_entity::attempt::{self, AttemptOutcome};
+use sea_orm::{
+    sea_query::Expr,
+    ActiveEnum,
+    ColumnTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    FromQueryResult,
+    QueryFilter,
+    QueryOrder,
+    QuerySelect,
+    Select,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::handler_utils::ise;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of attempts returned when no limit is given.
+const DEFAULT_LIMIT: u64 = 100;
+/// The most attempts that are returned at once.
+const MAX_LIMIT: u64 = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct AttemptQueryParams {
+    pub(crate) user_id:     Option<i64>,
+    pub(crate) flag_id:     Option<String>,
+    /// Only include attempts that were rejected.
+    #[serde(default)]
+    pub(crate) failed_only: bool,
+    /// Only include attempts made after this time.
+    pub(crate) since:       Option<chrono::DateTime<Utc>>,
+    /// Only include attempts made before this time.
+    pub(crate) until:       Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct AttemptPageParams {
+    /// Only include attempts older than the attempt with this id, to get the next page.
+    pub(crate) before: Option<i64>,
+    /// Only include attempts newer than the attempt with this id, to get the previous page.
+    pub(crate) after:  Option<i64>,
+    /// The maximum number of attempts to return. Defaults to 100 and is capped at 1000.
+    pub(crate) limit:  Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AttemptSummary {
+    pub(crate) user_id:         i64,
+    pub(crate) flag_id:         String,
+    pub(crate) attempts:        i64,
+    pub(crate) failed:          i64,
+    /// The number of distinct values that were submitted. Values that were redacted are not
+    /// counted.
+    pub(crate) distinct_values: i64,
+    pub(crate) solved:          bool,
+    pub(crate) first_attempt:   chrono::DateTime<Utc>,
+    pub(crate) last_attempt:    chrono::DateTime<Utc>,
+}
+
+/// The attempts of a user on a flag, as counted by the database.
+#[derive(Debug, Clone, FromQueryResult)]
+struct SummaryRow {
+    user_id:         i64,
+    flag_id:         String,
+    attempts:        i64,
+    failed:          i64,
+    distinct_values: i64,
+    solves:          i64,
+    first_attempt:   chrono::DateTime<Utc>,
+    last_attempt:    chrono::DateTime<Utc>,
+}
+
+/// Find the attempts that match the query parameters.
+fn filter_attempts(params: &AttemptQueryParams) -> Select<attempt::Entity> {
+    let mut query = attempt::Entity::find();
+
+    if let Some(user_id) = params.user_id {
+        query = query.filter(attempt::Column::UserId.eq(user_id));
+    }
+    if let Some(flag_id) = &params.flag_id {
+        query = query.filter(attempt::Column::FlagId.eq(flag_id.clone()));
+    }
+    if params.failed_only {
+        query = query.filter(attempt::Column::Outcome.eq(AttemptOutcome::Incorrect));
+    }
+    if let Some(since) = params.since {
+        query = query.filter(attempt::Column::AttemptTime.gte(since));
+    }
+    if let Some(until) = params.until {
+        query = query.filter(attempt::Column::AttemptTime.lt(until));
+    }
+
+    query
+}
+
+/// Query flag submission attempts, most recent first. Attempts are paged by their ids, which
+/// increase with their attempt times.
+async fn query_attempts(
+    params: &AttemptQueryParams,
+    page: &AttemptPageParams,
+    conn: &DatabaseConnection,
+) -> Result<Vec<attempt::Model>, Error> {
+    let mut query = filter_attempts(params);
+
+    if let Some(before) = page.before {
+        query = query.filter(attempt::Column::Id.lt(before));
+    }
+    if let Some(after) = page.after {
+        query = query.filter(attempt::Column::Id.gt(after));
+    }
+    // The page after an attempt is the oldest attempts newer than it
+    let query = if page.after.is_some() {
+        query.order_by_asc(attempt::Column::Id)
+    } else {
+        query.order_by_desc(attempt::Column::Id)
+    };
+
+    let mut attempts = query
+        .limit(page.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT))
+        .all(conn)
+        .await
+        .map_err(ise!("QAQA"))?;
+    if page.after.is_some() {
+        attempts.reverse();
+    }
+
+    Ok(attempts)
+}
+
+#[get("")]
+pub(crate) async fn get_attempts(
+    _: RequirePermission<perm::ViewAttempts>,
+    params: web::Query<AttemptQueryParams>,
+    page: web::Query<AttemptPageParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let attempts = query_attempts(&params, &page, conn.as_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(attempts))
+}
+
+/// Summarise the attempts that match the query parameters per user and flag, ordered by the number
+/// of failed attempts. The attempts are counted by the database, rather than loaded.
+async fn summarise(
+    params: &AttemptQueryParams,
+    conn: &DatabaseConnection,
+) -> Result<Vec<AttemptSummary>, DbErr> {
+    // Counts the attempts with any of the outcomes
+    let count_outcomes = |outcomes: &[AttemptOutcome]| {
+        let outcomes: Vec<String> = outcomes.iter().map(|o| o.to_value().to_string()).collect();
+        Expr::cust(&format!(
+            r#"SUM(CASE WHEN "outcome" IN ({}) THEN 1 ELSE 0 END)"#,
+            outcomes.join(", ")
+        ))
+    };
+
+    let mut summaries = filter_attempts(params)
+        .select_only()
+        .column(attempt::Column::UserId)
+        .column(attempt::Column::FlagId)
+        .column_as(Expr::col(attempt::Column::Id).count(), "attempts")
+        .column_as(count_outcomes(&[AttemptOutcome::Incorrect]), "failed")
+        .column_as(Expr::cust(r#"COUNT(DISTINCT "value")"#), "distinct_values")
+        .column_as(
+            count_outcomes(&[AttemptOutcome::Correct, AttemptOutcome::Duplicate]),
+            "solves",
+        )
+        .column_as(
+            Expr::col(attempt::Column::AttemptTime).min(),
+            "first_attempt",
+        )
+        .column_as(
+            Expr::col(attempt::Column::AttemptTime).max(),
+            "last_attempt",
+        )
+        .group_by(attempt::Column::UserId)
+        .group_by(attempt::Column::FlagId)
+        .into_model::<SummaryRow>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|row| AttemptSummary {
+            user_id:         row.user_id,
+            flag_id:         row.flag_id,
+            attempts:        row.attempts,
+            failed:          row.failed,
+            distinct_values: row.distinct_values,
+            solved:          row.solves > 0,
+            first_attempt:   row.first_attempt,
+            last_attempt:    row.last_attempt,
+        })
+        .collect::<Vec<_>>();
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.failed));
+
+    Ok(summaries)
+}
+
+/// Summarise attempts per user and flag, ordered by the number of failed attempts. Users with many
+/// failed attempts are likely to be struggling; many distinct values in a short time span usually
+/// indicates brute forcing.
+#[get("/summary")]
+pub(crate) async fn get_attempt_summary(
+    _: RequirePermission<perm::ViewAttempts>,
+    params: web::Query<AttemptQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let summaries = summarise(&params, conn.as_ref())
+        .await
+        .map_err(ise!("QASA"))?;
+
+    Ok(HttpResponse::Ok().json(summaries))
+}
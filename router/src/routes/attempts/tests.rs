@@ -0,0 +1,114 @@
+use chrono::{Duration, TimeZone, Utc};
+use migration::{Migrator, MigratorTrait};
+use router_entity::attempt::{self, AttemptOutcome};
+use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, Set};
+
+use super::{query_attempts, summarise, AttemptPageParams, AttemptQueryParams};
+
+/// Create attempts with ids 1 to `count`, made a minute apart.
+async fn database(count: i64) -> DatabaseConnection {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    for id in 1..=count {
+        attempt::ActiveModel {
+            id:           Set(id),
+            user_id:      Set(1),
+            flag_id:      Set("f1".to_string()),
+            value:        Set(None),
+            outcome:      Set(AttemptOutcome::Incorrect),
+            reason:       Set(None),
+            attempt_time: Set(Utc.ymd(2024, 1, 1).and_hms(0, 0, 0) + Duration::minutes(id)),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    conn
+}
+
+fn all() -> AttemptQueryParams {
+    AttemptQueryParams {
+        user_id:     None,
+        flag_id:     None,
+        failed_only: false,
+        since:       None,
+        until:       None,
+    }
+}
+
+async fn page(
+    conn: &DatabaseConnection,
+    before: Option<i64>,
+    after: Option<i64>,
+    limit: Option<u64>,
+) -> Vec<i64> {
+    let page = AttemptPageParams {
+        before,
+        after,
+        limit,
+    };
+    query_attempts(&all(), &page, conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect()
+}
+
+#[tokio::test]
+async fn pages_through_attempts() {
+    let conn = database(5).await;
+
+    assert_eq!(page(&conn, None, None, Some(2)).await, [5, 4]);
+    assert_eq!(page(&conn, Some(4), None, Some(2)).await, [3, 2]);
+    assert_eq!(page(&conn, Some(2), None, Some(2)).await, [1]);
+    assert_eq!(page(&conn, None, Some(1), Some(2)).await, [3, 2]);
+    assert_eq!(page(&conn, Some(5), Some(1), None).await, [4, 3, 2]);
+}
+
+#[tokio::test]
+async fn caps_the_limit() {
+    let conn = database(1001).await;
+
+    assert_eq!(page(&conn, None, None, None).await.len(), 100);
+    assert_eq!(page(&conn, None, None, Some(1001)).await.len(), 1000);
+}
+
+#[tokio::test]
+async fn summarises_attempts_per_user_and_flag() {
+    let conn = database(3).await;
+    // A second user makes a correct attempt, then submits it again
+    for (id, value, outcome) in [
+        (4, "flag", AttemptOutcome::Correct),
+        (5, "flag", AttemptOutcome::Duplicate),
+    ] {
+        attempt::ActiveModel {
+            id:           Set(id),
+            user_id:      Set(2),
+            flag_id:      Set("f1".to_string()),
+            value:        Set(Some(value.to_string())),
+            outcome:      Set(outcome),
+            reason:       Set(None),
+            attempt_time: Set(Utc.ymd(2024, 1, 1).and_hms(0, 0, 0) + Duration::minutes(id)),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+
+    let summaries = summarise(&all(), &conn).await.unwrap();
+    let counts: Vec<_> = summaries
+        .iter()
+        .map(|s| (s.user_id, s.attempts, s.failed, s.distinct_values, s.solved))
+        .collect();
+    assert_eq!(counts, [(1, 3, 3, 0, false), (2, 2, 0, 1, true)]);
+    assert_eq!(
+        summaries[0].first_attempt,
+        Utc.ymd(2024, 1, 1).and_hms(0, 1, 0)
+    );
+    assert_eq!(
+        summaries[0].last_attempt,
+        Utc.ymd(2024, 1, 1).and_hms(0, 3, 0)
+    );
+}
@@ -0,0 +1,54 @@
+use actix_web::{get, web, Error, HttpResponse};
+use authz::{perm, RequirePermission};
+use chrono::Utc;
+use router_entity::audit_log;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Deserialize;
+
+use crate::handler_utils::ise;
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct AuditQueryParams {
+    /// The `_scpU` id of the user that performed the actions.
+    pub(crate) actor:  Option<String>,
+    pub(crate) action: Option<String>,
+    pub(crate) target: Option<String>,
+    /// Only include entries at or after this time.
+    pub(crate) since:  Option<chrono::DateTime<Utc>>,
+    /// Only include entries before this time.
+    pub(crate) until:  Option<chrono::DateTime<Utc>>,
+    /// The maximum number of entries to return. Defaults to 100.
+    pub(crate) limit:  Option<u64>,
+}
+
+/// List audit log entries, most recent first.
+#[get("")]
+pub(crate) async fn get_audit_log(
+    _: RequirePermission<perm::ViewAuditLog>,
+    params: web::Query<AuditQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let mut query = audit_log::Entity::find()
+        .order_by_desc(audit_log::Column::Timestamp)
+        .limit(params.limit.unwrap_or(100));
+
+    if let Some(actor) = &params.actor {
+        query = query.filter(audit_log::Column::Actor.eq(actor.clone()));
+    }
+    if let Some(action) = &params.action {
+        query = query.filter(audit_log::Column::Action.eq(action.clone()));
+    }
+    if let Some(target) = &params.target {
+        query = query.filter(audit_log::Column::Target.eq(target.clone()));
+    }
+    if let Some(since) = params.since {
+        query = query.filter(audit_log::Column::Timestamp.gte(since));
+    }
+    if let Some(until) = params.until {
+        query = query.filter(audit_log::Column::Timestamp.lt(until));
+    }
+
+    let entries = query.all(conn.as_ref()).await.map_err(ise!("GALQE"))?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
@@ -1,25 +1,67 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use actix_web::{error::ErrorUnauthorized, get, web, Error, HttpRequest, HttpResponse};
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use authz::{Permission, Roles};
 use chrono::Utc;
 use router_entity::{
+    attachment,
     category,
-    challenge,
+    challenge::{self, Difficulty, LatePolicy},
+    challenge_tag,
     flag::{self, FlagType},
+    hint,
+    hint_unlock,
     service,
     submission,
 };
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 
-use crate::handler_utils::{self, ise};
+use crate::{
+    handler_utils::{self, ise},
+    overrides::{self, Overrides},
+    prerequisites::{self, Locked},
+    scoring,
+    teams,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ReturnPayload {
     /// The ID of the challenge
+    pub(crate) id:          i64,
+    /// Whether the challenge's flags are solved once per team.
+    pub(crate) team_mode:   bool,
+    /// A markdown description of the challenge.
+    pub(crate) description: String,
+    pub(crate) author:      Option<String>,
+    pub(crate) difficulty:  Option<Difficulty>,
+    pub(crate) tags:        Vec<String>,
+    pub(crate) services:    Vec<ReturnService>,
+    pub(crate) flags:       Vec<ReturnFlag>,
+    pub(crate) hints:       Vec<ReturnHint>,
+    pub(crate) attachments: Vec<ReturnAttachment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReturnAttachment {
+    pub(crate) id:           i64,
+    pub(crate) name:         String,
+    pub(crate) content_type: String,
+    pub(crate) size:         i64,
+    pub(crate) sha256:       String,
+    /// The path that the attachment can be downloaded from.
+    pub(crate) url:          String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReturnHint {
     pub(crate) id:       i64,
-    pub(crate) services: Vec<ReturnService>,
-    pub(crate) flags:    Vec<ReturnFlag>,
+    /// The flag that the hint is for, if any.
+    pub(crate) flag_id:  Option<String>,
+    pub(crate) cost:     i32,
+    pub(crate) unlocked: bool,
+    /// The content of the hint. Only present once the user has unlocked it.
+    pub(crate) content:  Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,10 +70,29 @@ struct ReturnFlag {
     pub(crate) flag_type:          FlagType,
     pub(crate) display_name:       String,
     pub(crate) category:           String,
+    /// The number of points the flag is currently worth.
     pub(crate) points:             i32,
+    /// The number of users that have solved the flag.
+    pub(crate) solves:             i64,
+    /// The time after which submissions for this flag are late.
+    pub(crate) deadline:           Option<chrono::DateTime<Utc>>,
+    pub(crate) late_policy:        Option<LatePolicy>,
+    /// The bonus points for the first, second and third solves.
+    pub(crate) blood_bonus:        [i32; 3],
+    /// The first, second and third solves of the flag, if any.
+    pub(crate) bloods:             Vec<ReturnBlood>,
     pub(crate) submission_details: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReturnBlood {
+    pub(crate) rank:    i32,
+    pub(crate) time:    chrono::DateTime<Utc>,
+    /// The user that solved the flag. Only shown to staff and to the user or their team.
+    pub(crate) user_id: Option<i64>,
+    pub(crate) team_id: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ReturnService {
     pub(crate) id:         i64,
@@ -45,22 +106,35 @@ struct ReturnService {
 #[allow(clippy::too_many_lines)]
 pub(crate) async fn get_all(
     req: HttpRequest,
+    roles: Roles,
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
+    let is_admin = roles.can(Permission::ViewUnreleased);
+
     // Get the auth token
-    let token = req
-        .headers()
-        .get("X-Scp-Auth")
-        .ok_or_else(|| ErrorUnauthorized("Missing authentication token"))?
-        .to_str()
-        .map_err(ise!("GCEAT"))?;
-
-    // Get roles for user
-    let roles = crate::gaia_utils::get_roles(token)
-        .await
-        .map_err(ise!("GCGUR"))?;
+    let claims = handler_utils::get_claims(&req)?;
+    // Get the user id/email
+    let email = claims.user_id;
+
+    let uid = handler_utils::parse_user_id(&email).unwrap();
 
-    let is_admin = roles.contains("admin") || roles.contains("tutor");
+    // Tutors and admins can see challenges and flags that have not been unlocked
+    let locked = if is_admin {
+        Locked::default()
+    } else {
+        prerequisites::get_locked(conn.as_ref(), uid)
+            .await
+            .map_err(ise!("GCGLC"))?
+    };
+
+    // Students may have been granted individual windows for some services
+    let overrides = if is_admin {
+        Overrides::default()
+    } else {
+        overrides::get_overrides(conn.as_ref(), uid)
+            .await
+            .map_err(ise!("GCGDO"))?
+    };
 
     let challenges_and_services = challenge::Entity::find()
         .find_with_related(service::Entity)
@@ -82,26 +156,42 @@ pub(crate) async fn get_all(
         .map(|c| (c.id, c.name))
         .collect();
 
+    let mut details: HashMap<i64, challenge::Model> = challenges_and_services
+        .iter()
+        .map(|(c, _)| (c.id, c.clone()))
+        .collect();
+
+    let team_mode_challenges: HashSet<i64> = challenges_and_services
+        .iter()
+        .filter(|(c, _)| c.team_mode)
+        .map(|(c, _)| c.id)
+        .collect();
+
+    let solve_counts = scoring::get_solve_counts(conn.as_ref())
+        .await
+        .map_err(ise!("GCQSC"))?;
+
     let mut map: HashMap<i64, (Vec<ReturnService>, Vec<ReturnFlag>)> = challenges_and_services
         .into_iter()
         .filter_map(|(challenge, services)| {
+            if locked.challenges.contains(&challenge.id) {
+                return None;
+            }
+
             let services = services
                 .into_iter()
                 .filter_map(|s| {
-                    if !is_admin {
-                        if let Some(dt) = s.not_before {
-                            if chrono::offset::Utc::now().lt(&dt) {
-                                return None;
-                            }
-                        }
+                    let window = overrides.window(&s);
+                    if !is_admin && !window.has_started(chrono::offset::Utc::now()) {
+                        return None;
                     }
 
                     Some(ReturnService {
                         category:   categories.get(&s.category_id).unwrap().clone(),
                         id:         s.id,
                         name:       s.name,
-                        not_after:  s.not_after,
-                        not_before: s.not_before,
+                        not_after:  window.not_after,
+                        not_before: window.not_before,
                     })
                 })
                 .collect::<Vec<ReturnService>>();
@@ -118,13 +208,27 @@ pub(crate) async fn get_all(
         if map.contains_key(&challenge.id) {
             let flags = flags
                 .into_iter()
-                .map(|f| ReturnFlag {
-                    category:           categories.get(&f.category_id).unwrap().clone(),
-                    display_name:       f.display_name,
-                    flag_type:          f.flag_type,
-                    id:                 f.id,
-                    points:             f.points,
-                    submission_details: None,
+                .filter(|f| !locked.flags.contains(&f.id))
+                .map(|f| {
+                    let solves = *solve_counts.get(&f.id).unwrap_or(&0);
+                    let deadline = overrides.deadline(&challenge, &f);
+                    ReturnFlag {
+                        deadline: deadline.as_ref().map(|d| d.time),
+                        late_policy: deadline.map(|d| d.policy),
+                        blood_bonus: [
+                            f.first_blood_bonus,
+                            f.second_blood_bonus,
+                            f.third_blood_bonus,
+                        ],
+                        bloods: vec![],
+                        category: categories.get(&f.category_id).unwrap().clone(),
+                        points: scoring::flag_value(&f, solves),
+                        display_name: f.display_name,
+                        flag_type: f.flag_type,
+                        id: f.id,
+                        solves,
+                        submission_details: None,
+                    }
                 })
                 .collect();
             map.insert(
@@ -134,23 +238,49 @@ pub(crate) async fn get_all(
         }
     }
 
-    // Get the auth token
-    let claims = handler_utils::get_claims(&req)?;
-    // Get the user id/email
-    let email = claims.user_id;
+    // Submissions by other members of the user's team count towards team mode challenges
+    let team_id = teams::get_team_id(conn.as_ref(), uid)
+        .await
+        .map_err(ise!("GCGTI"))?;
+    let members = match team_id {
+        Some(team_id) => teams::get_member_ids(conn.as_ref(), team_id)
+            .await
+            .map_err(ise!("GCGTM"))?,
+        None => vec![uid],
+    };
+
+    let mut bloods = submission::Entity::find()
+        .filter(submission::Column::Blood.is_not_null())
+        .find_also_related(flag::Entity)
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GCQB"))?;
+    bloods.sort_by_key(|(s, _)| s.blood);
+    for (sub, flag) in bloods {
+        let (Some(flag), Some(rank)) = (flag, sub.blood) else {
+            continue;
+        };
+        let Some((_, flags)) = map.get_mut(&flag.challenge_id) else {
+            continue;
+        };
+        let Some(f) = flags.iter_mut().find(|f| f.id == flag.id) else {
+            continue;
+        };
 
-    let uid = email
-        .strip_prefix("_scpU")
-        .unwrap()
-        .strip_suffix("@unsw.scp.platform")
-        .unwrap()
-        .to_string()
-        .parse::<i64>()
-        .unwrap();
+        // Other students only see when a flag was solved, not by whom
+        let visible =
+            is_admin || sub.user_id == uid || (sub.team_id.is_some() && sub.team_id == team_id);
+        f.bloods.push(ReturnBlood {
+            rank,
+            time: sub.submission_time,
+            user_id: visible.then_some(sub.user_id),
+            team_id: if visible { sub.team_id } else { None },
+        });
+    }
 
-    // Get all flags that have a submission by this user
+    // Get all flags that have a submission by this user or their team
     submission::Entity::find()
-        .filter(submission::Column::UserId.eq(uid))
+        .filter(submission::Column::UserId.is_in(members))
         .find_also_related(flag::Entity)
         .all(conn.as_ref())
         .await
@@ -165,6 +295,10 @@ pub(crate) async fn get_all(
             if !map.contains_key(&flag.challenge_id) {
                 return;
             }
+            let by_team = submission.user_id != uid;
+            if by_team && !team_mode_challenges.contains(&flag.challenge_id) {
+                return;
+            }
             if let Some(f) = map
                 .get_mut(&flag.challenge_id)
                 .unwrap()
@@ -172,19 +306,97 @@ pub(crate) async fn get_all(
                 .iter_mut()
                 .find(|f| f.id.as_str() == flag.id.as_str())
             {
+                // Prefer showing the user's own submission over their team's
+                if by_team && f.submission_details.is_some() {
+                    return;
+                }
                 f.submission_details = Some(format!(
-                    "Submitted on {}",
-                    submission.submission_time.to_rfc3339()
+                    "Submitted {}on {}{}",
+                    if by_team { "by your team " } else { "" },
+                    submission.submission_time.to_rfc3339(),
+                    if submission.late_seconds.is_some() {
+                        format!(" (late, -{}%)", submission.late_penalty)
+                    } else {
+                        String::new()
+                    }
                 ));
             }
         });
 
+    let unlocked_hints: HashSet<i64> = hint_unlock::Entity::find()
+        .filter(hint_unlock::Column::UserId.eq(uid))
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GCQHU"))?
+        .into_iter()
+        .map(|u| u.hint_id)
+        .collect();
+
+    // Hints for flags that have not been unlocked are hidden along with the flag
+    let mut hints: HashMap<i64, Vec<ReturnHint>> = HashMap::new();
+    for h in hint::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GCQH"))?
+    {
+        if h.flag_id.as_ref().is_some_and(|f| locked.flags.contains(f)) {
+            continue;
+        }
+
+        let unlocked = unlocked_hints.contains(&h.id);
+        hints.entry(h.challenge_id).or_default().push(ReturnHint {
+            id: h.id,
+            flag_id: h.flag_id,
+            cost: h.cost,
+            unlocked,
+            content: (unlocked || is_admin).then_some(h.content),
+        });
+    }
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for t in challenge_tag::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GCQT"))?
+    {
+        tags.entry(t.challenge_id).or_default().push(t.tag);
+    }
+
+    let mut attachments: HashMap<i64, Vec<ReturnAttachment>> = HashMap::new();
+    for a in attachment::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GCQA"))?
+    {
+        attachments
+            .entry(a.challenge_id)
+            .or_default()
+            .push(ReturnAttachment {
+                url:          format!("/api/challenges/attachments/{}", a.id),
+                id:           a.id,
+                name:         a.name,
+                content_type: a.content_type,
+                size:         a.size,
+                sha256:       a.sha256,
+            });
+    }
+
     let return_data: Vec<ReturnPayload> = map
         .into_iter()
-        .map(|(k, v)| ReturnPayload {
-            id:       k,
-            flags:    v.1,
-            services: v.0,
+        .map(|(k, v)| {
+            let challenge = details.remove(&k).unwrap();
+            ReturnPayload {
+                id:          k,
+                team_mode:   challenge.team_mode,
+                description: challenge.description,
+                author:      challenge.author,
+                difficulty:  challenge.difficulty,
+                tags:        tags.remove(&k).unwrap_or_default(),
+                flags:       v.1,
+                services:    v.0,
+                hints:       hints.remove(&k).unwrap_or_default(),
+                attachments: attachments.remove(&k).unwrap_or_default(),
+            }
         })
         .collect();
 
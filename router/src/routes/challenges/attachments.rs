@@ -0,0 +1,68 @@
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web,
+    Error,
+    HttpRequest,
+    HttpResponse,
+};
+use api_error::{ApiError, Code};
+use authz::Permission;
+use router_entity::attachment;
+use sea_orm::{DatabaseConnection, EntityTrait};
+
+use crate::{
+    attachments,
+    handler_utils::{self, ise},
+    overrides,
+    prerequisites,
+};
+
+/// Download the contents of a challenge attachment. Students may only download attachments for
+/// challenges that they can currently see.
+#[get("/attachments/{id}")]
+pub(crate) async fn download_attachment(
+    req: HttpRequest,
+    attachment_id: web::Path<i64>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // Get the auth token
+    let claims = handler_utils::get_claims(&req)?;
+    let uid = handler_utils::parse_user_id(&claims.user_id).unwrap();
+
+    let attachment = attachment::Entity::find_by_id(*attachment_id)
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("DAQA"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Attachment does not exist"))?;
+
+    let roles = handler_utils::get_request_roles(&req).await?;
+    if !roles.can(Permission::ViewUnreleased) {
+        let locked = prerequisites::get_locked(conn.as_ref(), uid)
+            .await
+            .map_err(ise!("DAGLC"))?;
+
+        // Challenges are hidden until at least one of their services has started
+        let started = overrides::has_started(conn.as_ref(), uid, attachment.challenge_id)
+            .await
+            .map_err(ise!("DAHS"))?;
+
+        if locked.challenges.contains(&attachment.challenge_id) || !started {
+            return Err(ApiError::new(Code::Locked)
+                .detail("This attachment is not available yet")
+                .into());
+        }
+    }
+
+    let data = attachments::read_blob(attachment.id)
+        .await
+        .map_err(ise!("DARAB"))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(attachment.content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters:  vec![DispositionParam::Filename(attachment.name)],
+        })
+        .body(data))
+}
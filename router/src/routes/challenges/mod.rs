@@ -1,3 +1,7 @@
 pub use all_challenges::get_all;
+pub use attachments::download_attachment;
+pub use prerequisites::get_prerequisites;
 
 mod all_challenges;
+mod attachments;
+mod prerequisites;
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use actix_web::{get, web, Error, HttpResponse};
+use authz::{perm, RequirePermission};
+use router_entity::{
+    category,
+    flag,
+    prerequisite::{self, PrerequisiteKind},
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::Serialize;
+
+use crate::handler_utils::ise;
+
+/// A single edge in the prerequisite graph.
+#[derive(Debug, Clone, Serialize)]
+struct ReturnPrerequisite {
+    id:                    i64,
+    challenge_id:          i64,
+    locked_flag_id:        Option<String>,
+    kind:                  PrerequisiteKind,
+    required_flag_id:      Option<String>,
+    /// The challenge that the required flag belongs to.
+    required_challenge_id: Option<i64>,
+    required_category:     Option<String>,
+    required_points:       Option<i32>,
+}
+
+/// List every prerequisite rule so that staff can inspect the unlock graph.
+#[get("/prerequisites")]
+pub(crate) async fn get_prerequisites(
+    _: RequirePermission<perm::ViewUnreleased>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let rules = prerequisite::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GPQP"))?;
+
+    let flag_challenges: HashMap<String, i64> = flag::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GPQF"))?
+        .into_iter()
+        .map(|f| (f.id, f.challenge_id))
+        .collect();
+    let category_names: HashMap<i64, String> = category::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GPQC"))?
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect();
+
+    let graph: Vec<ReturnPrerequisite> = rules
+        .into_iter()
+        .map(|p| ReturnPrerequisite {
+            id:                    p.id,
+            challenge_id:          p.challenge_id,
+            locked_flag_id:        p.locked_flag_id,
+            kind:                  p.kind,
+            required_challenge_id: p
+                .required_flag_id
+                .as_ref()
+                .and_then(|f| flag_challenges.get(f).copied()),
+            required_flag_id:      p.required_flag_id,
+            required_category:     p
+                .required_category_id
+                .and_then(|c| category_names.get(&c).cloned()),
+            required_points:       p.required_points,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(graph))
+}
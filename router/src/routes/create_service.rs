@@ -1,16 +1,20 @@
 use std::collections::{HashMap, HashSet};
 
-use actix_web::{
-    error::{ErrorBadRequest, ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized},
-    post,
-    web,
-    Error,
-    HttpRequest,
-    HttpResponse,
-};
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::{perm, RequirePermission};
 use chrono::Utc;
 use idgenerator::{IdGeneratorOptions, IdInstance};
-use router_entity::{category, challenge, flag, service};
+use router_entity::{
+    attachment,
+    category,
+    challenge::{self, Difficulty, LatePolicy},
+    challenge_tag,
+    flag,
+    hint,
+    prerequisite::{self, PrerequisiteKind},
+    service,
+};
 use sea_orm::{
     ActiveModelTrait,
     ColumnTrait,
@@ -21,18 +25,26 @@ use sea_orm::{
     TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use webhooks::{Event, Payload};
 
-use crate::registry::services::{validate_flags, validate_services};
-
-/// Macro to quickly construct an internal server error with an error code.
-macro_rules! ise {
-    ($code:expr) => {
-        |e| {
-            tracing::error!("exception occurred: {}", e);
-            ErrorInternalServerError(concat!("Internal server error: EC.", $code))
-        }
-    };
-}
+use crate::{
+    attachments,
+    audit,
+    handler_utils::{self, ise},
+    notify,
+    prerequisites,
+    registry::services::{
+        prerequisite_dependencies,
+        validate_attachments,
+        validate_details,
+        validate_flags,
+        validate_hints,
+        validate_late_policy,
+        validate_prerequisites,
+        validate_services,
+    },
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct NewService {
@@ -43,6 +55,13 @@ pub(crate) struct NewService {
     pub(crate) nbf:      Option<chrono::DateTime<Utc>>,
     /// The date after which students cannot access the challenge.
     pub(crate) naf:      Option<chrono::DateTime<Utc>>,
+    /// The container image that each user is given their own instance of. If not set, users
+    /// share a single deployment of the service.
+    #[serde(default)]
+    pub(crate) image:    Option<String>,
+    /// The port that instances of the image listen on. Defaults to 80.
+    #[serde(default)]
+    pub(crate) port:     Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,54 +72,180 @@ pub(crate) struct NewFlag {
     pub(crate) id:           String,
     pub(crate) display_name: String,
     pub(crate) category:     String,
+    /// The number of points the flag is worth. If the scoring mode is `decay`, this is the initial
+    /// value of the flag.
     pub(crate) points:       i32,
     /// The flag that will be used as part of the flag generation process.
     /// If the flag type is `static`, it will be the actual flag that is submitted.
     pub(crate) flag:         String,
+    /// The flag's scoring mode. Should be either `static` or `decay`. Defaults to `static`.
+    #[serde(default)]
+    pub(crate) scoring_mode: Option<String>,
+    /// The lowest value a decaying flag can reach.
+    #[serde(default)]
+    pub(crate) minimum:      Option<i32>,
+    /// The number of solves after which a decaying flag reaches its minimum value.
+    #[serde(default)]
+    pub(crate) decay:        Option<i32>,
+    /// The time after which submissions for this flag are late. Takes precedence over the
+    /// challenge's deadline.
+    #[serde(default)]
+    pub(crate) deadline:     Option<chrono::DateTime<Utc>>,
+    /// How late submissions are handled. Should be either `reject` or `penalty`. Defaults to
+    /// `reject`.
+    #[serde(default)]
+    pub(crate) late_policy:  Option<String>,
+    /// The percentage of the flag's value that is deducted for late submissions.
+    #[serde(default)]
+    pub(crate) late_penalty: i32,
+    /// Bonus points for the first, second and third users to solve the flag.
+    #[serde(default)]
+    pub(crate) blood_bonus:  Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct NewPrerequisite {
+    /// The id of the flag that is locked by this rule. If not provided, the whole challenge is
+    /// locked.
+    #[serde(default)]
+    pub(crate) flag:              Option<String>,
+    /// The id of a flag that must be solved to unlock the challenge or flag.
+    #[serde(default)]
+    pub(crate) requires_flag:     Option<String>,
+    /// The category in which `requires_points` must be earned to unlock the challenge or flag.
+    #[serde(default)]
+    pub(crate) requires_category: Option<String>,
+    #[serde(default)]
+    pub(crate) requires_points:   Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct NewHint {
+    /// The id of the flag that the hint is for. If not provided, the hint is for the whole
+    /// challenge.
+    #[serde(default)]
+    pub(crate) flag:    Option<String>,
+    pub(crate) content: String,
+    /// The number of points deducted from a user's score when they unlock the hint.
+    #[serde(default)]
+    pub(crate) cost:    i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct NewAttachment {
+    /// The file name presented to users when downloading the attachment.
+    pub(crate) name:         String,
+    /// Defaults to `application/octet-stream`.
+    #[serde(default)]
+    pub(crate) content_type: Option<String>,
+    /// The base64 encoded contents of the file.
+    pub(crate) data:         String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct NewServicePayload {
-    pub(crate) services: Vec<NewService>,
-    pub(crate) flags:    Vec<NewFlag>,
+    pub(crate) services:      Vec<NewService>,
+    pub(crate) flags:         Vec<NewFlag>,
+    /// Whether the challenge's flags are solved once per team rather than once per user.
+    #[serde(default)]
+    pub(crate) team_mode:     bool,
+    /// Rules that must be satisfied before users can access the challenge or its flags.
+    #[serde(default)]
+    pub(crate) prerequisites: Vec<NewPrerequisite>,
+    #[serde(default)]
+    pub(crate) hints:         Vec<NewHint>,
+    /// A markdown description of the challenge.
+    #[serde(default)]
+    pub(crate) description:   String,
+    #[serde(default)]
+    pub(crate) author:        Option<String>,
+    /// The challenge's difficulty. Should be one of `easy`, `medium` or `hard`.
+    #[serde(default)]
+    pub(crate) difficulty:    Option<String>,
+    #[serde(default)]
+    pub(crate) tags:          Vec<String>,
+    /// Files that are provided to users as part of the challenge.
+    #[serde(default)]
+    pub(crate) attachments:   Vec<NewAttachment>,
+    /// The time after which submissions for the challenge's flags are late.
+    #[serde(default)]
+    pub(crate) deadline:      Option<chrono::DateTime<Utc>>,
+    /// How late submissions are handled. Should be either `reject` or `penalty`. Defaults to
+    /// `reject`.
+    #[serde(default)]
+    pub(crate) late_policy:   Option<String>,
+    /// The percentage of a flag's value that is deducted for late submissions.
+    #[serde(default)]
+    pub(crate) late_penalty:  i32,
 }
 
 #[tracing::instrument]
 #[post("/services")]
 pub(crate) async fn create_service(
+    _: RequirePermission<perm::ManageChallenges>,
     req: HttpRequest,
     conn: web::Data<DatabaseConnection>,
     payload: web::Json<NewServicePayload>,
 ) -> Result<HttpResponse, Error> {
-    let token = req
-        .headers()
-        .get("x-scp-auth")
-        .ok_or_else(|| ErrorUnauthorized(""))?
-        .to_str()
-        .map_err(ErrorForbidden)?;
-
-    // Get the roles for the user
-    let roles = crate::gaia_utils::get_roles(token)
-        .await
-        .map_err(ErrorInternalServerError)?;
-
-    // Determine if the user has enough permissions to create new services
-    if !roles.contains("admin") {
-        return Err(ErrorForbidden(""));
-    }
-
     // Validate the service entries
     if !validate_services(&payload.services) {
-        return Err(ErrorBadRequest("Invalid service definitions"));
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid service definitions")
+            .into());
     }
 
     // Validate the flag entries
     if !validate_flags(&payload.flags) {
-        return Err(ErrorBadRequest("Invalid flag definitions"));
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid flag definitions")
+            .into());
     }
 
+    // Validate the prerequisite entries
+    if !validate_prerequisites(&payload.prerequisites, &payload.flags) {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid prerequisite definitions")
+            .into());
+    }
+
+    // Validate the hint entries
+    if !validate_hints(&payload.hints, &payload.flags) {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid hint definitions")
+            .into());
+    }
+
+    // Validate the challenge details
+    if !validate_details(payload.difficulty.as_deref(), &payload.tags) {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid challenge details")
+            .into());
+    }
+
+    // Validate the submission deadline
+    if !validate_late_policy(payload.late_policy.as_deref(), payload.late_penalty) {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid late submission policy")
+            .into());
+    }
+
+    // Validate and decode the attachments
+    if !validate_attachments(&payload.attachments) {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Invalid attachment definitions")
+            .into());
+    }
+    let attachment_data = payload
+        .attachments
+        .iter()
+        .map(|a| base64::decode(&a.data))
+        .collect::<Result<Vec<Vec<u8>>, _>>()
+        .map_err(|_| {
+            ApiError::new(Code::InvalidRequest).detail("Attachment data must be base64 encoded")
+        })?;
+
     // Create a new transaction
-    let txn = conn.begin().await.map_err(ErrorInternalServerError)?;
+    let txn = conn.begin().await.map_err(ise!("CSBTX"))?;
 
     // Setup id generator
     let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
@@ -109,7 +254,23 @@ pub(crate) async fn create_service(
 
     // Create a new challenge
     let new_challenge = challenge::ActiveModel {
-        id: Set(new_challenge_id),
+        id:           Set(new_challenge_id),
+        team_mode:    Set(payload.team_mode),
+        description:  Set(payload.description.clone()),
+        author:       Set(payload.author.clone()),
+        difficulty:   Set(match payload.difficulty.as_deref() {
+            Some("easy") => Some(Difficulty::Easy),
+            Some("medium") => Some(Difficulty::Medium),
+            Some("hard") => Some(Difficulty::Hard),
+            _ => None,
+        }),
+        deadline:     Set(payload.deadline),
+        late_policy:  Set(match payload.late_policy.as_deref() {
+            None | Some("reject") => LatePolicy::Reject,
+            Some("penalty") => LatePolicy::Penalty,
+            Some(v) => unreachable!("got: {}", v),
+        }),
+        late_penalty: Set(payload.late_penalty),
     };
     new_challenge.insert(&txn).await.map_err(ise!("CSCNC"))?;
 
@@ -119,6 +280,12 @@ pub(crate) async fn create_service(
         .iter()
         .map(|s| s.category.as_str())
         .chain(payload.flags.iter().map(|f| f.category.as_str()))
+        .chain(
+            payload
+                .prerequisites
+                .iter()
+                .filter_map(|p| p.requires_category.as_deref()),
+        )
         .collect::<HashSet<&str>>();
 
     let existing: HashMap<String, category::Model> = category::Entity::find()
@@ -170,10 +337,12 @@ pub(crate) async fn create_service(
         .map_err(ise!("CSQSI"))?
         .is_empty()
     {
-        return Err(ErrorBadRequest(format!(
-            "Cannot create a service with a name that already exists: {:?}",
-            internal_names
-        )));
+        return Err(ApiError::new(Code::AlreadyExists)
+            .detail(format!(
+                "Cannot create a service with a name that already exists: {:?}",
+                internal_names
+            ))
+            .into());
     }
 
     // Insert new services into the database
@@ -189,6 +358,8 @@ pub(crate) async fn create_service(
             name:              Set(s.name.clone()),
             not_after:         Set(s.naf),
             not_before:        Set(s.nbf),
+            image:             Set(s.image.clone()),
+            port:              Set(s.image.as_ref().map(|_| i32::from(s.port.unwrap_or(80)))),
         })
         .collect::<Vec<service::ActiveModel>>();
 
@@ -206,13 +377,15 @@ pub(crate) async fn create_service(
             .await
             .map_err(ise!("CSQAF"))?;
         if !duplicate_flags.is_empty() {
-            return Err(ErrorBadRequest(format!(
-                "Attempted to insert duplicate flags into the database: {:?}",
-                duplicate_flags
-                    .into_iter()
-                    .map(|f| f.id)
-                    .collect::<Vec<String>>()
-            )));
+            return Err(ApiError::new(Code::AlreadyExists)
+                .detail(format!(
+                    "Attempted to insert duplicate flags into the database: {:?}",
+                    duplicate_flags
+                        .into_iter()
+                        .map(|f| f.id)
+                        .collect::<Vec<String>>()
+                ))
+                .into());
         }
 
         // Insert new flags into the database
@@ -220,17 +393,34 @@ pub(crate) async fn create_service(
             .flags
             .iter()
             .map(|f| flag::ActiveModel {
-                category_id:  Set(*category_name_id_map.get(&f.category).unwrap()),
-                challenge_id: Set(new_challenge_id),
-                flag:         Set(f.flag.clone()),
-                flag_type:    Set(match f.flag_type.as_str() {
+                category_id:        Set(*category_name_id_map.get(&f.category).unwrap()),
+                challenge_id:       Set(new_challenge_id),
+                flag:               Set(f.flag.clone()),
+                flag_type:          Set(match f.flag_type.as_str() {
                     "static" => flag::FlagType::Static,
                     "dynamic" => flag::FlagType::Dynamic,
                     v => unreachable!("got: {}", v),
                 }),
-                id:           Set(f.id.clone()),
-                points:       Set(f.points),
-                display_name: Set(f.display_name.clone()),
+                id:                 Set(f.id.clone()),
+                points:             Set(f.points),
+                display_name:       Set(f.display_name.clone()),
+                scoring_mode:       Set(match f.scoring_mode.as_deref() {
+                    None | Some("static") => flag::ScoringMode::Static,
+                    Some("decay") => flag::ScoringMode::Decay,
+                    Some(v) => unreachable!("got: {}", v),
+                }),
+                minimum_points:     Set(f.minimum),
+                decay:              Set(f.decay),
+                deadline:           Set(f.deadline),
+                late_policy:        Set(match f.late_policy.as_deref() {
+                    None | Some("reject") => LatePolicy::Reject,
+                    Some("penalty") => LatePolicy::Penalty,
+                    Some(v) => unreachable!("got: {}", v),
+                }),
+                late_penalty:       Set(f.late_penalty),
+                first_blood_bonus:  Set(f.blood_bonus.first().copied().unwrap_or(0)),
+                second_blood_bonus: Set(f.blood_bonus.get(1).copied().unwrap_or(0)),
+                third_blood_bonus:  Set(f.blood_bonus.get(2).copied().unwrap_or(0)),
             })
             .collect::<Vec<flag::ActiveModel>>();
 
@@ -240,8 +430,180 @@ pub(crate) async fn create_service(
             .map_err(ise!("CSINF"))?;
     }
 
+    if !payload.prerequisites.is_empty() {
+        // Ensure that all required flags exist, either in this payload or the database
+        let new_flag_ids: HashSet<&str> = payload.flags.iter().map(|f| f.id.as_str()).collect();
+        let required_flag_ids: HashSet<String> = payload
+            .prerequisites
+            .iter()
+            .filter_map(|p| p.requires_flag.clone())
+            .filter(|f| !new_flag_ids.contains(f.as_str()))
+            .collect();
+        let existing_flags = flag::Entity::find()
+            .filter(flag::Column::Id.is_in(required_flag_ids.clone()))
+            .all(&txn)
+            .await
+            .map_err(ise!("CSQRF"))?;
+        if existing_flags.len() != required_flag_ids.len() {
+            return Err(ApiError::new(Code::InvalidRequest)
+                .detail(format!(
+                    "Prerequisites refer to flags that do not exist: {required_flag_ids:?}"
+                ))
+                .into());
+        }
+
+        // Ensure that the new prerequisites do not form a cycle with existing ones
+        let mut dependencies = prerequisites::get_dependencies(&txn)
+            .await
+            .map_err(ise!("CSQPD"))?;
+        dependencies.extend(prerequisite_dependencies(
+            &payload.prerequisites,
+            &payload.flags,
+        ));
+        if prerequisites::has_cycle(&dependencies) {
+            return Err(ApiError::new(Code::InvalidRequest)
+                .detail("Prerequisites cannot depend on each other in a cycle")
+                .into());
+        }
+
+        // Insert new prerequisites into the database
+        let new_prerequisites = payload
+            .prerequisites
+            .iter()
+            .map(|p| prerequisite::ActiveModel {
+                id:                   Set(IdInstance::next_id()),
+                challenge_id:         Set(new_challenge_id),
+                locked_flag_id:       Set(p.flag.clone()),
+                kind:                 Set(if p.requires_flag.is_some() {
+                    PrerequisiteKind::SolveFlag
+                } else {
+                    PrerequisiteKind::CategoryPoints
+                }),
+                required_flag_id:     Set(p.requires_flag.clone()),
+                required_category_id: Set(p
+                    .requires_category
+                    .as_ref()
+                    .map(|c| *category_name_id_map.get(c).unwrap())),
+                required_points:      Set(p.requires_points),
+            })
+            .collect::<Vec<prerequisite::ActiveModel>>();
+
+        prerequisite::Entity::insert_many(new_prerequisites)
+            .exec(&txn)
+            .await
+            .map_err(ise!("CSINP"))?;
+    }
+
+    if !payload.hints.is_empty() {
+        // Insert new hints into the database
+        let new_hints = payload
+            .hints
+            .iter()
+            .map(|h| hint::ActiveModel {
+                id:           Set(IdInstance::next_id()),
+                challenge_id: Set(new_challenge_id),
+                flag_id:      Set(h.flag.clone()),
+                content:      Set(h.content.clone()),
+                cost:         Set(h.cost),
+            })
+            .collect::<Vec<hint::ActiveModel>>();
+
+        hint::Entity::insert_many(new_hints)
+            .exec(&txn)
+            .await
+            .map_err(ise!("CSINH"))?;
+    }
+
+    if !payload.tags.is_empty() {
+        // Insert new tags into the database
+        let mut seen = HashSet::new();
+        let new_tags = payload
+            .tags
+            .iter()
+            .filter(|t| seen.insert(t.as_str()))
+            .map(|t| challenge_tag::ActiveModel {
+                id:           Set(IdInstance::next_id()),
+                challenge_id: Set(new_challenge_id),
+                tag:          Set(t.clone()),
+            })
+            .collect::<Vec<challenge_tag::ActiveModel>>();
+
+        challenge_tag::Entity::insert_many(new_tags)
+            .exec(&txn)
+            .await
+            .map_err(ise!("CSINT"))?;
+    }
+
+    // Write attachments to the blob directory, but only store them under their ids once they are
+    // recorded in the database
+    let mut blobs = attachments::StagedBlobs::default();
+    for (new_attachment, data) in payload.attachments.iter().zip(attachment_data) {
+        let attachment_id = IdInstance::next_id();
+        blobs
+            .write(attachment_id, &data)
+            .await
+            .map_err(ise!("CSWAB"))?;
+
+        let new_attachment = attachment::ActiveModel {
+            id:           Set(attachment_id),
+            challenge_id: Set(new_challenge_id),
+            name:         Set(new_attachment.name.clone()),
+            content_type: Set(new_attachment
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string())),
+            size:         Set(i64::try_from(data.len()).map_err(ise!("CSCAS"))?),
+            sha256:       Set(format!("{:x}", Sha256::digest(&data))),
+        };
+        new_attachment.insert(&txn).await.map_err(ise!("CSINA"))?;
+    }
+
+    // Record the new challenge without flag values or attachment contents
+    let mut audited = serde_json::to_value(&payload.0).map_err(ise!("CSSAP"))?;
+    for f in audited["flags"].as_array_mut().into_iter().flatten() {
+        f["flag"] = "[redacted]".into();
+    }
+    for a in audited["attachments"].as_array_mut().into_iter().flatten() {
+        a["data"] = serde_json::Value::Null;
+    }
+    audit::record(
+        &txn,
+        &handler_utils::get_claims(&req)?.user_id,
+        "service.create",
+        new_challenge_id,
+        None,
+        Some(audited),
+    )
+    .await
+    .map_err(ise!("CSRAL"))?;
+
     // Commit transaction
     txn.commit().await.map_err(ise!("CSCFT"))?;
+    blobs.publish().await.map_err(ise!("CSPAB"))?;
+
+    // Announcing services before they are released would reveal them early
+    let now = Utc::now();
+    if payload
+        .services
+        .iter()
+        .any(|s| s.nbf.is_some_and(|nbf| nbf > now))
+    {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let service_names: Vec<&str> = payload.services.iter().map(|s| s.name.as_str()).collect();
+    notify::notify(
+        conn.as_ref(),
+        Payload::new(
+            Event::ServiceCreated,
+            format!("New challenge available: {}", service_names.join(", ")),
+            &serde_json::json!({
+                "challenge_id": new_challenge_id,
+                "services": service_names,
+                "flags": payload.flags.len(),
+            }),
+        ),
+    );
 
     Ok(HttpResponse::Ok().finish())
 }
@@ -0,0 +1,5 @@
+use actix_web::{get, HttpResponse};
+
+/// List every error code that the platform's APIs may return, so that clients can interpret them.
+#[get("")]
+pub(crate) async fn get_catalog() -> HttpResponse { HttpResponse::Ok().json(api_error::catalog()) }
@@ -1,17 +1,6 @@
-use actix_web::{
-    error::{
-        ErrorBadRequest,
-        ErrorForbidden,
-        ErrorInternalServerError,
-        ErrorNotFound,
-        ErrorUnauthorized,
-    },
-    post,
-    web,
-    Error,
-    HttpRequest,
-    HttpResponse,
-};
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::Roles;
 use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
 
@@ -35,28 +24,34 @@ impl EvaluationRequestResponse {
 #[post("/evaluate")]
 pub(crate) async fn evaluate(
     req: HttpRequest,
+    roles: Roles,
     payload: web::Json<EvaluateRequestPayload>,
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
     let id = req
         .headers()
         .get("x-scp-auth")
-        .ok_or_else(|| ErrorUnauthorized(""))?
+        .ok_or_else(|| ApiError::new(Code::Unauthenticated))?
         .to_str()
-        .map_err(ErrorForbidden)?;
+        .map_err(|_| ApiError::new(Code::InvalidToken))?;
 
-    let search_uri = url::Url::parse(&payload.uri).map_err(ErrorBadRequest)?;
+    let search_uri = url::Url::parse(&payload.uri)
+        .map_err(|e| ApiError::new(Code::InvalidRequest).detail(e.to_string()))?;
 
     // Search the registry to determine where the request should go
-    registry::evaluate_uri(search_uri, id, conn.as_ref())
+    registry::evaluate_uri(search_uri, id, &roles, conn.as_ref())
         .await
         .map(|destination| {
             HttpResponse::Ok().json(EvaluationRequestResponse::new(destination.to_string()))
         })
-        .map_err(|e| match e {
-            EvaluationErrors::Forbidden => ErrorForbidden(""),
-            EvaluationErrors::NotFound => ErrorNotFound(""),
-            EvaluationErrors::InvalidUriError => ErrorBadRequest(""),
-            _ => ErrorInternalServerError(""),
+        .map_err(|e| {
+            let code = match e {
+                EvaluationErrors::Forbidden => Code::Forbidden,
+                EvaluationErrors::NotFound => Code::NotFound,
+                EvaluationErrors::InvalidUriError => Code::InvalidRequest,
+                EvaluationErrors::InternalError => Code::InternalError,
+                EvaluationErrors::InstanceUnavailable => Code::InstanceUnavailable,
+            };
+            ApiError::new(code).detail(e.to_string()).into()
         })
 }
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use actix_web::{get, http::header, web, Error, HttpRequest, HttpResponse};
+use authz::{Permission, Roles};
+use sea_orm::DatabaseConnection;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    feed::{SolveFeed, Viewer},
+    handler_utils::{self, ise},
+    teams,
+};
+
+/// How often a comment is sent to keep idle connections open.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Stream solve events as they happen using Server-Sent Events. Tutors and admins receive every
+/// event; students receive their own and their team's events along with anonymised first bloods.
+#[get("")]
+pub(crate) async fn get_feed(
+    req: HttpRequest,
+    roles: Roles,
+    feed: web::Data<SolveFeed>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // Get the auth token
+    let claims = handler_utils::get_claims(&req)?;
+    let uid = handler_utils::parse_user_id(&claims.user_id).unwrap();
+
+    let viewer = if roles.can(Permission::ViewAllSolves) {
+        Viewer::Staff
+    } else {
+        Viewer::Student {
+            user_id: uid,
+            team_id: teams::get_team_id(conn.as_ref(), uid)
+                .await
+                .map_err(ise!("GFGTI"))?,
+        }
+    };
+
+    let state = (
+        feed.subscribe(),
+        tokio::time::interval(HEARTBEAT_INTERVAL),
+        viewer,
+    );
+    let stream = futures_util::stream::unfold(
+        state,
+        |(mut rx, mut heartbeat, viewer)| async move {
+            loop {
+                tokio::select! {
+                    _ = heartbeat.tick() => {
+                        let comment = web::Bytes::from_static(b": heartbeat\n\n");
+                        return Some((Ok::<_, Error>(comment), (rx, heartbeat, viewer)));
+                    },
+                    event = rx.recv() => match event {
+                        Ok(event) => {
+                            if let Some(event) = event.visible_to(viewer) {
+                                let data = serde_json::to_string(&event).unwrap_or_default();
+                                let message = web::Bytes::from(format!("event: solve\ndata: {data}\n\n"));
+                                return Some((Ok(message), (rx, heartbeat, viewer)));
+                            }
+                        },
+                        // Slow subscribers skip the events they missed
+                        Err(RecvError::Lagged(_)) => {},
+                        Err(RecvError::Closed) => return None,
+                    },
+                }
+            }
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(stream))
+}
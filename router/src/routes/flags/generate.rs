@@ -1,18 +1,15 @@
-use actix_web::{
-    error::{ErrorBadGateway, ErrorNotFound},
-    get,
-    web,
-    Error,
-    HttpRequest,
-    HttpResponse,
-};
-use hmac::{Hmac, Mac};
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
 use router_entity::flag::{self, FlagType};
 use sea_orm::{DatabaseConnection, EntityTrait};
 use serde::Deserialize;
-use sha2::Sha256;
 
-use crate::{handler_utils::ise, HMAC_KEY};
+use crate::{
+    config,
+    flag_codec,
+    handler_utils::{self, ise},
+    teams,
+};
 
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct GenerateFlagQueryParams {
@@ -20,8 +17,6 @@ pub(crate) struct GenerateFlagQueryParams {
     pub(crate) id: String,
 }
 
-type HmacSha256 = Hmac<Sha256>;
-
 #[get("/generate")]
 pub(crate) async fn generate_flag(
     req: HttpRequest,
@@ -29,7 +24,7 @@ pub(crate) async fn generate_flag(
     conn: web::Data<DatabaseConnection>,
 ) -> Result<HttpResponse, Error> {
     // Get the auth token
-    let claims = crate::handler_utils::get_claims(&req)?;
+    let claims = handler_utils::get_claims(&req)?;
     // Get the user id/email
     let email = claims.user_id;
 
@@ -38,24 +33,22 @@ pub(crate) async fn generate_flag(
         .one(conn.as_ref())
         .await
         .map_err(ise!("GFFFI"))?
-        .ok_or_else(|| ErrorNotFound("Supplied flag ID does not exist"))?;
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Supplied flag ID does not exist"))?;
 
     if let FlagType::Static = found_flag.flag_type {
-        return Err(ErrorBadGateway("Cannot generate a flag for a static flag"));
+        return Err(ApiError::new(Code::StaticFlag).into());
     }
 
-    // Hash the username and flag id together
-    let mut mac = HmacSha256::new_from_slice(HMAC_KEY.as_bytes()).unwrap();
-    mac.update(format!("{}_{}", email, params.id).as_bytes());
-    let result = mac.finalize();
-    let signature = base64::encode(result.into_bytes());
+    // Flags for challenges in team mode are generated for the user's team instead
+    let uid = handler_utils::parse_user_id(&email).unwrap();
+    let identity = teams::get_solving_team(conn.as_ref(), found_flag.challenge_id, uid)
+        .await
+        .map_err(ise!("GFGST"))?
+        .map_or(email, teams::team_identity);
 
-    let generated_flag = format!(
-        "COMP6443{{{}.{}.{}}}",
-        found_flag.flag,
-        base64::encode(&email),
-        signature
-    );
+    let key = config::get().keyring.signing_key();
+    let generated_flag = flag_codec::encode(key, &found_flag.flag, &params.id, &identity)
+        .ok_or_else(|| ApiError::internal("GFEF", format!("cannot bind a flag to {identity}")))?;
 
-    Ok(HttpResponse::Ok().body(generated_flag))
+    Ok(HttpResponse::Ok().body(format!("COMP6443{{{generated_flag}}}")))
 }
@@ -0,0 +1,682 @@
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::Permission;
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use router_entity::{
+    attempt::{self, AttemptOutcome},
+    challenge::{self, LatePolicy},
+    flag::{self, FlagType},
+    incident,
+    submission,
+    user,
+};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DatabaseTransaction,
+    DbErr,
+    EntityTrait,
+    PaginatorTrait,
+    QueryFilter,
+    Set,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use webhooks::{Event, Payload};
+
+use crate::{
+    config::{self, AttemptValueMode},
+    feed::{SolveEvent, SolveFeed},
+    flag_codec::{self, DecodeError},
+    handler_utils::{self, ise},
+    keyring::SignatureError,
+    notify,
+    overrides,
+    prerequisites,
+    rate_limit,
+    teams,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SubmitFlagPayload {
+    pub(crate) flag: String,
+}
+
+/// The reason that a submitted flag was rejected.
+#[derive(Debug, Clone)]
+enum Rejection {
+    /// The flag was invalid. The number is returned to the user and recorded with the attempt.
+    Invalid(i32),
+    /// The flag is a valid dynamic flag, but it was generated for another user.
+    Shared { owner: String },
+    /// The flag id does not exist.
+    NotFound,
+}
+
+impl Rejection {
+    /// The number returned to the user and recorded with the attempt.
+    fn reason(&self) -> Option<i32> {
+        match self {
+            Rejection::Invalid(reason) => Some(*reason),
+            Rejection::Shared { .. } => Some(7),
+            Rejection::NotFound => None,
+        }
+    }
+
+    /// The error returned to the user.
+    fn error(&self) -> ApiError {
+        let code = match self.reason() {
+            None => return ApiError::new(Code::NotFound).detail("Flag does not exist"),
+            Some(1) => Code::FlagMalformed,
+            Some(2) => Code::FlagIncorrect,
+            Some(3) => Code::FlagNotDynamic,
+            Some(4) => Code::FlagWrongName,
+            Some(5) => Code::FlagBadEncoding,
+            Some(6) => Code::FlagBadIdentity,
+            Some(7) => Code::FlagNotOwned,
+            Some(9) => Code::FlagKeyRetired,
+            Some(_) => Code::FlagBadSignature,
+        };
+
+        ApiError::new(code).with("reason", self.reason())
+    }
+}
+
+/// Check a submitted flag against the flag it was submitted for. Dynamic flags must have been
+/// generated for one of the supplied identities.
+fn check_flag(
+    actual_flag: Option<&flag::Model>,
+    submitted: &str,
+    identities: &[String],
+    flag_id: &str,
+) -> Result<(), Rejection> {
+    // Ensure that the supplied flag is of the form COMP6443{...}
+    let Some(contents) = submitted
+        .strip_prefix("COMP6443{")
+        .and_then(|s| s.strip_suffix('}'))
+        .filter(|s| !s.is_empty())
+    else {
+        return Err(Rejection::Invalid(1));
+    };
+
+    let actual_flag = actual_flag.ok_or(Rejection::NotFound)?;
+
+    match actual_flag.flag_type {
+        FlagType::Static => {
+            if !flag_codec::secure_eq(contents, &actual_flag.flag) {
+                return Err(Rejection::Invalid(2));
+            }
+        },
+        FlagType::Dynamic => {
+            let flag = flag_codec::decode(
+                &config::get().keyring,
+                contents,
+                &actual_flag.flag,
+                flag_id,
+                chrono::offset::Utc::now(),
+            )
+            .map_err(|e| {
+                Rejection::Invalid(match e {
+                    DecodeError::Malformed => 3,
+                    DecodeError::WrongName => 4,
+                    DecodeError::BadEncoding => 5,
+                    DecodeError::BadIdentity => 6,
+                    DecodeError::Signature(SignatureError::Invalid) => 8,
+                    DecodeError::Signature(SignatureError::Retired) => 9,
+                })
+            })?;
+
+            if !identities.contains(&flag.owner) {
+                // Determine if this is a genuine flag that was generated for someone else
+                if flag.verified.is_ok() {
+                    return Err(Rejection::Shared { owner: flag.owner });
+                }
+
+                return Err(Rejection::Invalid(7));
+            }
+
+            match flag.verified {
+                Ok(()) => {},
+                Err(SignatureError::Invalid) => return Err(Rejection::Invalid(8)),
+                Err(SignatureError::Retired) => return Err(Rejection::Invalid(9)),
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Store a submission attempt and return its id. The submitted value is stored according to
+/// `ATTEMPT_VALUE_MODE`.
+async fn record_attempt<C>(
+    conn: &C,
+    user_id: i64,
+    flag_id: &str,
+    submitted: &str,
+    outcome: AttemptOutcome,
+    reason: Option<i32>,
+) -> Result<i64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let value = match config::get().attempt_value_mode {
+        AttemptValueMode::Plain => Some(submitted.to_string()),
+        AttemptValueMode::Redact => None,
+        AttemptValueMode::Hash => Some(format!("{:x}", Sha256::digest(submitted.as_bytes()))),
+    };
+
+    let id = IdInstance::next_id();
+    let new_attempt = attempt::ActiveModel {
+        id:           Set(id),
+        user_id:      Set(user_id),
+        flag_id:      Set(flag_id.to_string()),
+        value:        Set(value),
+        outcome:      Set(outcome),
+        reason:       Set(reason),
+        attempt_time: Set(chrono::offset::Utc::now()),
+    };
+    new_attempt.insert(conn).await?;
+
+    Ok(id)
+}
+
+/// Whether an error is a violation of the unique index on the blood ranks of a flag.
+fn is_blood_taken(e: &DbErr) -> bool {
+    // Postgres names the index, while SQLite names its columns
+    let message = e.to_string();
+    message.contains("idx-submissions-flagid-blood")
+        || (message.contains("UNIQUE constraint failed") && message.contains("submissions.blood"))
+}
+
+/// Whether an error is a violation of the unique indexes that allow each user, or each team in
+/// team mode, to solve a flag once.
+fn is_already_submitted(e: &DbErr) -> bool {
+    // Postgres names the index, while SQLite names its columns
+    let message = e.to_string();
+    message.contains("idx-submissions-flagid-userid")
+        || message.contains("idx-submissions-flagid-teamid")
+        || (message.contains("UNIQUE constraint failed")
+            && (message.contains("submissions.user_id") || message.contains("submissions.team_id")))
+}
+
+/// The result of storing a correct submission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stored {
+    /// The submission was stored, along with the blood rank it was awarded, if any.
+    Solved(Option<i32>),
+    /// The user or their team submitted the flag in a concurrent request first.
+    Duplicate,
+}
+
+/// Store a correct submission, awarding a blood rank if it is the first, second or third solve of
+/// the flag.
+///
+/// The rank is counted from the solves that the transaction can see, so a concurrent solve may
+/// claim it first. The insert is made in a savepoint, so that if the unique index on the rank
+/// rejects it, the next rank is tried instead. Once every rank is taken, the submission is stored
+/// without one, so a valid solve never fails because of a race. A concurrent solve by the same
+/// user or team is caught by the unique indexes on the solvers of a flag instead.
+async fn insert_submission(
+    txn: &DatabaseTransaction,
+    new_submission: submission::ActiveModel,
+) -> Result<Stored, DbErr> {
+    let flag_id = new_submission.flag_id.clone().unwrap();
+    let previous_solves = submission::Entity::find()
+        .filter(submission::Column::FlagId.eq(flag_id))
+        .count(txn)
+        .await?;
+
+    let mut rank = previous_solves + 1;
+    loop {
+        let blood = i32::try_from(rank).ok().filter(|rank| *rank <= 3);
+        let savepoint = txn.begin().await?;
+        let mut new_submission = new_submission.clone();
+        new_submission.blood = Set(blood);
+
+        match new_submission.insert(&savepoint).await {
+            Ok(_) => {
+                savepoint.commit().await?;
+                return Ok(Stored::Solved(blood));
+            },
+            Err(e) if is_already_submitted(&e) => {
+                savepoint.rollback().await?;
+                return Ok(Stored::Duplicate);
+            },
+            Err(e) if blood.is_some() && is_blood_taken(&e) => {
+                savepoint.rollback().await?;
+                rank += 1;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Ensure that the user has not made too many attempts recently.
+async fn check_rate_limit(conn: &DatabaseConnection, uid: i64, flag_id: &str) -> Result<(), Error> {
+    if let Some(retry_after) = rate_limit::check(conn, uid, flag_id)
+        .await
+        .map_err(ise!("SFCRL"))?
+    {
+        let seconds = (retry_after.num_milliseconds() + 999) / 1000;
+        return Err(ApiError::new(Code::RateLimited)
+            .detail(format!("Try again in {seconds} seconds."))
+            .retry_after(seconds)
+            .into());
+    }
+
+    Ok(())
+}
+
+/// Ensure that the user may submit flags for a flag's challenge. Users who can view unreleased
+/// challenges may submit flags that are locked or not yet released.
+async fn check_access(
+    req: &HttpRequest,
+    conn: &DatabaseConnection,
+    uid: i64,
+    actual_flag: &flag::Model,
+) -> Result<(), Error> {
+    // Ensure that the user has unlocked the flag
+    let locked = prerequisites::get_locked(conn, uid)
+        .await
+        .map_err(ise!("SFGLC"))?;
+    if locked.flags.contains(&actual_flag.id)
+        || locked.challenges.contains(&actual_flag.challenge_id)
+    {
+        let roles = handler_utils::get_request_roles(req).await?;
+        if !roles.can(Permission::ViewUnreleased) {
+            return Err(ApiError::new(Code::Locked)
+                .detail("This flag has not been unlocked yet")
+                .into());
+        }
+    }
+
+    // Ensure that the challenge has been released to the user
+    if !overrides::has_started(conn, uid, actual_flag.challenge_id)
+        .await
+        .map_err(ise!("SFCHS"))?
+    {
+        let roles = handler_utils::get_request_roles(req).await?;
+        if !roles.can(Permission::ViewUnreleased) {
+            return Err(ApiError::new(Code::NotReleased)
+                .detail("This challenge has not been released yet")
+                .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Record a rejected submission, and an incident if the user submitted another user's flag.
+/// Failures only count towards a lockout if the flag exists, so that lockouts are not created for
+/// made up flag ids. Attempts for those still count towards the per user limit.
+async fn record_rejection(
+    conn: &DatabaseConnection,
+    uid: i64,
+    flag_id: &str,
+    actual_flag: Option<&flag::Model>,
+    submitted: &str,
+    rejection: &Rejection,
+) -> Result<(), Error> {
+    let attempt_id = record_attempt(
+        conn,
+        uid,
+        flag_id,
+        submitted,
+        AttemptOutcome::Incorrect,
+        rejection.reason(),
+    )
+    .await
+    .map_err(ise!("SFRIA"))?;
+
+    if let Some(actual_flag) = actual_flag {
+        rate_limit::record_failure(conn, uid, &actual_flag.id)
+            .await
+            .map_err(ise!("SFRRF"))?;
+    }
+
+    if let Rejection::Shared { owner } = rejection {
+        warn!(
+            "user {} submitted flag {} generated for {}",
+            uid, flag_id, owner
+        );
+        record_incidents(conn, uid, flag_id, attempt_id, owner)
+            .await
+            .map_err(ise!("SFCNI"))?;
+    }
+
+    Ok(())
+}
+
+/// Record an incident against each user that a shared flag was generated for. Flags generated
+/// for a team are recorded against each of its current members, other than the submitter.
+async fn record_incidents<C>(
+    conn: &C,
+    uid: i64,
+    flag_id: &str,
+    attempt_id: i64,
+    owner: &str,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let owner_ids = match handler_utils::parse_user_id(owner) {
+        Some(owner_id) => vec![owner_id],
+        None => match teams::parse_team_identity(owner) {
+            Some(team_id) => teams::get_member_ids(conn, team_id).await?,
+            None => Vec::new(),
+        },
+    };
+
+    let incident_time = chrono::offset::Utc::now();
+    for owner_id in owner_ids.into_iter().filter(|id| *id != uid) {
+        let new_incident = incident::ActiveModel {
+            id:            Set(IdInstance::next_id()),
+            flag_id:       Set(flag_id.to_string()),
+            submitter_id:  Set(uid),
+            owner_id:      Set(owner_id),
+            attempt_id:    Set(attempt_id),
+            incident_time: Set(incident_time),
+        };
+        new_incident.insert(conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Determine how late a submission is and the percentage of the flag's value that is deducted
+/// for it. Late submissions are recorded and rejected if the deadline's policy rejects them.
+async fn check_deadline(
+    conn: &DatabaseConnection,
+    uid: i64,
+    actual_flag: &flag::Model,
+    submitted: &str,
+) -> Result<(Option<chrono::Duration>, i32), Error> {
+    let challenge = challenge::Entity::find_by_id(actual_flag.challenge_id)
+        .one(conn)
+        .await
+        .map_err(ise!("SFQC"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Challenge does not exist"))?;
+    let deadline = overrides::get_overrides(conn, uid)
+        .await
+        .map_err(ise!("SFGDO"))?
+        .deadline(&challenge, actual_flag);
+    let lateness = deadline
+        .as_ref()
+        .and_then(|d| d.lateness(chrono::offset::Utc::now()));
+
+    let late_penalty = match (&deadline, lateness) {
+        (Some(d), Some(_)) if d.policy == LatePolicy::Reject => {
+            record_attempt(
+                conn,
+                uid,
+                &actual_flag.id,
+                submitted,
+                AttemptOutcome::Late,
+                None,
+            )
+            .await
+            .map_err(ise!("SFRLA"))?;
+
+            return Err(ApiError::new(Code::DeadlinePassed)
+                .detail("The submission deadline for this flag has passed")
+                .into());
+        },
+        (Some(d), Some(_)) => d.penalty,
+        _ => 0,
+    };
+
+    Ok((lateness, late_penalty))
+}
+
+/// Create the user's row if this is their first submission.
+async fn ensure_user(txn: &DatabaseTransaction, uid: i64) -> Result<(), Error> {
+    if user::Entity::find_by_id(uid)
+        .one(txn)
+        .await
+        .map_err(ise!("SFGU"))?
+        .is_none()
+    {
+        let new_user = user::ActiveModel { id: Set(uid) };
+        new_user.insert(txn).await.map_err(ise!("SFCNU"))?;
+    }
+
+    Ok(())
+}
+
+/// Whether the user, or their team if the flag is solved once per team, has already submitted
+/// the flag.
+async fn has_submitted(
+    txn: &DatabaseTransaction,
+    uid: i64,
+    team_id: Option<i64>,
+    flag_id: &str,
+) -> Result<bool, Error> {
+    let submitters = match team_id {
+        Some(team_id) => teams::get_member_ids(txn, team_id)
+            .await
+            .map_err(ise!("SFGTM"))?,
+        None => vec![uid],
+    };
+
+    Ok(submission::Entity::find()
+        .filter(submission::Column::FlagId.eq(flag_id))
+        .filter(submission::Column::UserId.is_in(submitters))
+        .one(txn)
+        .await
+        .map_err(ise!("SFFES"))?
+        .is_some())
+}
+
+/// Record a submission of a flag that the user or their team has already submitted, and reject
+/// it.
+async fn reject_duplicate(
+    conn: &DatabaseConnection,
+    uid: i64,
+    flag_id: &str,
+    submitted: &str,
+    team_id: Option<i64>,
+) -> Result<HttpResponse, Error> {
+    record_attempt(
+        conn,
+        uid,
+        flag_id,
+        submitted,
+        AttemptOutcome::Duplicate,
+        None,
+    )
+    .await
+    .map_err(ise!("SFRDA"))?;
+
+    Err(ApiError::new(Code::AlreadySubmitted)
+        .detail(if team_id.is_some() {
+            "The user's team has already submitted this flag"
+        } else {
+            "The user has already submitted this flag"
+        })
+        .into())
+}
+
+/// Send webhooks and publish a solve to the live feed.
+fn announce_solve(
+    conn: &DatabaseConnection,
+    feed: &SolveFeed,
+    actual_flag: flag::Model,
+    uid: i64,
+    team_id: Option<i64>,
+    blood: Option<i32>,
+    submission_time: chrono::DateTime<chrono::Utc>,
+) {
+    let data = serde_json::json!({
+        "flag_id": actual_flag.id,
+        "challenge_id": actual_flag.challenge_id,
+        "user_id": uid,
+        "team_id": team_id,
+        "blood": blood,
+        "timestamp": submission_time,
+    });
+    notify::notify(
+        conn,
+        Payload::new(
+            Event::Solve,
+            format!("{} was solved", actual_flag.display_name),
+            &data,
+        ),
+    );
+    if blood == Some(1) {
+        notify::notify(
+            conn,
+            Payload::new(
+                Event::FirstBlood,
+                format!("First blood on {}!", actual_flag.display_name),
+                &data,
+            ),
+        );
+    }
+
+    feed.publish(SolveEvent {
+        user_id: Some(uid),
+        team_id,
+        flag_id: actual_flag.id,
+        challenge_id: actual_flag.challenge_id,
+        timestamp: submission_time,
+        first_blood: blood == Some(1),
+    });
+}
+
+#[post("/{id}/submit")]
+pub(crate) async fn submit_flag(
+    req: HttpRequest,
+    conn: web::Data<DatabaseConnection>,
+    flag_id: web::Path<String>,
+    flag_payload: web::Json<SubmitFlagPayload>,
+    feed: web::Data<SolveFeed>,
+) -> Result<HttpResponse, Error> {
+    // Get the auth token
+    let claims = handler_utils::get_claims(&req)?;
+    // Get the user id/email
+    let email = claims.user_id;
+
+    let uid = handler_utils::parse_user_id(&email).unwrap();
+
+    // Setup id generator
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).map_err(ise!("CIG"))?;
+
+    // Ensure that the user has not made too many attempts recently
+    check_rate_limit(conn.as_ref(), uid, &flag_id).await?;
+
+    // Check if this flag name correlates with this flag id
+    let actual_flag = flag::Entity::find_by_id(flag_id.clone())
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("SFFFV"))?;
+
+    // Flags for challenges in team mode are solved once per team
+    let team_id = match &actual_flag {
+        Some(f) => {
+            check_access(&req, conn.as_ref(), uid, f).await?;
+            teams::get_solving_team(conn.as_ref(), f.challenge_id, uid)
+                .await
+                .map_err(ise!("SFGST"))?
+        },
+        None => None,
+    };
+
+    // Users may submit dynamic flags generated for themselves or their team
+    let mut identities = vec![email.clone()];
+    identities.extend(team_id.map(teams::team_identity));
+
+    if let Err(rejection) = check_flag(
+        actual_flag.as_ref(),
+        flag_payload.flag.as_str(),
+        &identities,
+        &flag_id,
+    ) {
+        record_rejection(
+            conn.as_ref(),
+            uid,
+            &flag_id,
+            actual_flag.as_ref(),
+            &flag_payload.flag,
+            &rejection,
+        )
+        .await?;
+        return Err(rejection.error().into());
+    }
+    let actual_flag = actual_flag.unwrap();
+
+    // Determine whether the submission is late
+    let (lateness, late_penalty) =
+        check_deadline(conn.as_ref(), uid, &actual_flag, &flag_payload.flag).await?;
+
+    // So far so good; store the submission in the database
+    let txn = conn.begin().await.map_err(ise!("SFSTX"))?;
+    ensure_user(&txn, uid).await?;
+
+    // Determine if the user (or their team) has already submitted this flag
+    if has_submitted(&txn, uid, team_id, &actual_flag.id).await? {
+        txn.rollback().await.map_err(ise!("SFRTX"))?;
+        return reject_duplicate(conn.as_ref(), uid, &flag_id, &flag_payload.flag, team_id).await;
+    }
+
+    // Create a new submission
+    let submission_time = chrono::offset::Utc::now();
+    let new_submission = submission::ActiveModel {
+        flag_id:         Set(actual_flag.id.clone()),
+        id:              Set(IdInstance::next_id()),
+        submission_time: Set(submission_time),
+        user_id:         Set(uid),
+        team_id:         Set(team_id),
+        late_seconds:    Set(lateness.map(|l| l.num_seconds())),
+        late_penalty:    Set(late_penalty),
+        blood:           Set(None),
+    };
+    let blood = match insert_submission(&txn, new_submission)
+        .await
+        .map_err(ise!("SFCNS"))?
+    {
+        Stored::Solved(blood) => blood,
+        Stored::Duplicate => {
+            txn.rollback().await.map_err(ise!("SFRTX"))?;
+            return reject_duplicate(conn.as_ref(), uid, &flag_id, &flag_payload.flag, team_id)
+                .await;
+        },
+    };
+
+    record_attempt(
+        &txn,
+        uid,
+        &flag_id,
+        &flag_payload.flag,
+        AttemptOutcome::Correct,
+        None,
+    )
+    .await
+    .map_err(ise!("SFRCA"))?;
+
+    rate_limit::clear(&txn, uid, Some(&flag_id))
+        .await
+        .map_err(ise!("SFCLO"))?;
+
+    // Commit
+    txn.commit().await.map_err(ise!("SFCTX"))?;
+
+    announce_solve(
+        conn.as_ref(),
+        &feed,
+        actual_flag,
+        uid,
+        team_id,
+        blood,
+        submission_time,
+    );
+
+    Ok(HttpResponse::Accepted().finish())
+}
@@ -0,0 +1,253 @@
+use chrono::{TimeZone, Utc};
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use migration::{Migrator, MigratorTrait};
+use router_entity::{
+    attempt::{self, AttemptOutcome},
+    category,
+    challenge::{self, LatePolicy},
+    flag::{self, FlagType, ScoringMode},
+    incident,
+    submission,
+    team,
+    team_member,
+    user,
+};
+use sea_orm::{
+    ActiveModelTrait,
+    Database,
+    DatabaseConnection,
+    EntityTrait,
+    QueryOrder,
+    Set,
+    TransactionTrait,
+};
+
+use super::{insert_submission, record_incidents, Stored};
+
+async fn database(users: i64) -> DatabaseConnection {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    challenge::ActiveModel {
+        id:           Set(1),
+        team_mode:    Set(false),
+        description:  Set(String::new()),
+        author:       Set(None),
+        difficulty:   Set(None),
+        deadline:     Set(None),
+        late_policy:  Set(LatePolicy::Reject),
+        late_penalty: Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    category::ActiveModel {
+        id:   Set(1),
+        name: Set("web".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    flag::ActiveModel {
+        id:                 Set("f1".to_string()),
+        challenge_id:       Set(1),
+        category_id:        Set(1),
+        flag:               Set("flag".to_string()),
+        flag_type:          Set(FlagType::Static),
+        points:             Set(100),
+        display_name:       Set("Flag".to_string()),
+        scoring_mode:       Set(ScoringMode::Static),
+        minimum_points:     Set(None),
+        decay:              Set(None),
+        deadline:           Set(None),
+        late_policy:        Set(LatePolicy::Reject),
+        late_penalty:       Set(0),
+        first_blood_bonus:  Set(0),
+        second_blood_bonus: Set(0),
+        third_blood_bonus:  Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    for id in 1..=users {
+        user::ActiveModel { id: Set(id) }
+            .insert(&conn)
+            .await
+            .unwrap();
+    }
+    conn
+}
+
+fn new_submission(id: i64, user_id: i64) -> submission::ActiveModel {
+    submission::ActiveModel {
+        id:              Set(id),
+        user_id:         Set(user_id),
+        flag_id:         Set("f1".to_string()),
+        submission_time: Set(Utc.ymd(2024, 1, 1).and_hms(0, 0, 0)),
+        team_id:         Set(None),
+        late_seconds:    Set(None),
+        late_penalty:    Set(0),
+        blood:           Set(None),
+    }
+}
+
+async fn store(conn: &DatabaseConnection, new_submission: submission::ActiveModel) -> Stored {
+    let txn = conn.begin().await.unwrap();
+    let stored = insert_submission(&txn, new_submission).await.unwrap();
+    txn.commit().await.unwrap();
+    stored
+}
+
+async fn solve(conn: &DatabaseConnection, user_id: i64) -> Option<i32> {
+    match store(conn, new_submission(user_id, user_id)).await {
+        Stored::Solved(blood) => blood,
+        Stored::Duplicate => panic!("user {user_id} had already solved the flag"),
+    }
+}
+
+async fn bloods(conn: &DatabaseConnection) -> Vec<Option<i32>> {
+    submission::Entity::find()
+        .order_by_asc(submission::Column::Id)
+        .all(conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.blood)
+        .collect()
+}
+
+#[tokio::test]
+async fn awards_bloods_in_order() {
+    let conn = database(5).await;
+    for user_id in 1..=5 {
+        solve(&conn, user_id).await;
+    }
+
+    assert_eq!(bloods(&conn).await, [Some(1), Some(2), Some(3), None, None]);
+}
+
+#[tokio::test]
+async fn accepts_concurrent_solves() {
+    let conn = database(2).await;
+
+    let (a, b) = tokio::join!(solve(&conn, 1), solve(&conn, 2));
+    let mut awarded = vec![a, b];
+    awarded.sort_unstable();
+    assert_eq!(awarded, [Some(1), Some(2)]);
+}
+
+#[tokio::test]
+async fn skips_ranks_claimed_by_concurrent_solves() {
+    let conn = database(3).await;
+
+    // A concurrent solve claimed the second blood without being counted, as if it committed
+    // after this solve's count
+    let mut raced = new_submission(1, 1);
+    raced.blood = Set(Some(2));
+    raced.insert(&conn).await.unwrap();
+
+    assert_eq!(solve(&conn, 2).await, Some(3));
+    assert_eq!(solve(&conn, 3).await, None);
+    assert_eq!(bloods(&conn).await, [Some(2), Some(3), None]);
+}
+
+#[tokio::test]
+async fn rejects_concurrent_duplicates() {
+    let conn = database(3).await;
+
+    // The same user submits twice at once
+    let (a, b) = tokio::join!(
+        store(&conn, new_submission(1, 1)),
+        store(&conn, new_submission(2, 1))
+    );
+    let mut stored = vec![a, b];
+    stored.sort_by_key(|s| *s == Stored::Duplicate);
+    assert_eq!(stored, [Stored::Solved(Some(1)), Stored::Duplicate]);
+
+    // Two members of a team submit at once
+    team::ActiveModel {
+        id:   Set(1),
+        name: Set("team".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    let for_team = |id, user_id| {
+        let mut sub = new_submission(id, user_id);
+        sub.team_id = Set(Some(1));
+        sub
+    };
+    let (a, b) = tokio::join!(store(&conn, for_team(3, 2)), store(&conn, for_team(4, 3)));
+    let mut stored = vec![a, b];
+    stored.sort_by_key(|s| *s == Stored::Duplicate);
+    assert_eq!(stored, [Stored::Solved(Some(2)), Stored::Duplicate]);
+    assert_eq!(bloods(&conn).await, [Some(1), Some(2)]);
+}
+
+#[tokio::test]
+async fn records_incidents_for_team_owners() {
+    IdInstance::init(IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6)).unwrap();
+    let conn = database(0).await;
+    team::ActiveModel {
+        id:   Set(1),
+        name: Set("team".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    for user_id in [2, 3] {
+        team_member::ActiveModel {
+            id:      Set(user_id),
+            team_id: Set(1),
+            user_id: Set(user_id),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    attempt::ActiveModel {
+        id:           Set(1),
+        user_id:      Set(4),
+        flag_id:      Set("f1".to_string()),
+        value:        Set(None),
+        outcome:      Set(AttemptOutcome::Incorrect),
+        reason:       Set(Some(7)),
+        attempt_time: Set(Utc.ymd(2024, 1, 1).and_hms(0, 0, 0)),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    let owners = |submitter_id| {
+        let conn = conn.clone();
+        async move {
+            let mut owners: Vec<_> = incident::Entity::find()
+                .all(&conn)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|i| i.submitter_id == submitter_id)
+                .map(|i| i.owner_id)
+                .collect();
+            owners.sort_unstable();
+            owners
+        }
+    };
+
+    record_incidents(&conn, 4, "f1", 1, "_scpU5@unsw.scp.platform")
+        .await
+        .unwrap();
+    assert_eq!(owners(4).await, [5]);
+
+    // Flags generated for a team are recorded against its members
+    record_incidents(&conn, 6, "f1", 1, "_scpT1@unsw.scp.platform")
+        .await
+        .unwrap();
+    assert_eq!(owners(6).await, [2, 3]);
+
+    // Members who submit their own team's flag are not recorded against themselves
+    record_incidents(&conn, 3, "f1", 1, "_scpT1@unsw.scp.platform")
+        .await
+        .unwrap();
+    assert_eq!(owners(3).await, [2]);
+}
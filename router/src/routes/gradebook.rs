@@ -0,0 +1,65 @@
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web,
+    Error,
+    HttpRequest,
+    HttpResponse,
+};
+use api_error::{ApiError, Code};
+use authz::{perm, RequirePermission};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    gaia_utils,
+    gradebook::{self, GroupBy},
+    handler_utils::ise,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GradebookQueryParams {
+    /// Either `json` or `csv`. Defaults to `json`.
+    #[serde(default)]
+    pub(crate) format: Option<String>,
+    /// Whether marks are grouped by `category` or `challenge`. Defaults to `category`.
+    #[serde(default)]
+    pub(crate) by:     GroupBy,
+}
+
+/// Export every student's marks for upload to the university's gradebook.
+#[get("")]
+pub(crate) async fn export_gradebook(
+    _: RequirePermission<perm::ExportGradebook>,
+    req: HttpRequest,
+    params: web::Query<GradebookQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let token = req
+        .headers()
+        .get("X-Scp-Auth")
+        .ok_or_else(|| ApiError::new(Code::Unauthenticated).detail("Missing authentication token"))?
+        .to_str()
+        .map_err(|_| ApiError::new(Code::InvalidToken))?;
+
+    // Resolve user ids to enrolment emails
+    let users = gaia_utils::get_users(token).await.map_err(ise!("EGGU"))?;
+
+    let gradebook = gradebook::build(conn.as_ref(), &users, params.by)
+        .await
+        .map_err(ise!("EGBG"))?;
+
+    match params.format.as_deref() {
+        None | Some("json") => Ok(HttpResponse::Ok().json(gradebook)),
+        Some("csv") => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters:  vec![DispositionParam::Filename("gradebook.csv".to_string())],
+            })
+            .body(gradebook::to_csv(&gradebook))),
+        Some(_) => Err(ApiError::new(Code::InvalidRequest)
+            .detail("The format must be either json or csv")
+            .into()),
+    }
+}
@@ -0,0 +1,126 @@
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::Permission;
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use router_entity::{hint, hint_unlock};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    ConnectionTrait,
+    DatabaseConnection,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    Set,
+};
+use serde::Serialize;
+
+use crate::{
+    handler_utils::{self, ise},
+    overrides,
+    prerequisites,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Serialize)]
+struct ReturnHint {
+    id:      i64,
+    flag_id: Option<String>,
+    cost:    i32,
+    content: String,
+}
+
+/// Determine whether a hint is available to a user. Hints are available once the hint's challenge
+/// has been released to the user and they have unlocked the challenge or flag that the hint is
+/// for. Returns the error to reject the user with if it is not.
+async fn check_available<C>(
+    conn: &C,
+    uid: i64,
+    hint: &hint::Model,
+) -> Result<Option<ApiError>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    if !overrides::has_started(conn, uid, hint.challenge_id).await? {
+        return Ok(Some(
+            ApiError::new(Code::NotReleased).detail("This challenge has not been released yet"),
+        ));
+    }
+
+    let locked = prerequisites::get_locked(conn, uid).await?;
+    if locked.challenges.contains(&hint.challenge_id)
+        || hint
+            .flag_id
+            .as_ref()
+            .is_some_and(|f| locked.flags.contains(f))
+    {
+        return Ok(Some(
+            ApiError::new(Code::Locked).detail("This hint has not been unlocked yet"),
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Unlock a hint for the requesting user and return its content. The hint's cost is deducted from
+/// the user's score. Unlocking a hint more than once does not deduct its cost again.
+#[post("/{id}/unlock")]
+pub(crate) async fn unlock_hint(
+    req: HttpRequest,
+    hint_id: web::Path<i64>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // Get the auth token
+    let claims = handler_utils::get_claims(&req)?;
+    let uid = handler_utils::parse_user_id(&claims.user_id).unwrap();
+
+    let hint = hint::Entity::find_by_id(*hint_id)
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("UHQH"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Hint does not exist"))?;
+
+    // Ensure that the hint is available to the user before revealing it or deducting its cost
+    if let Some(error) = check_available(conn.as_ref(), uid, &hint)
+        .await
+        .map_err(ise!("UHCA"))?
+    {
+        let roles = handler_utils::get_request_roles(&req).await?;
+        if !roles.can(Permission::ViewUnreleased) {
+            return Err(error.into());
+        }
+    }
+
+    if hint_unlock::Entity::find()
+        .filter(hint_unlock::Column::HintId.eq(hint.id))
+        .filter(hint_unlock::Column::UserId.eq(uid))
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("UHQU"))?
+        .is_none()
+    {
+        // Setup id generator
+        let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+        IdInstance::init(generator_options).map_err(ise!("CIG"))?;
+
+        let new_unlock = hint_unlock::ActiveModel {
+            id:          Set(IdInstance::next_id()),
+            hint_id:     Set(hint.id),
+            user_id:     Set(uid),
+            unlock_time: Set(chrono::offset::Utc::now()),
+        };
+        new_unlock
+            .insert(conn.as_ref())
+            .await
+            .map_err(ise!("UHINU"))?;
+    }
+
+    Ok(HttpResponse::Ok().json(ReturnHint {
+        id:      hint.id,
+        flag_id: hint.flag_id,
+        cost:    hint.cost,
+        content: hint.content,
+    }))
+}
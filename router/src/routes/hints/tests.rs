@@ -0,0 +1,81 @@
+use api_error::Code;
+use chrono::{Duration, Utc};
+use migration::{Migrator, MigratorTrait};
+use router_entity::{
+    category,
+    challenge::{self, LatePolicy},
+    hint,
+    service,
+};
+use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, Set};
+
+use super::check_available;
+
+/// Create a challenge with a hint and a service that opens at `not_before`.
+async fn database(not_before: chrono::DateTime<Utc>) -> (DatabaseConnection, hint::Model) {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    challenge::ActiveModel {
+        id:           Set(1),
+        team_mode:    Set(false),
+        description:  Set(String::new()),
+        author:       Set(None),
+        difficulty:   Set(None),
+        deadline:     Set(None),
+        late_policy:  Set(LatePolicy::Reject),
+        late_penalty: Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    category::ActiveModel {
+        id:   Set(1),
+        name: Set("web".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    service::ActiveModel {
+        id:                Set(1),
+        challenge_id:      Set(1),
+        category_id:       Set(1),
+        name:              Set("web1".to_string()),
+        internal_hostname: Set("web1.challenges.svc.cluster.local".to_string()),
+        external_hostname: Set("web1".to_string()),
+        not_before:        Set(Some(not_before)),
+        not_after:         Set(None),
+        image:             Set(None),
+        port:              Set(None),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    let hint = hint::ActiveModel {
+        id:           Set(1),
+        challenge_id: Set(1),
+        flag_id:      Set(None),
+        content:      Set("Look closer".to_string()),
+        cost:         Set(10),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    (conn, hint)
+}
+
+#[tokio::test]
+async fn hides_hints_before_release() {
+    let (conn, hint) = database(Utc::now() + Duration::hours(1)).await;
+
+    let error = check_available(&conn, 1, &hint).await.unwrap();
+    assert_eq!(error.map(|e| e.code()), Some(Code::NotReleased));
+}
+
+#[tokio::test]
+async fn reveals_released_hints() {
+    let (conn, hint) = database(Utc::now() - Duration::hours(1)).await;
+
+    assert!(check_available(&conn, 1, &hint).await.unwrap().is_none());
+}
@@ -0,0 +1,39 @@
+use actix_web::{get, web, Error, HttpResponse};
+use authz::{perm, RequirePermission};
+use router_entity::incident;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Deserialize;
+
+use crate::handler_utils::ise;
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IncidentQueryParams {
+    /// Only include incidents where this user either submitted or owned the flag.
+    pub(crate) user_id: Option<i64>,
+    pub(crate) flag_id: Option<String>,
+}
+
+/// List all shared flag incidents, most recent first.
+#[get("")]
+pub(crate) async fn get_incidents(
+    _: RequirePermission<perm::ViewIncidents>,
+    params: web::Query<IncidentQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let mut query = incident::Entity::find().order_by_desc(incident::Column::IncidentTime);
+
+    if let Some(user_id) = params.user_id {
+        query = query.filter(
+            Condition::any()
+                .add(incident::Column::SubmitterId.eq(user_id))
+                .add(incident::Column::OwnerId.eq(user_id)),
+        );
+    }
+    if let Some(flag_id) = &params.flag_id {
+        query = query.filter(incident::Column::FlagId.eq(flag_id.clone()));
+    }
+
+    let incidents = query.all(conn.as_ref()).await.map_err(ise!("GIQI"))?;
+
+    Ok(HttpResponse::Ok().json(incidents))
+}
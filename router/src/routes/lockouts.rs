@@ -0,0 +1,58 @@
+use actix_web::{delete, get, web, Error, HttpRequest, HttpResponse};
+use authz::{perm, RequirePermission};
+use router_entity::lockout;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::Deserialize;
+
+use crate::{
+    audit,
+    handler_utils::{get_claims, ise},
+    rate_limit,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ResetLockoutQueryParams {
+    /// The flag to reset the lockout for. If not provided, lockouts for all flags are reset.
+    pub(crate) flag_id: Option<String>,
+}
+
+/// List all users that have failed a flag at least once since their last correct submission.
+#[get("")]
+pub(crate) async fn get_lockouts(
+    _: RequirePermission<perm::ViewLockouts>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let lockouts = lockout::Entity::find()
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GLQL"))?;
+
+    Ok(HttpResponse::Ok().json(lockouts))
+}
+
+/// Reset a user's lockouts so that they may submit flags again.
+#[delete("/{user_id}")]
+pub(crate) async fn reset_lockouts(
+    _: RequirePermission<perm::ResetLockouts>,
+    req: HttpRequest,
+    user_id: web::Path<i64>,
+    params: web::Query<ResetLockoutQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    rate_limit::clear(conn.as_ref(), *user_id, params.flag_id.as_deref())
+        .await
+        .map_err(ise!("RLCL"))?;
+
+    audit::record(
+        conn.as_ref(),
+        &get_claims(&req)?.user_id,
+        "lockout.reset",
+        *user_id,
+        None,
+        Some(serde_json::json!({ "flag_id": params.flag_id })),
+    )
+    .await
+    .map_err(ise!("RLRAL"))?;
+
+    Ok(HttpResponse::Ok().finish())
+}
@@ -1,4 +1,17 @@
+pub mod attempts;
+pub mod audit;
 pub mod challenges;
 pub mod create_service;
+pub mod errors;
 pub mod evaluation;
+pub mod feed;
 pub mod flags;
+pub mod gradebook;
+pub mod hints;
+pub mod incidents;
+pub mod lockouts;
+pub mod overrides;
+pub mod roles;
+pub mod scoreboard;
+pub mod teams;
+pub mod webhooks;
@@ -0,0 +1,165 @@
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::{perm, RequirePermission};
+use chrono::Utc;
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use router_entity::{challenge, deadline_override, service};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    EntityTrait,
+    ModelTrait,
+    QueryFilter,
+    Set,
+};
+use serde::Deserialize;
+
+use crate::{
+    audit,
+    handler_utils::{get_claims, ise},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct OverrideQueryParams {
+    pub(crate) user_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct NewOverride {
+    pub(crate) user_id:      i64,
+    /// The challenge that the override applies to. Exactly one of `challenge_id` and `service_id`
+    /// must be provided.
+    #[serde(default)]
+    pub(crate) challenge_id: Option<i64>,
+    #[serde(default)]
+    pub(crate) service_id:   Option<i64>,
+    #[serde(default)]
+    pub(crate) not_before:   Option<chrono::DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) not_after:    Option<chrono::DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) reason:       Option<String>,
+}
+
+/// List all deadline overrides, optionally for a single user.
+#[get("")]
+pub(crate) async fn get_overrides(
+    _: RequirePermission<perm::ManageOverrides>,
+    params: web::Query<OverrideQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let mut query = deadline_override::Entity::find();
+    if let Some(user_id) = params.user_id {
+        query = query.filter(deadline_override::Column::UserId.eq(user_id));
+    }
+
+    let overrides = query.all(conn.as_ref()).await.map_err(ise!("GOQO"))?;
+
+    Ok(HttpResponse::Ok().json(overrides))
+}
+
+/// Grant a user an individual window for a challenge or service.
+#[post("")]
+pub(crate) async fn create_override(
+    _: RequirePermission<perm::ManageOverrides>,
+    req: HttpRequest,
+    payload: web::Json<NewOverride>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    match (payload.challenge_id, payload.service_id) {
+        (Some(challenge_id), None) => {
+            challenge::Entity::find_by_id(challenge_id)
+                .one(conn.as_ref())
+                .await
+                .map_err(ise!("COQC"))?
+                .ok_or_else(|| ApiError::new(Code::NotFound).detail("Challenge does not exist"))?;
+        },
+        (None, Some(service_id)) => {
+            service::Entity::find_by_id(service_id)
+                .one(conn.as_ref())
+                .await
+                .map_err(ise!("COQS"))?
+                .ok_or_else(|| ApiError::new(Code::NotFound).detail("Service does not exist"))?;
+        },
+        _ => {
+            return Err(ApiError::new(Code::InvalidRequest)
+                .detail("Exactly one of challenge_id and service_id must be provided")
+                .into())
+        },
+    }
+
+    if payload.not_before.is_none() && payload.not_after.is_none() {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("At least one of not_before and not_after must be provided")
+            .into());
+    }
+    if let (Some(nbf), Some(naf)) = (payload.not_before, payload.not_after) {
+        if nbf >= naf {
+            return Err(ApiError::new(Code::InvalidRequest)
+                .detail("not_before must be before not_after")
+                .into());
+        }
+    }
+
+    // Setup id generator
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).map_err(ise!("CIG"))?;
+
+    let new_override = deadline_override::ActiveModel {
+        id:           Set(IdInstance::next_id()),
+        user_id:      Set(payload.user_id),
+        challenge_id: Set(payload.challenge_id),
+        service_id:   Set(payload.service_id),
+        not_before:   Set(payload.not_before),
+        not_after:    Set(payload.not_after),
+        reason:       Set(payload.reason.clone()),
+    };
+    let new_override = new_override
+        .insert(conn.as_ref())
+        .await
+        .map_err(ise!("COINO"))?;
+
+    audit::record(
+        conn.as_ref(),
+        &get_claims(&req)?.user_id,
+        "override.create",
+        new_override.id,
+        None,
+        Some(serde_json::to_value(&new_override).map_err(ise!("COSO"))?),
+    )
+    .await
+    .map_err(ise!("CORAL"))?;
+
+    Ok(HttpResponse::Ok().json(new_override))
+}
+
+/// Revoke a deadline override.
+#[delete("/{id}")]
+pub(crate) async fn delete_override(
+    _: RequirePermission<perm::ManageOverrides>,
+    req: HttpRequest,
+    override_id: web::Path<i64>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let existing = deadline_override::Entity::find_by_id(*override_id)
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("DOQO"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Override does not exist"))?;
+    let before = serde_json::to_value(&existing).map_err(ise!("DOSO"))?;
+    existing.delete(conn.as_ref()).await.map_err(ise!("DODO"))?;
+
+    audit::record(
+        conn.as_ref(),
+        &get_claims(&req)?.user_id,
+        "override.delete",
+        *override_id,
+        Some(before),
+        None,
+    )
+    .await
+    .map_err(ise!("DORAL"))?;
+
+    Ok(HttpResponse::Ok().finish())
+}
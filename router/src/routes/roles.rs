@@ -0,0 +1,19 @@
+use actix_web::{post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+
+use crate::{gaia_utils, handler_utils::get_claims};
+
+/// Forget the cached roles of some users. Called by gaia whenever a user's roles change.
+#[post("/invalidate")]
+pub(crate) async fn invalidate_roles(
+    req: HttpRequest,
+    ids: web::Json<Vec<String>>,
+) -> Result<HttpResponse, Error> {
+    if get_claims(&req)?.service() != Some("gaia") {
+        return Err(ApiError::new(Code::Forbidden).into());
+    }
+
+    gaia_utils::invalidate_roles(&ids);
+
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,37 @@
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    handler_utils::{self, ise},
+    scoring,
+};
+
+#[get("/scoreboard")]
+pub(crate) async fn get_scoreboard(
+    req: HttpRequest,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // Only authenticated users may view the scoreboard
+    handler_utils::get_claims(&req)?;
+
+    let scoreboard = scoring::get_scoreboard(conn.as_ref())
+        .await
+        .map_err(ise!("GSCS"))?;
+
+    Ok(HttpResponse::Ok().json(scoreboard))
+}
+
+#[get("/scoreboard/teams")]
+pub(crate) async fn get_team_scoreboard(
+    req: HttpRequest,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // Only authenticated users may view the scoreboard
+    handler_utils::get_claims(&req)?;
+
+    let scoreboard = scoring::get_team_scoreboard(conn.as_ref())
+        .await
+        .map_err(ise!("GTSCS"))?;
+
+    Ok(HttpResponse::Ok().json(scoreboard))
+}
@@ -0,0 +1,246 @@
+use actix_web::{delete, get, post, web, Error, HttpRequest, HttpResponse};
+use api_error::{ApiError, Code};
+use authz::{perm, RequirePermission};
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use router_entity::{team, team_member};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DatabaseConnection,
+    EntityTrait,
+    ModelTrait,
+    QueryFilter,
+    Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit,
+    handler_utils::{get_claims, ise},
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct NewTeam {
+    pub(crate) name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct NewTeamMember {
+    pub(crate) user_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReturnTeam {
+    pub(crate) id:      i64,
+    pub(crate) name:    String,
+    pub(crate) members: Vec<i64>,
+}
+
+#[get("")]
+pub(crate) async fn get_teams(
+    _: RequirePermission<perm::ManageTeams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let teams: Vec<ReturnTeam> = team::Entity::find()
+        .find_with_related(team_member::Entity)
+        .all(conn.as_ref())
+        .await
+        .map_err(ise!("GTQT"))?
+        .into_iter()
+        .map(|(t, members)| ReturnTeam {
+            id:      t.id,
+            name:    t.name,
+            members: members.into_iter().map(|m| m.user_id).collect(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(teams))
+}
+
+#[post("")]
+pub(crate) async fn create_team(
+    _: RequirePermission<perm::ManageTeams>,
+    req: HttpRequest,
+    payload: web::Json<NewTeam>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    if payload.name.is_empty() {
+        return Err(ApiError::new(Code::InvalidRequest)
+            .detail("Team names cannot be empty")
+            .into());
+    }
+
+    if team::Entity::find()
+        .filter(team::Column::Name.eq(payload.name.clone()))
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("CTQT"))?
+        .is_some()
+    {
+        return Err(ApiError::new(Code::AlreadyExists)
+            .detail("A team with this name already exists")
+            .into());
+    }
+
+    // Setup id generator
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).map_err(ise!("CIG"))?;
+
+    let new_team = team::ActiveModel {
+        id:   Set(IdInstance::next_id()),
+        name: Set(payload.name.clone()),
+    };
+    let txn = conn.begin().await.map_err(ise!("CTSTX"))?;
+    let new_team = new_team.insert(&txn).await.map_err(ise!("CTINT"))?;
+
+    audit::record(
+        &txn,
+        &get_claims(&req)?.user_id,
+        "team.create",
+        new_team.id,
+        None,
+        Some(serde_json::json!({ "name": new_team.name })),
+    )
+    .await
+    .map_err(ise!("CTRAL"))?;
+    txn.commit().await.map_err(ise!("CTCTX"))?;
+
+    Ok(HttpResponse::Ok().json(ReturnTeam {
+        id:      new_team.id,
+        name:    new_team.name,
+        members: vec![],
+    }))
+}
+
+#[delete("/{id}")]
+pub(crate) async fn delete_team(
+    _: RequirePermission<perm::ManageTeams>,
+    req: HttpRequest,
+    team_id: web::Path<i64>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    // The members, team and audit entry are removed and recorded together, so that a failure
+    // cannot leave a team without its members
+    let txn = conn.begin().await.map_err(ise!("DTSTX"))?;
+    let existing = team::Entity::find_by_id(*team_id)
+        .one(&txn)
+        .await
+        .map_err(ise!("DTQT"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Team does not exist"))?;
+
+    let members = crate::teams::get_member_ids(&txn, existing.id)
+        .await
+        .map_err(ise!("DTQM"))?;
+    let before = serde_json::json!({ "name": existing.name, "members": members });
+
+    team_member::Entity::delete_many()
+        .filter(team_member::Column::TeamId.eq(existing.id))
+        .exec(&txn)
+        .await
+        .map_err(ise!("DTDM"))?;
+    let team_id = existing.id;
+    existing.delete(&txn).await.map_err(ise!("DTDT"))?;
+
+    audit::record(
+        &txn,
+        &get_claims(&req)?.user_id,
+        "team.delete",
+        team_id,
+        Some(before),
+        None,
+    )
+    .await
+    .map_err(ise!("DTRAL"))?;
+    txn.commit().await.map_err(ise!("DTCTX"))?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/{id}/members")]
+pub(crate) async fn add_member(
+    _: RequirePermission<perm::ManageTeams>,
+    req: HttpRequest,
+    team_id: web::Path<i64>,
+    payload: web::Json<NewTeamMember>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    team::Entity::find_by_id(*team_id)
+        .one(conn.as_ref())
+        .await
+        .map_err(ise!("AMQT"))?
+        .ok_or_else(|| ApiError::new(Code::NotFound).detail("Team does not exist"))?;
+
+    // Users may only be a member of a single team
+    if crate::teams::get_team_id(conn.as_ref(), payload.user_id)
+        .await
+        .map_err(ise!("AMQM"))?
+        .is_some()
+    {
+        return Err(ApiError::new(Code::AlreadyExists)
+            .detail("The user is already a member of a team")
+            .into());
+    }
+
+    // Setup id generator
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).map_err(ise!("CIG"))?;
+
+    let new_member = team_member::ActiveModel {
+        id:      Set(IdInstance::next_id()),
+        team_id: Set(*team_id),
+        user_id: Set(payload.user_id),
+    };
+    new_member
+        .insert(conn.as_ref())
+        .await
+        .map_err(ise!("AMINM"))?;
+
+    audit::record(
+        conn.as_ref(),
+        &get_claims(&req)?.user_id,
+        "team.member.add",
+        *team_id,
+        None,
+        Some(serde_json::json!({ "user_id": payload.user_id })),
+    )
+    .await
+    .map_err(ise!("AMRAL"))?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/{id}/members/{user_id}")]
+pub(crate) async fn remove_member(
+    _: RequirePermission<perm::ManageTeams>,
+    req: HttpRequest,
+    path: web::Path<(i64, i64)>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let (team_id, user_id) = path.into_inner();
+    let result = team_member::Entity::delete_many()
+        .filter(team_member::Column::TeamId.eq(team_id))
+        .filter(team_member::Column::UserId.eq(user_id))
+        .exec(conn.as_ref())
+        .await
+        .map_err(ise!("RMDM"))?;
+
+    if result.rows_affected == 0 {
+        return Err(ApiError::new(Code::NotFound)
+            .detail("The user is not a member of this team")
+            .into());
+    }
+
+    audit::record(
+        conn.as_ref(),
+        &get_claims(&req)?.user_id,
+        "team.member.remove",
+        team_id,
+        Some(serde_json::json!({ "user_id": user_id })),
+        None,
+    )
+    .await
+    .map_err(ise!("RMRAL"))?;
+
+    Ok(HttpResponse::Ok().finish())
+}
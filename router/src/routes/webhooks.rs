@@ -0,0 +1,41 @@
+use actix_web::{get, web, Error, HttpResponse};
+use authz::RequireAdmin;
+use router_entity::webhook_delivery;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Deserialize;
+
+use crate::handler_utils::ise;
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct DeliveryQueryParams {
+    pub(crate) event:  Option<String>,
+    /// Only include deliveries that ran out of retries.
+    #[serde(default)]
+    pub(crate) failed: bool,
+    /// The maximum number of deliveries to return. Defaults to 100.
+    pub(crate) limit:  Option<u64>,
+}
+
+/// List webhook deliveries, most recent first. Webhook URLs often contain credentials, so only
+/// admins may view them.
+#[get("/deliveries")]
+pub(crate) async fn get_deliveries(
+    _: RequireAdmin,
+    params: web::Query<DeliveryQueryParams>,
+    conn: web::Data<DatabaseConnection>,
+) -> Result<HttpResponse, Error> {
+    let mut query = webhook_delivery::Entity::find()
+        .order_by_desc(webhook_delivery::Column::StartedTime)
+        .limit(params.limit.unwrap_or(100));
+
+    if let Some(event) = &params.event {
+        query = query.filter(webhook_delivery::Column::Event.eq(event.clone()));
+    }
+    if params.failed {
+        query = query.filter(webhook_delivery::Column::Success.eq(false));
+    }
+
+    let deliveries = query.all(conn.as_ref()).await.map_err(ise!("GWDQD"))?;
+
+    Ok(HttpResponse::Ok().json(deliveries))
+}
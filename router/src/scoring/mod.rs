@@ -0,0 +1,312 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::Utc;
+use router_entity::{
+    flag::{self, ScoringMode},
+    hint,
+    hint_unlock,
+    submission,
+    team,
+    team_member,
+};
+use sea_orm::{sea_query::Expr, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, QuerySelect};
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, FromQueryResult)]
+struct SolveCount {
+    flag_id: String,
+    solves:  i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ScoreboardEntry {
+    pub(crate) user_id:         i64,
+    pub(crate) score:           i64,
+    /// The time of the user's most recent submission, used to break ties.
+    pub(crate) last_submission: chrono::DateTime<Utc>,
+    pub(crate) bloods:          Bloods,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TeamScoreboardEntry {
+    pub(crate) team_id:         i64,
+    pub(crate) name:            String,
+    pub(crate) score:           i64,
+    /// The time of the team's most recent submission, used to break ties.
+    pub(crate) last_submission: chrono::DateTime<Utc>,
+    pub(crate) bloods:          Bloods,
+}
+
+/// The number of flags that were solved first, second or third.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Bloods {
+    pub(crate) first:  i64,
+    pub(crate) second: i64,
+    pub(crate) third:  i64,
+}
+
+impl Bloods {
+    fn record(&mut self, blood: Option<i32>) {
+        match blood {
+            Some(1) => self.first += 1,
+            Some(2) => self.second += 1,
+            Some(3) => self.third += 1,
+            _ => {},
+        }
+    }
+}
+
+/// Get the number of users that have solved each flag. Flags without any submissions are omitted.
+pub(crate) async fn get_solve_counts<C>(conn: &C) -> Result<HashMap<String, i64>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(submission::Entity::find()
+        .select_only()
+        .column(submission::Column::FlagId)
+        .column_as(Expr::col(submission::Column::Id).count(), "solves")
+        .group_by(submission::Column::FlagId)
+        .into_model::<SolveCount>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|c| (c.flag_id, c.solves))
+        .collect())
+}
+
+/// Get the total cost of the hints that each user has unlocked. Users that have not unlocked any
+/// hints are omitted.
+pub(crate) async fn get_hint_costs<C>(conn: &C) -> Result<HashMap<i64, i64>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut costs: HashMap<i64, i64> = HashMap::new();
+    for (unlock, hint) in hint_unlock::Entity::find()
+        .find_also_related(hint::Entity)
+        .all(conn)
+        .await?
+    {
+        if let Some(hint) = hint {
+            *costs.entry(unlock.user_id).or_insert(0) += i64::from(hint.cost);
+        }
+    }
+
+    Ok(costs)
+}
+
+/// Get the ids of the current members of every team.
+pub(crate) async fn get_team_members<C>(conn: &C) -> Result<HashMap<i64, Vec<i64>>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut members: HashMap<i64, Vec<i64>> = HashMap::new();
+    for m in team_member::Entity::find().all(conn).await? {
+        members.entry(m.team_id).or_default().push(m.user_id);
+    }
+
+    Ok(members)
+}
+
+/// Get the users that a submission is credited to. A submission made in team mode is credited in
+/// full to every current member of its team, and to its submitter if they have since left; any
+/// other submission is credited to its submitter alone. The gradebook and both scoreboards follow
+/// this rule.
+pub(crate) fn credited_users(
+    sub: &submission::Model,
+    team_members: &HashMap<i64, Vec<i64>>,
+) -> Vec<i64> {
+    let mut users = sub
+        .team_id
+        .and_then(|t| team_members.get(&t).cloned())
+        .unwrap_or_default();
+    if !users.contains(&sub.user_id) {
+        users.push(sub.user_id);
+    }
+
+    users
+}
+
+/// Calculate how many points a flag is currently worth given the number of users that have solved
+/// it.
+///
+/// Decaying flags follow the same curve as the dynamic scoring used by `CTFd`: the value drops
+/// quadratically from `points` and reaches `minimum_points` after `decay` solves. The first solve
+/// does not reduce the value of the flag.
+pub(crate) fn flag_value(flag: &flag::Model, solves: i64) -> i32 {
+    let (minimum, decay) = match (&flag.scoring_mode, flag.minimum_points, flag.decay) {
+        (ScoringMode::Decay, Some(minimum), Some(decay)) if decay > 0 => (minimum, decay),
+        _ => return flag.points,
+    };
+
+    let initial = f64::from(flag.points);
+    let minimum_f = f64::from(minimum);
+    #[allow(clippy::cast_precision_loss)]
+    let solves = (solves - 1).max(0) as f64;
+
+    let value = ((minimum_f - initial) / f64::from(decay).powi(2)) * solves.powi(2) + initial;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let value = value.ceil() as i32;
+
+    value.max(minimum)
+}
+
+/// Deduct a late penalty, as a percentage, from a flag's value.
+pub(crate) fn apply_penalty(value: i32, penalty: i32) -> i64 {
+    i64::from(value) * i64::from(100 - penalty.clamp(0, 100)) / 100
+}
+
+/// Get the bonus awarded for being the first, second or third to solve a flag.
+pub(crate) fn blood_bonus(flag: &flag::Model, blood: Option<i32>) -> i32 {
+    match blood {
+        Some(1) => flag.first_blood_bonus,
+        Some(2) => flag.second_blood_bonus,
+        Some(3) => flag.third_blood_bonus,
+        _ => 0,
+    }
+}
+
+/// Calculate how many points a submission is worth given the current value of its flag. Blood
+/// bonuses are added before any late penalty is deducted.
+pub(crate) fn submission_points(flag: &flag::Model, value: i32, sub: &submission::Model) -> i64 {
+    apply_penalty(value + blood_bonus(flag, sub.blood), sub.late_penalty)
+}
+
+/// Calculate the total score of every user that has been credited with at least one flag, as given
+/// by [`credited_users`], ordered from highest to lowest. The cost of any hints a user has unlocked
+/// is deducted from their score. Users with the same score are ordered by who reached it first.
+pub(crate) async fn get_scoreboard<C>(conn: &C) -> Result<Vec<ScoreboardEntry>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let solve_counts = get_solve_counts(conn).await?;
+
+    let flags: HashMap<String, (flag::Model, i32)> = flag::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|f| {
+            let value = flag_value(&f, *solve_counts.get(&f.id).unwrap_or(&0));
+            (f.id.clone(), (f, value))
+        })
+        .collect();
+
+    let team_members = get_team_members(conn).await?;
+
+    let mut scores: HashMap<i64, ScoreboardEntry> = HashMap::new();
+    for sub in submission::Entity::find().all(conn).await? {
+        let value = match flags.get(&sub.flag_id) {
+            Some((flag, value)) => submission_points(flag, *value, &sub),
+            None => 0,
+        };
+
+        for user_id in credited_users(&sub, &team_members) {
+            let entry = scores.entry(user_id).or_insert(ScoreboardEntry {
+                user_id,
+                score: 0,
+                last_submission: sub.submission_time,
+                bloods: Bloods::default(),
+            });
+
+            entry.score += value;
+            entry.bloods.record(sub.blood);
+            if sub.submission_time > entry.last_submission {
+                entry.last_submission = sub.submission_time;
+            }
+        }
+    }
+
+    for (user_id, cost) in get_hint_costs(conn).await? {
+        if let Some(entry) = scores.get_mut(&user_id) {
+            entry.score -= cost;
+        }
+    }
+
+    let mut scoreboard: Vec<ScoreboardEntry> = scores.into_values().collect();
+    scoreboard.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.last_submission.cmp(&b.last_submission))
+    });
+
+    Ok(scoreboard)
+}
+
+/// Calculate the total score of every team that has submitted at least one flag, ordered from
+/// highest to lowest. Each submission counts once towards every team with a member it is credited
+/// to, as given by [`credited_users`], so a team mode flag counts once however many members it is
+/// credited to. The cost of any hints the team's members have unlocked is deducted from its score.
+pub(crate) async fn get_team_scoreboard<C>(conn: &C) -> Result<Vec<TeamScoreboardEntry>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let solve_counts = get_solve_counts(conn).await?;
+
+    let flags: HashMap<String, (flag::Model, i32)> = flag::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|f| {
+            let value = flag_value(&f, *solve_counts.get(&f.id).unwrap_or(&0));
+            (f.id.clone(), (f, value))
+        })
+        .collect();
+
+    let team_members = get_team_members(conn).await?;
+    let memberships: HashMap<i64, i64> = team_members
+        .iter()
+        .flat_map(|(team_id, users)| users.iter().map(|u| (*u, *team_id)))
+        .collect();
+    let team_names: HashMap<i64, String> = team::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t.name))
+        .collect();
+
+    let mut scores: HashMap<i64, TeamScoreboardEntry> = HashMap::new();
+    for sub in submission::Entity::find().all(conn).await? {
+        let value = match flags.get(&sub.flag_id) {
+            Some((flag, value)) => submission_points(flag, *value, &sub),
+            None => 0,
+        };
+        let teams: BTreeSet<i64> = credited_users(&sub, &team_members)
+            .iter()
+            .filter_map(|u| memberships.get(u).copied())
+            .collect();
+
+        for team_id in teams {
+            let entry = scores.entry(team_id).or_insert(TeamScoreboardEntry {
+                team_id,
+                name: team_names.get(&team_id).cloned().unwrap_or_default(),
+                score: 0,
+                last_submission: sub.submission_time,
+                bloods: Bloods::default(),
+            });
+
+            entry.score += value;
+            entry.bloods.record(sub.blood);
+            if sub.submission_time > entry.last_submission {
+                entry.last_submission = sub.submission_time;
+            }
+        }
+    }
+
+    for (user_id, cost) in get_hint_costs(conn).await? {
+        if let Some(entry) = memberships.get(&user_id).and_then(|t| scores.get_mut(t)) {
+            entry.score -= cost;
+        }
+    }
+
+    let mut scoreboard: Vec<TeamScoreboardEntry> = scores.into_values().collect();
+    scoreboard.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.last_submission.cmp(&b.last_submission))
+    });
+
+    Ok(scoreboard)
+}
@@ -0,0 +1,203 @@
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use router_entity::{
+    category,
+    challenge::{self, LatePolicy},
+    flag::{self, FlagType, ScoringMode},
+    submission,
+    team,
+    team_member,
+    user,
+};
+use sea_orm::{ActiveModelTrait, Database, IntoActiveModel, Set};
+
+use super::{apply_penalty, flag_value, get_scoreboard, get_team_scoreboard, submission_points};
+use crate::gradebook::{self, GroupBy};
+
+fn decaying_flag(points: i32, minimum: i32, decay: i32) -> flag::Model {
+    flag::Model {
+        id: "test".to_string(),
+        challenge_id: 1,
+        category_id: 1,
+        flag: "test".to_string(),
+        flag_type: FlagType::Static,
+        points,
+        display_name: "Test".to_string(),
+        scoring_mode: ScoringMode::Decay,
+        minimum_points: Some(minimum),
+        decay: Some(decay),
+        deadline: None,
+        late_policy: LatePolicy::Reject,
+        late_penalty: 0,
+        first_blood_bonus: 0,
+        second_blood_bonus: 0,
+        third_blood_bonus: 0,
+    }
+}
+
+#[test]
+fn static_flags_do_not_decay() {
+    let mut f = decaying_flag(500, 100, 10);
+    f.scoring_mode = ScoringMode::Static;
+
+    assert_eq!(flag_value(&f, 0), 500);
+    assert_eq!(flag_value(&f, 50), 500);
+}
+
+#[test]
+fn first_solve_is_worth_full_points() {
+    let f = decaying_flag(500, 100, 10);
+
+    assert_eq!(flag_value(&f, 0), 500);
+    assert_eq!(flag_value(&f, 1), 500);
+}
+
+#[test]
+fn decays_to_minimum() {
+    let f = decaying_flag(500, 100, 10);
+
+    assert_eq!(flag_value(&f, 6), 400);
+    assert_eq!(flag_value(&f, 11), 100);
+    assert_eq!(flag_value(&f, 100), 100);
+}
+
+#[test]
+fn late_penalty_is_a_percentage() {
+    assert_eq!(apply_penalty(500, 0), 500);
+    assert_eq!(apply_penalty(500, 20), 400);
+    assert_eq!(apply_penalty(500, 100), 0);
+    assert_eq!(apply_penalty(99, 50), 49);
+}
+
+#[test]
+fn blood_bonus_is_penalised_with_the_flag() {
+    let mut f = decaying_flag(500, 100, 10);
+    f.first_blood_bonus = 100;
+    f.second_blood_bonus = 50;
+
+    let mut sub = submission::Model {
+        id:              1,
+        user_id:         1,
+        flag_id:         f.id.clone(),
+        submission_time: Utc::now(),
+        team_id:         None,
+        late_seconds:    None,
+        late_penalty:    0,
+        blood:           Some(1),
+    };
+    assert_eq!(submission_points(&f, 500, &sub), 600);
+
+    sub.blood = Some(3);
+    assert_eq!(submission_points(&f, 500, &sub), 500);
+
+    sub.blood = Some(2);
+    sub.late_penalty = 50;
+    assert_eq!(submission_points(&f, 500, &sub), 275);
+}
+
+#[tokio::test]
+async fn team_solves_are_credited_consistently() {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::fresh(&conn).await.unwrap();
+
+    challenge::ActiveModel {
+        id:           Set(1),
+        team_mode:    Set(true),
+        description:  Set(String::new()),
+        author:       Set(None),
+        difficulty:   Set(None),
+        deadline:     Set(None),
+        late_policy:  Set(LatePolicy::Reject),
+        late_penalty: Set(0),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    category::ActiveModel {
+        id:   Set(1),
+        name: Set("web".to_string()),
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+    for (id, points) in [("team", 100), ("solo", 50)] {
+        let mut f = decaying_flag(points, points, 10);
+        f.id = id.to_string();
+        f.flag = id.to_string();
+        f.into_active_model().insert(&conn).await.unwrap();
+    }
+    for id in 1..=3 {
+        user::ActiveModel { id: Set(id) }
+            .insert(&conn)
+            .await
+            .unwrap();
+    }
+    for (id, name) in [(1, "red"), (2, "blue")] {
+        team::ActiveModel {
+            id:   Set(id),
+            name: Set(name.to_string()),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    for (user_id, team_id) in [(1, 1), (2, 1), (3, 2)] {
+        team_member::ActiveModel {
+            id:      Set(user_id),
+            team_id: Set(team_id),
+            user_id: Set(user_id),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+    // User 1 solves the team flag for their team, and users 2 and 3 solve the other flag alone
+    for (id, user_id, flag_id, team_id) in [
+        (1, 1, "team", Some(1)),
+        (2, 2, "solo", None),
+        (3, 3, "solo", None),
+    ] {
+        submission::ActiveModel {
+            id:              Set(id),
+            user_id:         Set(user_id),
+            flag_id:         Set(flag_id.to_string()),
+            submission_time: Set(Utc::now()),
+            team_id:         Set(team_id),
+            late_seconds:    Set(None),
+            late_penalty:    Set(0),
+            blood:           Set(None),
+        }
+        .insert(&conn)
+        .await
+        .unwrap();
+    }
+
+    let gradebook = gradebook::build(&conn, &[], GroupBy::Category)
+        .await
+        .unwrap();
+    let totals: Vec<_> = gradebook
+        .students
+        .iter()
+        .map(|s| (s.user_id, s.total))
+        .collect();
+    assert_eq!(totals, [(1, 100), (2, 150), (3, 50)]);
+
+    // Each student's scoreboard score matches their mark
+    let mut scores: Vec<_> = get_scoreboard(&conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|u| (u.user_id, u.score))
+        .collect();
+    scores.sort_unstable();
+    assert_eq!(scores, totals);
+
+    // The team flag counts once towards the team, even though it is credited to both members
+    let scoreboard: Vec<_> = get_team_scoreboard(&conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| (t.team_id, t.score))
+        .collect();
+    assert_eq!(scoreboard, [(1, 150), (2, 50)]);
+}
@@ -0,0 +1,62 @@
+use router_entity::{challenge, team_member};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+
+/// Get the id of the team that a user is a member of.
+pub(crate) async fn get_team_id<C>(conn: &C, user_id: i64) -> Result<Option<i64>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(team_member::Entity::find()
+        .filter(team_member::Column::UserId.eq(user_id))
+        .one(conn)
+        .await?
+        .map(|m| m.team_id))
+}
+
+/// Get the ids of all users that are members of a team.
+pub(crate) async fn get_member_ids<C>(conn: &C, team_id: i64) -> Result<Vec<i64>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(team_member::Entity::find()
+        .filter(team_member::Column::TeamId.eq(team_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|m| m.user_id)
+        .collect())
+}
+
+/// Determine which team a user solves the flags of a challenge as. Returns `None` if the challenge
+/// is not in team mode or the user is not a member of a team.
+pub(crate) async fn get_solving_team<C>(
+    conn: &C,
+    challenge_id: i64,
+    user_id: i64,
+) -> Result<Option<i64>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let team_mode = challenge::Entity::find_by_id(challenge_id)
+        .one(conn)
+        .await?
+        .is_some_and(|c| c.team_mode);
+
+    if !team_mode {
+        return Ok(None);
+    }
+
+    get_team_id(conn, user_id).await
+}
+
+/// The identity that dynamic flags are bound to when they are generated for a team.
+pub(crate) fn team_identity(team_id: i64) -> String { format!("_scpT{team_id}@unsw.scp.platform") }
+
+/// Parse the id of a team from its identity.
+pub(crate) fn parse_team_identity(identity: &str) -> Option<i64> {
+    identity
+        .strip_prefix("_scpT")?
+        .strip_suffix("@unsw.scp.platform")?
+        .parse::<i64>()
+        .ok()
+}
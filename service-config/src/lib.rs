@@ -0,0 +1,400 @@
+#![warn(clippy::pedantic)]
+
+//! Typed service configuration, validated once at startup.
+//!
+//! Each service describes its configuration as a struct that is built through a [`Loader`]. Every
+//! key is looked up, in order, from:
+//!
+//! 1. the environment variable of the same name, e.g. `DB_URI`,
+//! 2. the file named by the same variable with a `_FILE` suffix, e.g.
+//!    `HMAC_KEY_FILE=/run/secrets/hmac_key`, with trailing whitespace removed,
+//! 3. the TOML file named by `CONFIG_FILE`, under the lower case key, e.g. `db_uri = "..."`,
+//! 4. the default of the key, if it has one.
+//!
+//! Secret keys are checked for strength with [`Loader::key`]. Outside of development mode, set with
+//! `DEV_MODE=true`, example keys and keys made of a repeating pattern are refused.
+//!
+//! Problems are collected instead of raised, so that [`load`] can report every one of them before
+//! the service starts. Running a service with `--print-config` prints the resolved configuration
+//! with secrets redacted, then exits.
+
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{self, Display},
+    fs,
+    process,
+    str::FromStr,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The command line flag that prints the configuration instead of starting the service.
+pub const PRINT_CONFIG_FLAG: &str = "--print-config";
+
+/// Keys that have been published as examples, e.g. in `docker-compose.yml`.
+const EXAMPLE_KEYS: &[&str] = &["12345679801234567980123456798012"];
+
+/// The length that a secret key must have, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLength {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+/// Where a configuration value was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Env,
+    /// A file named by a `*_FILE` variable.
+    File,
+    /// The TOML file named by `CONFIG_FILE`.
+    ConfigFile,
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Env => "env",
+            Source::File => "file",
+            Source::ConfigFile => "config file",
+            Source::Default => "default",
+        })
+    }
+}
+
+/// A key that was read, as shown by `--print-config`.
+#[derive(Debug, Clone)]
+struct Entry {
+    key:    String,
+    /// The raw value and where it came from. `None` if the key is unset.
+    value:  Option<(String, Source)>,
+    secret: bool,
+}
+
+type EnvFn = Box<dyn Fn(&str) -> Option<String>>;
+
+/// Reads configuration keys, recording every value and problem along the way.
+pub struct Loader {
+    env:      EnvFn,
+    file:     HashMap<String, String>,
+    entries:  Vec<Entry>,
+    errors:   Vec<String>,
+    dev_mode: Option<bool>,
+}
+
+impl Loader {
+    /// A loader reading from the process environment and the TOML file named by `CONFIG_FILE`.
+    #[must_use]
+    pub fn from_env() -> Self { Self::new(|key| env::var(key).ok()) }
+
+    /// A loader reading variables through `env`, and the TOML file named by its `CONFIG_FILE`.
+    pub fn new(env: impl Fn(&str) -> Option<String> + 'static) -> Self {
+        let mut loader = Self {
+            env:      Box::new(env),
+            file:     HashMap::new(),
+            entries:  Vec::new(),
+            errors:   Vec::new(),
+            dev_mode: None,
+        };
+
+        if let Some(path) = (loader.env)("CONFIG_FILE").filter(|p| !p.is_empty()) {
+            match fs::read_to_string(&path) {
+                Ok(contents) => loader.parse_file(&path, &contents),
+                Err(e) => loader.error(format!("CONFIG_FILE: could not read {path}: {e}")),
+            }
+        }
+
+        loader
+    }
+
+    fn parse_file(&mut self, path: &str, contents: &str) {
+        let table = match contents.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => return self.error(format!("{path}: expected a table")),
+            Err(e) => return self.error(format!("{path}: {e}")),
+        };
+
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::Array(items) => items
+                    .into_iter()
+                    .map(scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|items| items.join(",")),
+                other => scalar(other),
+            };
+            let Some(value) = value else {
+                self.error(format!(
+                    "{path}: `{key}` must be a plain value or a list of them"
+                ));
+                continue;
+            };
+            self.file.insert(key.to_lowercase(), value);
+        }
+    }
+
+    fn error(&mut self, error: String) { self.errors.push(error); }
+
+    /// Look up the raw value of a key. `Err` if the lookup itself failed and was recorded.
+    fn lookup(&mut self, key: &str) -> Result<Option<(String, Source)>, ()> {
+        let file_key = format!("{key}_FILE");
+        match ((self.env)(key), (self.env)(&file_key)) {
+            (Some(_), Some(_)) => {
+                self.error(format!("{key} and {file_key} are both set"));
+                Err(())
+            },
+            (Some(value), None) => Ok(Some((value, Source::Env))),
+            (None, Some(path)) => match fs::read_to_string(&path) {
+                Ok(value) => Ok(Some((value.trim_end().to_string(), Source::File))),
+                Err(e) => {
+                    self.error(format!("{file_key}: could not read {path}: {e}"));
+                    Err(())
+                },
+            },
+            (None, None) => Ok(self
+                .file
+                .get(&key.to_lowercase())
+                .map(|v| (v.clone(), Source::ConfigFile))),
+        }
+    }
+
+    /// Read and parse a key, falling back to `default` if it is unset. Keys without a default are
+    /// required and may not be empty. Returns `None` if the key could not be read or parsed, or is
+    /// missing.
+    fn read<T, E: Display>(
+        &mut self,
+        key: &str,
+        default: Option<String>,
+        secret: bool,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> Option<T> {
+        let Ok(value) = self.lookup(key) else {
+            self.entries.push(Entry {
+                key: key.to_string(),
+                value: None,
+                secret,
+            });
+            return None;
+        };
+        let required = default.is_none();
+        let value = value.or_else(|| default.map(|d| (d, Source::Default)));
+        self.entries.push(Entry {
+            key: key.to_string(),
+            value: value.clone(),
+            secret,
+        });
+
+        let Some((raw, source)) = value.filter(|(v, _)| !(required && v.is_empty())) else {
+            self.error(format!("{key} is required"));
+            return None;
+        };
+        match parse(&raw) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.error(format!("{key}: {e} (from {source})"));
+                None
+            },
+        }
+    }
+
+    /// A key with a default.
+    pub fn get<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr + ToString,
+        T::Err: Display,
+    {
+        self.read(key, Some(default.to_string()), false, str::parse)
+            .unwrap_or(default)
+    }
+
+    /// A key that must be set.
+    pub fn required<T>(&mut self, key: &str) -> T
+    where
+        T: FromStr + Default,
+        T::Err: Display,
+    {
+        self.read(key, None, false, str::parse).unwrap_or_default()
+    }
+
+    /// A key that must be set and whose value is redacted when printed.
+    pub fn secret(&mut self, key: &str) -> String {
+        self.read(key, None, true, |v| Ok::<_, String>(v.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// A key with a default whose value is redacted when printed.
+    pub fn secret_or(&mut self, key: &str, default: &str) -> String {
+        self.read(key, Some(default.to_string()), true, |v| {
+            Ok::<_, String>(v.to_string())
+        })
+        .unwrap_or_default()
+    }
+
+    /// Whether the service runs in development mode, set through `DEV_MODE`.
+    pub fn dev_mode(&mut self) -> bool {
+        if let Some(dev_mode) = self.dev_mode {
+            return dev_mode;
+        }
+        let dev_mode = self.get("DEV_MODE", false);
+        self.dev_mode = Some(dev_mode);
+        dev_mode
+    }
+
+    /// A secret key that must be set. See [`Loader::check_key`].
+    pub fn key(&mut self, key: &str, length: KeyLength) -> String {
+        let value = self.secret(key);
+        if !value.is_empty() {
+            self.check_key(key, &value, length);
+        }
+        value
+    }
+
+    /// Check that the value of a secret key has the right length and, outside of development
+    /// mode, is not an example key or a repeating pattern.
+    pub fn check_key(&mut self, key: &str, value: &str, length: KeyLength) {
+        let len = value.len();
+        match length {
+            KeyLength::Exactly(n) if len != n => {
+                self.error(format!("{key} must be exactly {n} bytes long, not {len}"));
+            },
+            KeyLength::AtLeast(n) if len < n => {
+                self.error(format!("{key} must be at least {n} bytes long, not {len}"));
+            },
+            _ => {},
+        }
+
+        if is_weak(value) && !self.dev_mode() {
+            self.error(format!(
+                "{key} is an example key or a repeating pattern. Generate a random key, e.g. with \
+                 `openssl rand -hex 16`, or set DEV_MODE=true"
+            ));
+        }
+    }
+
+    /// A key with a default, parsed by `parse`.
+    pub fn parse_with<T, E>(
+        &mut self,
+        key: &str,
+        default: &str,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> T
+    where
+        T: Default,
+        E: Display,
+    {
+        self.read(key, Some(default.to_string()), false, parse)
+            .unwrap_or_default()
+    }
+
+    /// A comma separated list, empty by default. In the config file it may also be an array.
+    pub fn list(&mut self, key: &str) -> Vec<String> {
+        self.parse_with(key, "", |v| {
+            Ok::<_, String>(
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )
+        })
+    }
+
+    /// The path of a file, which is read eagerly. Returns the contents of the file.
+    pub fn file_contents(&mut self, key: &str, default_path: &str) -> String {
+        self.parse_with(key, default_path, |path| {
+            fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))
+        })
+    }
+
+    /// Record a problem with the configuration that is not tied to reading a single key.
+    pub fn invalid(&mut self, problem: impl Into<String>) { self.errors.push(problem.into()); }
+
+    /// Every problem found so far, including keys in the config file that were never read.
+    #[must_use]
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = self.errors.clone();
+        let mut unknown: Vec<_> = self
+            .file
+            .keys()
+            .filter(|k| !self.entries.iter().any(|e| e.key.to_lowercase() == **k))
+            .collect();
+        unknown.sort();
+        errors.extend(
+            unknown
+                .into_iter()
+                .map(|k| format!("CONFIG_FILE: unknown key `{k}`")),
+        );
+        errors
+    }
+
+    /// The configuration that was read, as a TOML document with secrets redacted.
+    #[must_use]
+    pub fn report(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let key = entry.key.to_lowercase();
+                match &entry.value {
+                    None => format!("# {key} is not set\n"),
+                    Some((_, source)) if entry.secret => {
+                        format!("{key} = \"<redacted>\" # {source}\n")
+                    },
+                    Some((value, source)) => format!(
+                        "{key} = {} # {source}\n",
+                        toml::Value::String(value.clone())
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether a key is a published example, or repeats a pattern no longer than half of the key.
+fn is_weak(key: &str) -> bool {
+    let bytes = key.as_bytes();
+    EXAMPLE_KEYS.contains(&key)
+        || (1..=bytes.len() / 2)
+            .any(|period| bytes[period..].iter().zip(bytes).all(|(a, b)| a == b))
+}
+
+fn scalar(value: toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Build a service's configuration from the environment, for use at the top of `main`.
+///
+/// If the service was started with `--print-config`, the configuration and any problems with it
+/// are printed and the process exits. Otherwise, if there are any problems, every one of them is
+/// printed and the process exits with an error.
+pub fn load<C>(build: impl FnOnce(&mut Loader) -> C) -> C {
+    let mut loader = Loader::from_env();
+    let config = build(&mut loader);
+    let errors = loader.errors();
+
+    if env::args().skip(1).any(|a| a == PRINT_CONFIG_FLAG) {
+        print!("{}", loader.report());
+        for error in &errors {
+            eprintln!("error: {error}");
+        }
+        process::exit(i32::from(!errors.is_empty()));
+    }
+
+    if !errors.is_empty() {
+        eprintln!("invalid configuration:");
+        for error in &errors {
+            eprintln!("  - {error}");
+        }
+        process::exit(1);
+    }
+
+    config
+}
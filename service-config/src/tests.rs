@@ -0,0 +1,122 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{KeyLength, Loader};
+
+fn with_vars(vars: &[(&str, &str)]) -> Loader {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Loader::new(move |key| vars.get(key).cloned())
+}
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("service-config-{}-{name}", std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn reads_env_and_defaults() {
+    let mut loader = with_vars(&[("PORT", "8080"), ("NAME", "router")]);
+    assert_eq!(loader.get("PORT", 1_u16), 8080);
+    assert_eq!(loader.get("MISSING", 5_u16), 5);
+    assert_eq!(loader.required::<String>("NAME"), "router");
+    assert!(loader.errors().is_empty());
+}
+
+#[test]
+fn collects_every_problem() {
+    let mut loader = with_vars(&[("PORT", "eighty"), ("EMPTY", "")]);
+    assert_eq!(loader.get("PORT", 1_u16), 1);
+    loader.required::<String>("SMTP_ADDR");
+    loader.secret("EMPTY");
+    loader.parse_with("MODE", "fast", |v| match v {
+        "slow" => Ok(()),
+        _ => Err("must be slow"),
+    });
+
+    let errors = loader.errors();
+    assert_eq!(errors.len(), 4, "{errors:?}");
+    assert!(errors[0].starts_with("PORT: "));
+    assert_eq!(errors[1], "SMTP_ADDR is required");
+    assert_eq!(errors[2], "EMPTY is required");
+    assert_eq!(errors[3], "MODE: must be slow (from default)");
+}
+
+#[test]
+fn reads_secret_files() {
+    let path = temp_file("secret", "hunter2\n");
+    let path = path.to_str().unwrap();
+
+    let mut loader = with_vars(&[("KEY_FILE", path)]);
+    assert_eq!(loader.secret("KEY"), "hunter2");
+    assert!(loader.errors().is_empty());
+    assert!(loader.report().contains("key = \"<redacted>\" # file"));
+    assert!(!loader.report().contains("hunter2"));
+
+    let mut loader = with_vars(&[("KEY", "a"), ("KEY_FILE", path)]);
+    loader.secret("KEY");
+    assert_eq!(loader.errors(), ["KEY and KEY_FILE are both set"]);
+
+    let mut loader = with_vars(&[("KEY_FILE", "/nonexistent/secret")]);
+    loader.secret("KEY");
+    assert_eq!(loader.errors().len(), 1);
+}
+
+#[test]
+fn reads_config_file() {
+    let path = temp_file(
+        "config.toml",
+        "db_uri = \"sqlite://./test.db\"\nttl = 30\nurls = [\"a\", \"b\"]\ntypo = 1\n",
+    );
+
+    let mut loader = with_vars(&[("CONFIG_FILE", path.to_str().unwrap()), ("TTL", "10")]);
+    assert_eq!(loader.get("DB_URI", String::new()), "sqlite://./test.db");
+    assert_eq!(loader.get("TTL", 60_u64), 10);
+    assert_eq!(loader.list("URLS"), ["a", "b"]);
+    assert_eq!(loader.errors(), ["CONFIG_FILE: unknown key `typo`"]);
+
+    let report = loader.report();
+    assert!(report.contains("db_uri = \"sqlite://./test.db\" # config file"));
+    assert!(report.contains("ttl = \"10\" # env"));
+}
+
+#[test]
+fn checks_key_strength() {
+    let mut loader = with_vars(&[]);
+    loader.check_key(
+        "A",
+        "0123456789abcdef0123456789abcdeX",
+        KeyLength::Exactly(32),
+    );
+    loader.check_key("B", "too short", KeyLength::AtLeast(32));
+    loader.check_key(
+        "C",
+        "12345679801234567980123456798012",
+        KeyLength::Exactly(32),
+    );
+    loader.check_key(
+        "D",
+        "abcdabcdabcdabcdabcdabcdabcdabcdab",
+        KeyLength::AtLeast(32),
+    );
+    assert_eq!(loader.errors().len(), 3);
+    assert!(loader.errors()[0].starts_with("B must be at least 32 bytes long"));
+    assert!(loader.errors()[1].starts_with("C is an example key"));
+    assert!(loader.errors()[2].starts_with("D is an example key"));
+
+    let mut loader = with_vars(&[
+        ("DEV_MODE", "true"),
+        ("KEY", "12345679801234567980123456798012"),
+    ]);
+    loader.key("KEY", KeyLength::Exactly(32));
+    assert!(loader.errors().is_empty());
+
+    let mut loader = with_vars(&[("DEV_MODE", "true"), ("KEY", "short")]);
+    loader.key("KEY", KeyLength::Exactly(32));
+    assert_eq!(
+        loader.errors(),
+        ["KEY must be exactly 32 bytes long, not 5"]
+    );
+}
@@ -0,0 +1,283 @@
+#![warn(clippy::pedantic)]
+
+//! Signed outgoing webhooks for platform events.
+//!
+//! Each delivery is a JSON [`Payload`] posted to every configured endpoint that subscribes to the
+//! event. Payloads carry both a `content` and a `text` field so that Discord and Slack webhook URLs
+//! can be used directly. Requests are signed with HMAC-SHA256 over `{timestamp}.{body}` and the
+//! signature is sent in the `X-Scp-Signature` header as `t={timestamp},v1={hex digest}`. Each
+//! delivery has a snowflake id, which is sent in the `X-Scp-Delivery` header with every attempt so
+//! that endpoints can ignore retries of deliveries they have already handled.
+
+use std::{collections::HashSet, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use idgenerator::{IdGeneratorOptions, IdInstance};
+use serde::{Deserialize, Serialize};
+use service_config::{KeyLength, Loader};
+use sha2::Sha256;
+use tracing::warn;
+
+#[cfg(test)]
+mod tests;
+
+/// The header containing the timestamp and signature of a delivery.
+pub const SIGNATURE_HEADER: &str = "X-Scp-Signature";
+/// The header containing the name of the event being delivered.
+pub const EVENT_HEADER: &str = "X-Scp-Event";
+/// The header containing the id of the delivery, which is the same for every attempt.
+pub const DELIVERY_HEADER: &str = "X-Scp-Delivery";
+
+/// A platform event that can be delivered to webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// A user enrolled and downloaded their certificates.
+    Enrolment,
+    /// A flag was solved for the first time.
+    FirstBlood,
+    /// A flag was solved.
+    Solve,
+    /// A challenge and its services were created.
+    ServiceCreated,
+    /// A user's client certificate was revoked.
+    CertificateRevoked,
+}
+
+impl Event {
+    /// The name of the event, as used in configuration and the `X-Scp-Event` header.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::Enrolment => "enrolment",
+            Event::FirstBlood => "first_blood",
+            Event::Solve => "solve",
+            Event::ServiceCreated => "service_created",
+            Event::CertificateRevoked => "certificate_revoked",
+        }
+    }
+}
+
+/// The body of a webhook delivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payload {
+    pub event:     Event,
+    pub timestamp: DateTime<Utc>,
+    /// A human readable summary of the event, read by Discord.
+    pub content:   String,
+    /// The same summary, read by Slack.
+    pub text:      String,
+    /// Event specific details.
+    pub data:      serde_json::Value,
+}
+
+impl Payload {
+    /// Create a payload for an event with a human readable summary.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `data` cannot be serialised to JSON.
+    pub fn new<T: Serialize>(event: Event, summary: impl Into<String>, data: &T) -> Self {
+        let summary = summary.into();
+        Self {
+            event,
+            timestamp: Utc::now(),
+            content: summary.clone(),
+            text: summary,
+            data: serde_json::to_value(data).expect("webhook data must serialise"),
+        }
+    }
+}
+
+/// How webhooks are delivered.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The endpoints that events are posted to.
+    pub urls:         Vec<String>,
+    /// The key used to sign deliveries.
+    pub secret:       String,
+    /// The events that are delivered. Empty if every event is delivered.
+    pub events:       HashSet<Event>,
+    /// The number of times a delivery is attempted before giving up.
+    pub max_attempts: u32,
+    /// The delay before the first retry. Each further retry waits twice as long.
+    pub backoff:      Duration,
+}
+
+impl Config {
+    /// Read the configuration from the `WEBHOOK_URLS`, `WEBHOOK_SECRET`, `WEBHOOK_EVENTS`,
+    /// `WEBHOOK_MAX_ATTEMPTS` and `WEBHOOK_BACKOFF_MS` keys. URLs and events are comma separated.
+    /// The secret must be a key of at least 32 bytes if any URLs are set, so that deliveries
+    /// cannot be forged.
+    pub fn load(loader: &mut Loader) -> Self {
+        let urls = loader.list("WEBHOOK_URLS");
+        let secret = if urls.is_empty() {
+            loader.secret_or("WEBHOOK_SECRET", "")
+        } else {
+            loader.key("WEBHOOK_SECRET", KeyLength::AtLeast(32))
+        };
+        let events = loader.parse_with("WEBHOOK_EVENTS", "", |v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|e| !e.is_empty())
+                .map(|e| {
+                    serde_json::from_value(serde_json::Value::String(e.to_string()))
+                        .map_err(|_| format!("unknown webhook event {e}"))
+                })
+                .collect()
+        });
+
+        Self {
+            urls,
+            secret,
+            events,
+            max_attempts: loader.get("WEBHOOK_MAX_ATTEMPTS", 5),
+            backoff: Duration::from_millis(loader.get("WEBHOOK_BACKOFF_MS", 1000)),
+        }
+    }
+
+    /// Whether an event should be delivered.
+    #[must_use]
+    pub fn wants(&self, event: Event) -> bool {
+        !self.urls.is_empty() && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+/// The outcome of delivering a payload to a single endpoint.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    /// A snowflake id for the delivery, which services store it under.
+    pub id:           i64,
+    pub url:          String,
+    pub event:        Event,
+    /// The body that was sent.
+    pub body:         String,
+    pub attempts:     u32,
+    /// The status code of the last response, if any was received.
+    pub status_code:  Option<u16>,
+    pub success:      bool,
+    /// Why the last attempt failed.
+    pub error:        Option<String>,
+    pub started_at:   DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Generate the id of a delivery. The generator is shared with the rest of the service, which
+/// configures it the same way.
+fn next_id() -> i64 {
+    let generator_options = IdGeneratorOptions::new().worker_id(1).worker_id_bit_len(6);
+    IdInstance::init(generator_options).expect("the id generator options are valid");
+    IdInstance::next_id()
+}
+
+/// Sign a webhook body. Returns the value of the `X-Scp-Signature` header.
+///
+/// # Panics
+///
+/// Will panic if the HMAC cannot be constructed, which cannot happen for HMAC-SHA256.
+#[must_use]
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+/// Deliver a payload to every configured endpoint concurrently, retrying failed attempts with
+/// exponential backoff. Returns one [`Delivery`] per endpoint, or nothing if the event is not
+/// wanted.
+///
+/// # Panics
+///
+/// Will panic if the payload cannot be serialised to JSON.
+pub async fn deliver(config: &Config, payload: &Payload) -> Vec<Delivery> {
+    if !config.wants(payload.event) {
+        return vec![];
+    }
+
+    let body = serde_json::to_string(payload).expect("webhook payloads must serialise");
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+
+    join_all(
+        config
+            .urls
+            .iter()
+            .map(|url| deliver_to(config, &client, url, payload.event, body.clone())),
+    )
+    .await
+}
+
+async fn deliver_to(
+    config: &Config,
+    client: &reqwest::Client,
+    url: &str,
+    event: Event,
+    body: String,
+) -> Delivery {
+    let mut delivery = Delivery {
+        id: next_id(),
+        url: url.to_string(),
+        event,
+        body,
+        attempts: 0,
+        status_code: None,
+        success: false,
+        error: None,
+        started_at: Utc::now(),
+        completed_at: Utc::now(),
+    };
+
+    let mut backoff = config.backoff;
+    while delivery.attempts < config.max_attempts.max(1) {
+        if delivery.attempts > 0 {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        delivery.attempts += 1;
+
+        let result = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.as_str())
+            .header(DELIVERY_HEADER, delivery.id)
+            .header(
+                SIGNATURE_HEADER,
+                sign(&config.secret, Utc::now().timestamp(), &delivery.body),
+            )
+            .body(delivery.body.clone())
+            .send()
+            .await;
+
+        match result {
+            Ok(res) => {
+                delivery.status_code = Some(res.status().as_u16());
+                if res.status().is_success() {
+                    delivery.success = true;
+                    delivery.error = None;
+                    break;
+                }
+                delivery.error = Some(format!("endpoint responded with {}", res.status()));
+            },
+            Err(e) => {
+                delivery.status_code = None;
+                delivery.error = Some(e.to_string());
+            },
+        }
+    }
+
+    if !delivery.success {
+        warn!(
+            "giving up on {} webhook to {} after {} attempts",
+            event.as_str(),
+            url,
+            delivery.attempts
+        );
+    }
+
+    delivery.completed_at = Utc::now();
+    delivery
+}
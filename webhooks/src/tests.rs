@@ -0,0 +1,206 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use service_config::Loader;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use super::{deliver, sign, Config, Event, Payload, DELIVERY_HEADER, SIGNATURE_HEADER};
+
+/// A request received by the stand-in endpoint.
+struct Received {
+    headers: Vec<(String, String)>,
+    body:    String,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Start a local HTTP endpoint that answers with the given status codes in order, then with 200.
+async fn stand_in(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<Received>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(vec![]));
+    let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+
+    let log = received.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![];
+            let mut chunk = [0; 4096];
+
+            // Read until the headers and the full body have arrived
+            let request = loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&buf).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let headers: Vec<(String, String)> = head
+                        .lines()
+                        .skip(1)
+                        .filter_map(|l| l.split_once(':'))
+                        .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+                        .collect();
+                    let length = headers
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+                        .map_or(0, |(_, v)| v.parse::<usize>().unwrap());
+                    if body.len() >= length || n == 0 {
+                        break Received {
+                            headers,
+                            body: body.to_string(),
+                        };
+                    }
+                }
+            };
+            log.lock().unwrap().push(request);
+
+            let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+            let response =
+                format!("HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (url, received)
+}
+
+fn config(url: String) -> Config {
+    Config {
+        urls:         vec![url],
+        secret:       "secret".to_string(),
+        events:       HashSet::new(),
+        max_attempts: 3,
+        backoff:      Duration::from_millis(10),
+    }
+}
+
+#[tokio::test]
+async fn deliveries_are_signed() {
+    let (url, received) = stand_in(vec![]).await;
+    let payload = Payload::new(
+        Event::Solve,
+        "f1 was solved",
+        &serde_json::json!({"flag": "f1"}),
+    );
+
+    let deliveries = deliver(&config(url), &payload).await;
+    assert_eq!(deliveries.len(), 1);
+    assert!(deliveries[0].success);
+    assert_eq!(deliveries[0].attempts, 1);
+
+    let received = received.lock().unwrap();
+    let request = &received[0];
+    assert_eq!(request.header("X-Scp-Event"), Some("solve"));
+
+    let signature = request.header(SIGNATURE_HEADER).unwrap();
+    let timestamp: i64 = signature
+        .strip_prefix("t=")
+        .and_then(|s| s.split(',').next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(signature, sign("secret", timestamp, &request.body));
+
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["event"], "solve");
+    assert_eq!(body["content"], "f1 was solved");
+    assert_eq!(body["text"], "f1 was solved");
+    assert_eq!(body["data"]["flag"], "f1");
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried() {
+    let (url, received) = stand_in(vec![500, 502]).await;
+    let payload = Payload::new(Event::FirstBlood, "first blood", &());
+
+    let deliveries = deliver(&config(url), &payload).await;
+    assert!(deliveries[0].success);
+    assert_eq!(deliveries[0].attempts, 3);
+    assert_eq!(deliveries[0].status_code, Some(200));
+
+    // Every attempt carries the id of the delivery
+    let id = deliveries[0].id.to_string();
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 3);
+    assert!(received
+        .iter()
+        .all(|r| r.header(DELIVERY_HEADER) == Some(id.as_str())));
+}
+
+#[tokio::test]
+async fn deliveries_give_up_after_max_attempts() {
+    let (url, received) = stand_in(vec![503, 503, 503, 503]).await;
+    let payload = Payload::new(Event::ServiceCreated, "created", &());
+
+    let deliveries = deliver(&config(url), &payload).await;
+    assert!(!deliveries[0].success);
+    assert_eq!(deliveries[0].attempts, 3);
+    assert_eq!(deliveries[0].status_code, Some(503));
+    assert!(deliveries[0].error.is_some());
+    assert_eq!(received.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn unwanted_events_are_not_delivered() {
+    let (url, received) = stand_in(vec![]).await;
+    let mut config = config(url);
+    config.events = HashSet::from([Event::FirstBlood]);
+
+    let deliveries = deliver(&config, &Payload::new(Event::Solve, "solved", &())).await;
+    assert!(deliveries.is_empty());
+    assert!(received.lock().unwrap().is_empty());
+}
+
+/// Load the configuration from a set of variables, returning any problems with it.
+fn load(vars: &[(&'static str, &'static str)]) -> (Config, Vec<String>) {
+    let vars = vars.to_vec();
+    let mut loader = Loader::new(move |key| {
+        vars.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| (*v).to_string())
+    });
+    let config = Config::load(&mut loader);
+    (config, loader.errors())
+}
+
+#[test]
+fn secret_is_required_with_urls() {
+    let (config, errors) = load(&[]);
+    assert!(config.secret.is_empty());
+    assert!(errors.is_empty());
+
+    let (_, errors) = load(&[("WEBHOOK_URLS", "http://example.com")]);
+    assert!(errors.iter().any(|e| e.contains("WEBHOOK_SECRET")));
+
+    let (_, errors) = load(&[
+        ("WEBHOOK_URLS", "http://example.com"),
+        ("WEBHOOK_SECRET", "short"),
+    ]);
+    assert!(errors.iter().any(|e| e.contains("at least 32 bytes")));
+
+    let (_, errors) = load(&[
+        ("WEBHOOK_URLS", "http://example.com"),
+        ("WEBHOOK_SECRET", "abababababababababababababababab"),
+    ]);
+    assert!(errors.iter().any(|e| e.contains("repeating pattern")));
+
+    let (config, errors) = load(&[
+        ("WEBHOOK_URLS", "http://example.com"),
+        ("WEBHOOK_SECRET", "6f1c93a0d4b27e58c1a9f03d7b6e24c8"),
+    ]);
+    assert_eq!(config.secret, "6f1c93a0d4b27e58c1a9f03d7b6e24c8");
+    assert!(errors.is_empty(), "{errors:?}");
+}